@@ -0,0 +1,60 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use lox::compiler::interpreter::Backend;
+use lox::compiler::{ErrorReporter, Interpreter, LoxError, Parser, Resolver, Scanner};
+use std::cell::RefCell;
+use std::fs;
+use std::hint::black_box;
+use std::rc::Rc;
+
+struct PanicReporter;
+
+impl ErrorReporter for PanicReporter {
+    fn error(&mut self, line: usize, message: &str) {
+        panic!("[line {}] {}", line, message);
+    }
+
+    fn runtime_error(&mut self, error: &LoxError) {
+        panic!("{}", error);
+    }
+}
+
+// scan, parse, resolve and run, the same pipeline as `rlox script.lox`
+fn run(source: &str, backend: Backend) {
+    let mut reporter = PanicReporter;
+    let mut scanner = Scanner::new(source.to_string(), &mut reporter);
+    scanner.scan_tokens();
+    let statements = Parser::new(&scanner.tokens)
+        .parse()
+        .expect("fib.lox should parse");
+
+    let interpreter = Interpreter::new();
+    interpreter.set_backend(backend);
+    let interpreter = Rc::new(RefCell::new(interpreter));
+    let resolver = Resolver::new(interpreter.clone());
+    resolver.resolve_statements(&statements);
+
+    interpreter
+        .borrow_mut()
+        .interpret(statements)
+        .expect("fib.lox should run");
+}
+
+fn fib(c: &mut Criterion) {
+    let source = fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/lox_samples/fib.lox"))
+        .expect("lox_samples/fib.lox should exist");
+    // keep the calls but drop the output, which would otherwise swamp criterion's report
+    let source = source.replace("print fib(i);", "fib(i);");
+
+    let mut group = c.benchmark_group("lox_samples");
+    group.sample_size(20);
+    group.bench_function("fib.lox", |b| {
+        b.iter(|| run(black_box(&source), Backend::TreeWalk))
+    });
+    group.bench_function("fib.lox (vm)", |b| {
+        b.iter(|| run(black_box(&source), Backend::Bytecode))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, fib);
+criterion_main!(benches);
@@ -1,75 +1,265 @@
-// TODO: make this pretty printing visitor work
-// we can use the astvisitor to implement printing functionality
-/* pub trait ExprVisitor<T> {
-fn visit_binary(&self, binary: &Binary) -> T;
-fn visit_grouping(&self, grouping: &Grouping) -> T;
-fn visit_literal(&self, literal: &Literal) -> T;
-fn visit_unary(&self, unary: &Unary) -> T;
-} */
-
-use crate::compiler::expr::ExprVisitor;
-use crate::compiler::expr::{Binary, Grouping, Literal, Ternary, Unary, Variable};
+use crate::compiler::expr::{
+    Assign, Binary, Call, Expr, ExprVisitor, Get, Grouping, IndexGet, IndexSet, Lambda, List,
+    Literal, Logical, Map, Object, Set, Super, Ternary, This, Unary, Variable,
+};
+use crate::compiler::stmt::{
+    Block, Break, Class, Continue, Expression, Function, IfStmt, Print, ReturnStmt, Stmt,
+    StmtVisitor, Var, WhileStmt,
+};
 
+// Prints programs as S-expressions, one top-level statement after another, for
+// `rlox --dump-ast` and golden-file tests:
+//
+// (fun add (a b)
+//   (return (+ a b)))
+// (var total (call add 1 2.5))
+// (print (?: (> total 3) "big" "small"))
+//
+// Statements that contain other statements (blocks, if, while, functions and classes) put
+// each of those on its own line, indented two spaces. Expressions print on one line, apart
+// from lambdas, whose body statements are indented under the line the lambda starts on.
+// Literals print as Lox source would write them, so strings are quoted.
+//
+// A for loop shows up the way the parser desugars it: a block holding the initializer and
+// a while loop, whose increment is printed after the body as `(step ...)`.
 pub struct AstPrinter;
 
+impl AstPrinter {
+    /// Prints every statement of a program, each followed by a newline.
+    pub fn print_program(&self, statements: &[Stmt]) -> String {
+        statements
+            .iter()
+            .map(|statement| statement.accept(self) + "\n")
+            .collect()
+    }
+
+    pub fn print_expr(&self, expr: &Expr) -> String {
+        expr.accept(self)
+    }
+
+    // `(head parts...)` on one line
+    fn list(&self, head: &str, parts: &[String]) -> String {
+        let mut out = format!("({}", head);
+        for part in parts {
+            out.push(' ');
+            out.push_str(part);
+        }
+        out.push(')');
+        out
+    }
+
+    // `(head` followed by each child on its own indented line
+    fn nested(&self, head: &str, children: &[String]) -> String {
+        let mut out = format!("({}", head);
+        for child in children {
+            out.push_str("\n  ");
+            out.push_str(&child.replace('\n', "\n  "));
+        }
+        out.push(')');
+        out
+    }
+
+    // a function body's statements, without the block they're wrapped in
+    fn body(&self, body: &Stmt) -> Vec<String> {
+        match body {
+            Stmt::Block(block) => block.statements.iter().map(|s| s.accept(self)).collect(),
+            other => vec![other.accept(self)],
+        }
+    }
+
+    fn function(&self, keyword: &str, function: &Function) -> String {
+        let parameters: Vec<&str> = function
+            .parameters
+            .iter()
+            .map(|parameter| parameter.lexeme.as_str())
+            .collect();
+        let head = format!("{} ({})", keyword, parameters.join(" "));
+        self.nested(&head, &self.body(&function.body))
+    }
+}
+
 impl ExprVisitor<String> for AstPrinter {
-    fn visit_call(&self, call: &super::expr::Call) -> String {
-        //TODO: update with proper function calling logic
-        "".to_string()
+    fn visit_call(&self, call: &Call) -> String {
+        let mut parts = vec![call.callee.accept(self)];
+        parts.extend(call.args.iter().map(|arg| arg.accept(self)));
+        self.list("call", &parts)
+    }
+
+    fn visit_super(&self, sup: &Super) -> String {
+        format!("(super {})", sup.method.lexeme)
     }
 
-    fn visit_logical(&self, logical: &super::expr::Logical) -> String {
-        format!(
-            "({:?} {:?} {:?})",
-            logical.left.accept(self),
-            logical.operator.lexeme,
-            logical.right.accept(self)
+    fn visit_this(&self, _this: &This) -> String {
+        "this".to_string()
+    }
+
+    fn visit_list(&self, list: &List) -> String {
+        let elements: Vec<String> = list.elements.iter().map(|e| e.accept(self)).collect();
+        self.list("list", &elements)
+    }
+
+    fn visit_lambda(&self, lambda: &Lambda) -> String {
+        self.function("lambda", &lambda.declaration)
+    }
+
+    fn visit_map(&self, map: &Map) -> String {
+        let entries: Vec<String> = map
+            .entries
+            .iter()
+            .map(|(key, value)| format!("({} {})", key.accept(self), value.accept(self)))
+            .collect();
+        self.list("map", &entries)
+    }
+
+    fn visit_index_get(&self, index_get: &IndexGet) -> String {
+        self.list(
+            "index",
+            &[index_get.object.accept(self), index_get.index.accept(self)],
         )
     }
 
-    fn visit_assign(&self, assign: &super::expr::Assign) -> String {
-        format!(
-            "({:?} : {:?})",
-            assign.name.lexeme,
-            assign.value.accept(self)
+    fn visit_index_set(&self, index_set: &IndexSet) -> String {
+        self.list(
+            "index-set",
+            &[
+                index_set.object.accept(self),
+                index_set.index.accept(self),
+                index_set.value.accept(self),
+            ],
+        )
+    }
+
+    fn visit_get(&self, get: &Get) -> String {
+        self.list("get", &[get.object.accept(self), get.name.lexeme.clone()])
+    }
+
+    fn visit_set(&self, set: &Set) -> String {
+        self.list(
+            "set",
+            &[
+                set.object.accept(self),
+                set.name.lexeme.clone(),
+                set.value.accept(self),
+            ],
+        )
+    }
+
+    fn visit_logical(&self, logical: &Logical) -> String {
+        self.list(
+            &logical.operator.lexeme,
+            &[logical.left.accept(self), logical.right.accept(self)],
+        )
+    }
+
+    fn visit_assign(&self, assign: &Assign) -> String {
+        self.list(
+            "=",
+            &[assign.name.lexeme.clone(), assign.value.accept(self)],
         )
     }
 
     fn visit_binary(&self, binary: &Binary) -> String {
-        format!(
-            "({:?} {:?} {:?})",
-            binary.operator.lexeme,
-            binary.left.accept(self),
-            binary.right.accept(self)
+        self.list(
+            &binary.operator.lexeme,
+            &[binary.left.accept(self), binary.right.accept(self)],
         )
     }
 
     fn visit_grouping(&self, grouping: &Grouping) -> String {
-        format!("(group {:?})", grouping.expression.accept(self))
+        self.list("group", &[grouping.expression.accept(self)])
     }
 
     fn visit_literal(&self, literal: &Literal) -> String {
-        format!("{:?}", literal.value)
+        match &literal.value {
+            Object::String(s) => format!("{:?}", s.as_str()),
+            value => value.to_string(),
+        }
     }
 
     fn visit_unary(&self, unary: &Unary) -> String {
-        format!(
-            "({:?} {:?})",
-            unary.operator.lexeme,
-            unary.right.accept(self)
-        )
+        self.list(&unary.operator.lexeme, &[unary.right.accept(self)])
     }
 
-    fn visit_ternary(&self, _ternary: &Ternary) -> String {
-        format!(
-            "(ternary {:?} {:?} {:?})",
-            _ternary.condition.accept(self),
-            _ternary.true_branch.accept(self),
-            _ternary.false_branch.accept(self)
+    fn visit_ternary(&self, ternary: &Ternary) -> String {
+        self.list(
+            "?:",
+            &[
+                ternary.condition.accept(self),
+                ternary.true_branch.accept(self),
+                ternary.false_branch.accept(self),
+            ],
         )
     }
 
     fn visit_variable(&self, variable: &Variable) -> String {
-        format!("{:?}", variable.name)
+        variable.name.lexeme.clone()
+    }
+}
+
+impl StmtVisitor<String> for AstPrinter {
+    fn visit_expression(&self, expression: &Expression) -> String {
+        self.list("expr", &[expression.expression.accept(self)])
+    }
+
+    fn visit_print(&self, print: &Print) -> String {
+        self.list("print", &[print.expression.accept(self)])
+    }
+
+    fn visit_var(&self, var: &Var) -> String {
+        self.list(
+            "var",
+            &[var.name.lexeme.clone(), var.initializer.accept(self)],
+        )
+    }
+
+    fn visit_block(&self, block: &Block) -> String {
+        let statements: Vec<String> = block.statements.iter().map(|s| s.accept(self)).collect();
+        self.nested("block", &statements)
+    }
+
+    fn visit_if_stmt(&self, if_stmt: &IfStmt) -> String {
+        let head = format!("if {}", if_stmt.condition.accept(self));
+        let mut branches = vec![if_stmt.then_branch.accept(self)];
+        if let Some(else_branch) = &if_stmt.else_branch {
+            branches.push(else_branch.accept(self));
+        }
+        self.nested(&head, &branches)
+    }
+
+    fn visit_while_stmt(&self, while_stmt: &WhileStmt) -> String {
+        let head = format!("while {}", while_stmt.condition.accept(self));
+        let mut children = vec![while_stmt.body.accept(self)];
+        if let Some(increment) = &while_stmt.increment {
+            children.push(self.list("step", &[increment.accept(self)]));
+        }
+        self.nested(&head, &children)
+    }
+
+    fn visit_function(&self, function: &Function) -> String {
+        self.function(&format!("fun {}", function.name.lexeme), function)
+    }
+
+    fn visit_class(&self, class: &Class) -> String {
+        let mut head = format!("class {}", class.name.lexeme);
+        if let Some(superclass) = &class.superclass {
+            head = format!("{} (< {})", head, superclass.accept(self));
+        }
+        let methods: Vec<String> = class.methods.iter().map(|m| m.accept(self)).collect();
+        self.nested(&head, &methods)
+    }
+
+    fn visit_return_stmt(&self, return_stmt: &ReturnStmt) -> String {
+        match &return_stmt.value {
+            Some(value) => self.list("return", &[value.accept(self)]),
+            None => "(return)".to_string(),
+        }
+    }
+
+    fn visit_break(&self, _break_stmt: &Break) -> String {
+        "(break)".to_string()
+    }
+
+    fn visit_continue(&self, _continue_stmt: &Continue) -> String {
+        "(continue)".to_string()
     }
 }
@@ -1,10 +1,23 @@
 use crate::compiler::error::LoxError;
-use crate::compiler::expr::Object;
+use crate::compiler::expr::{LoxCallable, Object};
+use crate::compiler::token::Token;
+use std::rc::Rc;
 
 pub enum ControlFlow {
     None,
     Return(Object),
-    // can add more here as we go
+    Break,
+    Continue,
+    // a `return f(...);` in tail position, unwinding out of the function so the call is
+    // made by the caller's trampoline rather than on top of the current call
+    TailCall(PendingCall),
+}
+
+// a call whose callee and arguments have been evaluated and checked, ready to be made
+pub struct PendingCall {
+    pub callable: Rc<dyn LoxCallable>,
+    pub args: Vec<Object>,
+    pub call_site: Token,
 }
 
 pub type FlowResult<T> = Result<(T, ControlFlow), LoxError>;
@@ -18,6 +31,18 @@ pub fn return_value(value: Object) -> FlowResult<Object> {
     Ok((Object::Nil, ControlFlow::Return(value)))
 }
 
+pub fn tail_call(call: PendingCall) -> FlowResult<Object> {
+    Ok((Object::Nil, ControlFlow::TailCall(call)))
+}
+
+pub fn break_loop() -> FlowResult<Object> {
+    Ok((Object::Nil, ControlFlow::Break))
+}
+
+pub fn continue_loop() -> FlowResult<Object> {
+    Ok((Object::Nil, ControlFlow::Continue))
+}
+
 pub fn extract_value<T>(flow_res: FlowResult<T>) -> Result<T, LoxError> {
     flow_res.map(|(value, _)| value)
 }
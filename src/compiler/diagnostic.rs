@@ -0,0 +1,296 @@
+use crate::compiler::error::{LoxError, LoxErrorKind};
+use crate::compiler::token::Span;
+use std::fmt::Write;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const CYAN: &str = "\x1b[1;36m";
+
+// Turns a LoxError into a human readable report: a header with the message, the offending
+// source line with the token underlined, then any notes. For example:
+//
+// error[E0105]: Expect ';' after value.
+//  --> script.lox:1:9
+//   |
+// 1 | print 1 2;
+//   |         ^
+pub struct DiagnosticRenderer<'a> {
+    // the texts spans can point into, indexed by `Span::source`
+    sources: Vec<&'a str>,
+    // shown in the location line, usually the script path
+    name: &'a str,
+    color: bool,
+}
+
+impl<'a> DiagnosticRenderer<'a> {
+    pub fn new(source: &'a str, name: &'a str, color: bool) -> Self {
+        Self {
+            sources: vec![source],
+            name,
+            color,
+        }
+    }
+
+    /// For sessions made of several sources, like the inputs of a REPL. Errors that only
+    /// know their line are taken to come from the last one.
+    pub fn with_sources(sources: &'a [String], name: &'a str, color: bool) -> Self {
+        Self {
+            sources: sources.iter().map(String::as_str).collect(),
+            name,
+            color,
+        }
+    }
+
+    pub fn render(&self, error: &LoxError) -> String {
+        let (label, label_color) = match error.kind {
+            LoxErrorKind::Parse => ("error", RED),
+            LoxErrorKind::Runtime => ("runtime error", RED),
+            LoxErrorKind::Internal => ("internal error", RED),
+            LoxErrorKind::Warning => ("warning", YELLOW),
+        };
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{}{}",
+            self.paint(&format!("{}[{}]:", label, error.code), label_color),
+            self.paint(&format!(" {}", error.message), BOLD)
+        );
+
+        match (error.span(), error.line()) {
+            (Some(span), _) => self.snippet(&mut out, span, '^', label_color),
+            (None, Some(line)) => self.line_snippet(&mut out, line),
+            (None, None) => {}
+        }
+
+        for note in &error.notes {
+            match note.span {
+                Some(span) => {
+                    let _ = writeln!(
+                        out,
+                        "{}{}",
+                        self.paint("note:", CYAN),
+                        self.paint(&format!(" {}", note.message), BOLD)
+                    );
+                    self.snippet(&mut out, span, '-', CYAN);
+                }
+                None => {
+                    let _ = writeln!(out, "  {} {}", self.paint("= note:", CYAN), note.message);
+                }
+            }
+        }
+
+        if !error.trace.is_empty() {
+            let _ = writeln!(
+                out,
+                "{}",
+                self.paint("stack trace (innermost call first):", CYAN)
+            );
+            // runaway recursion leaves hundreds of identical frames, so print each run once
+            for run in error.trace.chunk_by(|a, b| a == b) {
+                let frame = &run[0];
+                let span = frame.call_site.span;
+                let location = if span == Span::default() {
+                    format!("{}:{}", self.name, frame.line)
+                } else {
+                    format!("{}:{}:{}", self.name, span.line, span.column)
+                };
+                let _ = writeln!(out, "  {} called at {}", frame.function, location);
+                if run.len() > 1 {
+                    let _ = writeln!(
+                        out,
+                        "  ... previous frame repeated {} more times",
+                        run.len() - 1
+                    );
+                }
+            }
+        }
+
+        out
+    }
+
+    // the source line containing `span` with the span underlined; spans running over
+    // several lines are only underlined up to the end of the first one
+    fn snippet(&self, out: &mut String, span: Span, marker: char, marker_color: &str) {
+        let source = self.sources.get(span.source).copied().unwrap_or("");
+        // scripts run from a .loxc file have no source to quote
+        if source.is_empty() {
+            self.location(out, span.line.to_string().len(), span.line, span.column);
+            return;
+        }
+        let start = span.start.min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |i| start + i);
+        let text = source[line_start..line_end].trim_end_matches('\r');
+
+        // keep tabs in the padding so the markers line up with the source
+        let padding: String = source[line_start..start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let end = span.end.clamp(start, line_end);
+        let width = source[start..end].chars().count().max(1);
+        let markers = marker.to_string().repeat(width);
+
+        let gutter = span.line.to_string().len();
+        self.location(out, gutter, span.line, span.column);
+        let _ = writeln!(out, "{} {}", " ".repeat(gutter), self.paint("|", BLUE));
+        let _ = writeln!(
+            out,
+            "{} {}",
+            self.paint(&format!("{} |", span.line), BLUE),
+            text
+        );
+        let _ = writeln!(
+            out,
+            "{} {} {}{}",
+            " ".repeat(gutter),
+            self.paint("|", BLUE),
+            padding,
+            self.paint(&markers, marker_color)
+        );
+    }
+
+    // used for scanner errors, which only know their line
+    fn line_snippet(&self, out: &mut String, line: usize) {
+        let gutter = line.to_string().len();
+        let _ = writeln!(
+            out,
+            "{}{} {}:{}",
+            " ".repeat(gutter),
+            self.paint("-->", BLUE),
+            self.name,
+            line
+        );
+        let source = self.sources.last().copied().unwrap_or("");
+        if let Some(text) = source.lines().nth(line.wrapping_sub(1)) {
+            let _ = writeln!(out, "{} {}", " ".repeat(gutter), self.paint("|", BLUE));
+            let _ = writeln!(out, "{} {}", self.paint(&format!("{} |", line), BLUE), text);
+        }
+    }
+
+    fn location(&self, out: &mut String, gutter: usize, line: usize, column: usize) {
+        let _ = writeln!(
+            out,
+            "{}{} {}:{}:{}",
+            " ".repeat(gutter),
+            self.paint("-->", BLUE),
+            self.name,
+            line,
+            column
+        );
+    }
+
+    fn paint(&self, text: &str, color: &str) -> String {
+        if self.color {
+            format!("{}{}{}", color, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+// How main reports diagnostics: rendered for people, or as JSON for tools
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFormat {
+    Human,
+    Json,
+}
+
+/// Serializes an error as a single-line JSON object. The field set and order are part of
+/// the documented output format (see README), so only ever add fields at the end:
+///
+/// `{"severity":"error","kind":"parse","code":"E0105","message":"...","file":"a.lox",
+///   "line":2,"column":9,"span":{"start":17,"end":18},"notes":[...],"trace":[...]}`
+///
+/// `line`, `column` and `span` are null when unknown; each note carries `message`,
+/// `line`, `column` and `span` the same way. `trace` lists the calls that were active when a
+/// runtime error was raised, innermost first, as `function`, `line`, `column` and `span`.
+pub fn to_json(error: &LoxError, file: &str) -> String {
+    let (severity, kind) = match error.kind {
+        LoxErrorKind::Parse => ("error", "parse"),
+        LoxErrorKind::Runtime => ("error", "runtime"),
+        LoxErrorKind::Internal => ("error", "internal"),
+        LoxErrorKind::Warning => ("warning", "warning"),
+    };
+    let span = error.span();
+
+    let notes: Vec<String> = error
+        .notes
+        .iter()
+        .map(|note| {
+            format!(
+                "{{\"message\":{},\"line\":{},\"column\":{},\"span\":{}}}",
+                json_string(&note.message),
+                json_number(note.span.map(|s| s.line)),
+                json_number(note.span.map(|s| s.column)),
+                json_span(note.span)
+            )
+        })
+        .collect();
+
+    let trace: Vec<String> = error
+        .trace
+        .iter()
+        .map(|frame| {
+            let span = Some(frame.call_site.span).filter(|span| *span != Span::default());
+            format!(
+                "{{\"function\":{},\"line\":{},\"column\":{},\"span\":{}}}",
+                json_string(&frame.function),
+                frame.line,
+                json_number(span.map(|s| s.column)),
+                json_span(span)
+            )
+        })
+        .collect();
+
+    format!(
+        "{{\"severity\":{},\"kind\":{},\"code\":{},\"message\":{},\"file\":{},\"line\":{},\"column\":{},\"span\":{},\"notes\":[{}],\"trace\":[{}]}}",
+        json_string(severity),
+        json_string(kind),
+        json_string(error.code.as_str()),
+        json_string(&error.message),
+        json_string(file),
+        json_number(span.map(|s| s.line).or(error.line())),
+        json_number(span.map(|s| s.column)),
+        json_span(span),
+        notes.join(","),
+        trace.join(",")
+    )
+}
+
+fn json_number(value: Option<usize>) -> String {
+    value.map_or_else(|| "null".to_string(), |n| n.to_string())
+}
+
+fn json_span(span: Option<Span>) -> String {
+    span.map_or_else(
+        || "null".to_string(),
+        |s| format!("{{\"start\":{},\"end\":{}}}", s.start, s.end),
+    )
+}
+
+fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -1,6 +1,9 @@
 use crate::compiler::Interpreter;
 use crate::compiler::error::{LoxError, Result};
+use crate::compiler::error_codes::ErrorCode;
 use crate::compiler::expr::Object;
+use crate::compiler::gc::{self, Trace, Tracer};
+use crate::compiler::lox_string::LoxString;
 use crate::compiler::token::Token;
 use std::cell::RefCell;
 use std::collections::HashMap;
@@ -9,10 +12,22 @@ use std::rc::Rc;
 
 pub type EnvRef = Rc<RefCell<Env>>;
 
+// Where the resolver found a local: how many envs out from the current one, and which
+// slot of that env holds it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Slot {
+    pub depth: usize,
+    pub index: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct Env {
     enclosing: Option<EnvRef>,
-    bindings: HashMap<String, Object>,
+    // locals, in the slots the resolver numbered them with (declaration order)
+    slots: Vec<Object>,
+    // globals are late bound and looked up by name, so only the outermost env uses this;
+    // names are interned in the same table as string values
+    bindings: HashMap<LoxString, Object>,
 }
 
 pub struct EnvGuard<'a> {
@@ -23,6 +38,7 @@ pub struct EnvGuard<'a> {
 impl<'a> EnvGuard<'a> {
     pub fn new(interpreter: &'a Interpreter, new_env: EnvRef) -> Self {
         // save old and replace with new given env
+        gc::push_active(new_env.clone());
         let previous = interpreter.env.replace(new_env);
         EnvGuard {
             interpreter,
@@ -34,6 +50,7 @@ impl<'a> EnvGuard<'a> {
 impl<'a> Drop for EnvGuard<'a> {
     fn drop(&mut self) {
         self.interpreter.env.replace(self.previous.clone());
+        gc::pop_active();
     }
 }
 
@@ -41,46 +58,52 @@ impl Env {
     pub fn new() -> Self {
         Env {
             enclosing: None,
+            slots: Vec::new(),
             bindings: HashMap::new(),
         }
     }
 
     pub fn new_global() -> EnvRef {
-        Rc::new(RefCell::new(Env {
+        gc::track_env(Rc::new(RefCell::new(Env {
             enclosing: None,
+            slots: Vec::new(),
             bindings: HashMap::new(),
-        }))
+        })))
     }
 
     pub fn new_enclosed(enclosing: EnvRef) -> EnvRef {
-        Rc::new(RefCell::new(Env {
+        gc::track_env(Rc::new(RefCell::new(Env {
             enclosing: Some(enclosing),
+            slots: Vec::new(),
             bindings: HashMap::new(),
-        }))
+        })))
     }
 
     pub fn enclose(&mut self, enclosing: Option<EnvRef>) {
         self.enclosing = enclosing;
     }
 
-    pub fn assign_at(&mut self, distance: usize, name: &Token, value: Object) -> Result<()> {
+    pub fn assign_at(&mut self, distance: usize, slot: usize, value: Object) -> Result<()> {
         if distance == 0 {
-            self.bindings.insert(name.lexeme.clone(), value);
-            return Ok(());
+            return match self.slots.get_mut(slot) {
+                Some(local) => {
+                    *local = value;
+                    Ok(())
+                }
+                None => Err(missing_slot(slot)),
+            };
         }
 
         self.ancestor(distance)?
             .borrow_mut()
-            .bindings
-            .insert(name.lexeme.clone(), value);
-        Ok(())
+            .assign_at(0, slot, value)
     }
 
     pub fn assign(&mut self, name: &Token, value: Object) -> Result<&Object> {
         // Check if the variable exists in the current environment
-        if self.bindings.contains_key(&name.lexeme) {
-            self.bindings.insert(name.lexeme.clone(), value);
-            return Ok(&self.bindings[&name.lexeme]);
+        if let Some(binding) = self.bindings.get_mut(name.lexeme.as_str()) {
+            *binding = value;
+            return Ok(&self.bindings[name.lexeme.as_str()]);
         }
 
         // If not in current environment, check in enclosing environments
@@ -89,17 +112,43 @@ impl Env {
                 enclosed.borrow_mut().assign(name, value)?;
                 // Return a reference to the value in this environment
                 // (this is slightly inconsistent since the value is actually in the parent)
-                Ok(&self.bindings.get(&name.lexeme).unwrap_or(&Object::Nil))
+                Ok(self
+                    .bindings
+                    .get(name.lexeme.as_str())
+                    .unwrap_or(&Object::Nil))
             }
             None => Err(LoxError::new_runtime(
                 name.clone(),
                 &format!("Undefined variable '{}' during assign.", name.lexeme),
-            )),
+            )
+            .with_code(ErrorCode::UndefinedVariable)),
         }
     }
 
-    pub fn define(&mut self, name: String, value: Object) {
-        self.bindings.insert(name, value);
+    // By-name access to globals for the bytecode VM. It only has a name where the
+    // tree-walker has a token, so building the "Undefined variable" error is left to it.
+    pub fn lookup(&self, name: &str) -> Option<Object> {
+        self.bindings.get(name).cloned()
+    }
+
+    pub fn assign_global(&mut self, name: &str, value: Object) -> bool {
+        match self.bindings.get_mut(name) {
+            Some(binding) => {
+                *binding = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Locals go in the next free slot, matching the order the resolver numbered them in;
+    // the name is only kept for globals.
+    pub fn define(&mut self, name: &str, value: Object) {
+        if self.enclosing.is_some() {
+            self.slots.push(value);
+        } else {
+            self.bindings.insert(LoxString::new(name), value);
+        }
     }
 
     pub fn ancestor(&self, distance: usize) -> Result<EnvRef> {
@@ -112,25 +161,19 @@ impl Env {
         Ok(current)
     }
 
-    pub fn get_at(&self, distance: usize, name: &str) -> Result<Object> {
+    pub fn get_at(&self, distance: usize, slot: usize) -> Result<Object> {
         if distance == 0 {
-            return Ok(self
-                .bindings
-                .get(name)
-                .ok_or_else(|| LoxError::new_internal(&format!("undefined var: {}", name)))?
-                .clone());
+            return self
+                .slots
+                .get(slot)
+                .cloned()
+                .ok_or_else(|| missing_slot(slot));
         }
 
-        return Ok(self
-            .ancestor(distance)?
-            .borrow()
-            .bindings
-            .get(name)
-            .ok_or_else(|| LoxError::new_internal(&format!("undefined var: {}", name)))?
-            .clone());
+        self.ancestor(distance)?.borrow().get_at(0, slot)
     }
 
-    pub fn get(&self, name: &String, token: &Token) -> Result<Object> {
+    pub fn get(&self, name: &str, token: &Token) -> Result<Object> {
         match self.bindings.get(name) {
             Some(value) => Ok(value.clone()),
             None => {
@@ -140,9 +183,26 @@ impl Env {
                     None => Err(LoxError::new_runtime(
                         token.clone(),
                         &format!("Undefined variable '{}' during get.", name),
-                    )),
+                    )
+                    .with_code(ErrorCode::UndefinedVariable)),
                 }
             }
         }
     }
 }
+
+impl Trace for Env {
+    fn trace(&self, tracer: &mut Tracer) {
+        if let Some(enclosing) = &self.enclosing {
+            tracer.edge(enclosing);
+        }
+        for value in self.slots.iter().chain(self.bindings.values()) {
+            tracer.object(value);
+        }
+    }
+}
+
+// the resolver and interpreter disagree about a scope's layout
+fn missing_slot(slot: usize) -> LoxError {
+    LoxError::new_internal(&format!("no local in slot {}", slot))
+}
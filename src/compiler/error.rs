@@ -1,4 +1,5 @@
-use crate::compiler::token::{Token, TokenType};
+use crate::compiler::error_codes::ErrorCode;
+use crate::compiler::token::{Span, Token, TokenType};
 use std::fmt;
 
 // Define different error types in our interpreter
@@ -13,12 +14,36 @@ pub enum LoxErrorKind {
     Warning,
 }
 
+// Secondary information attached to an error, optionally pointing somewhere else in the
+// source (e.g. where a clashing variable was first declared)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Note {
+    pub message: String,
+    pub span: Option<Span>,
+}
+
+// One call that was active when a runtime error was raised: what was called and the
+// token of the call expression that called it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackFrame {
+    pub function: String,
+    pub call_site: Token,
+    pub line: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct LoxError {
     pub kind: LoxErrorKind,
     pub message: String,
-    pub token: Option<Token>,
+    // boxed so Result<_, LoxError> stays small on the happy path
+    pub token: Option<Box<Token>>,
     pub line: Option<usize>,
+    pub notes: Vec<Note>,
+    // stable identifier for the diagnostic; constructors start from the generic code for
+    // the kind and call sites narrow it down with `with_code`
+    pub code: ErrorCode,
+    // active calls at the point a runtime error was raised, innermost first
+    pub trace: Vec<StackFrame>,
 }
 
 impl LoxError {
@@ -26,8 +51,11 @@ impl LoxError {
         LoxError {
             kind: LoxErrorKind::Runtime,
             message: message.to_string(),
-            token: Some(token),
+            token: Some(Box::new(token)),
             line: None,
+            notes: Vec::new(),
+            code: ErrorCode::RuntimeError,
+            trace: Vec::new(),
         }
     }
 
@@ -35,8 +63,11 @@ impl LoxError {
         LoxError {
             kind: LoxErrorKind::Parse,
             message: message.to_string(),
-            token: Some(token),
+            token: Some(Box::new(token)),
             line: None,
+            notes: Vec::new(),
+            code: ErrorCode::SyntaxError,
+            trace: Vec::new(),
         }
     }
 
@@ -46,6 +77,9 @@ impl LoxError {
             message: message.to_string(),
             token: None,
             line: Some(line),
+            notes: Vec::new(),
+            code: ErrorCode::SyntaxError,
+            trace: Vec::new(),
         }
     }
 
@@ -55,6 +89,9 @@ impl LoxError {
             message: message.to_string(),
             token: None,
             line: None,
+            notes: Vec::new(),
+            code: ErrorCode::InternalError,
+            trace: Vec::new(),
         }
     }
 
@@ -64,6 +101,45 @@ impl LoxError {
             message: message.to_string(),
             token: None,
             line: None,
+            notes: Vec::new(),
+            code: ErrorCode::Warning,
+            trace: Vec::new(),
+        }
+    }
+
+    pub fn new_warning_at(token: Token, message: &str) -> Self {
+        LoxError {
+            token: Some(Box::new(token)),
+            ..Self::new_warning(message)
+        }
+    }
+
+    pub fn with_code(mut self, code: ErrorCode) -> Self {
+        self.code = code;
+        self
+    }
+
+    pub fn with_note(mut self, message: &str, span: Option<Span>) -> Self {
+        self.notes.push(Note {
+            message: message.to_string(),
+            span,
+        });
+        self
+    }
+
+    /// Where in the source the error points, if it came from a scanned token.
+    pub fn span(&self) -> Option<Span> {
+        self.token
+            .as_ref()
+            .map(|token| token.span)
+            .filter(|span| *span != Span::default())
+    }
+
+    /// The line the error was reported on, whether it has a token or just a line number.
+    pub fn line(&self) -> Option<usize> {
+        match &self.token {
+            Some(token) if token.line > 0 => Some(token.line),
+            _ => self.line,
         }
     }
 }
@@ -113,6 +189,12 @@ impl fmt::Display for LoxError {
 pub trait ErrorReporter {
     fn error(&mut self, line: usize, message: &str);
     fn runtime_error(&mut self, error: &LoxError);
+
+    // scanner errors report their code through here; reporters that don't care about
+    // codes can rely on the default
+    fn error_with_code(&mut self, line: usize, _code: ErrorCode, message: &str) {
+        self.error(line, message);
+    }
 }
 
 // Define a type alias for our result type that uses LoxError
@@ -0,0 +1,391 @@
+use std::fmt;
+use std::str::FromStr;
+
+// Every diagnostic carries one of these codes. They are part of the public interface (JSON
+// output, `--explain`), so once published a code must keep its number and meaning; retire
+// codes rather than reusing them. Numbering:
+//   E01xx syntax errors from the scanner and parser
+//   E02xx static errors found by the resolver
+//   E03xx runtime errors
+//   E04xx errors loading precompiled .loxc files
+//   E09xx internal errors
+//   W02xx resolver warnings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    SyntaxError,
+    UnexpectedCharacter,
+    UnterminatedString,
+    InvalidEscape,
+    UnterminatedComment,
+    ExpectedToken,
+    ExpectedExpression,
+    InvalidAssignmentTarget,
+    TooManyParameters,
+    InvalidLiteral,
+
+    SelfReferencingInitializer,
+    DuplicateDeclaration,
+    TopLevelReturn,
+    ReturnFromInitializer,
+    ThisOutsideClass,
+    SuperOutsideClass,
+    SuperWithoutSuperclass,
+    InheritFromSelf,
+    LoopControlOutsideLoop,
+
+    RuntimeError,
+    UndefinedVariable,
+    UndefinedProperty,
+    OperandType,
+    NotCallable,
+    ArityMismatch,
+    PropertyOnNonInstance,
+    SuperclassNotClass,
+    InvalidIndex,
+    UndefinedKey,
+    NotIndexable,
+    InvalidMapKey,
+    NativeArgumentType,
+    DivisionByZero,
+    StackOverflow,
+
+    InvalidBytecode,
+    BytecodeVersion,
+
+    InternalError,
+
+    Warning,
+    UnusedVariable,
+}
+
+impl ErrorCode {
+    pub const ALL: &'static [ErrorCode] = &[
+        ErrorCode::SyntaxError,
+        ErrorCode::UnexpectedCharacter,
+        ErrorCode::UnterminatedString,
+        ErrorCode::InvalidEscape,
+        ErrorCode::UnterminatedComment,
+        ErrorCode::ExpectedToken,
+        ErrorCode::ExpectedExpression,
+        ErrorCode::InvalidAssignmentTarget,
+        ErrorCode::TooManyParameters,
+        ErrorCode::InvalidLiteral,
+        ErrorCode::SelfReferencingInitializer,
+        ErrorCode::DuplicateDeclaration,
+        ErrorCode::TopLevelReturn,
+        ErrorCode::ReturnFromInitializer,
+        ErrorCode::ThisOutsideClass,
+        ErrorCode::SuperOutsideClass,
+        ErrorCode::SuperWithoutSuperclass,
+        ErrorCode::InheritFromSelf,
+        ErrorCode::LoopControlOutsideLoop,
+        ErrorCode::RuntimeError,
+        ErrorCode::UndefinedVariable,
+        ErrorCode::UndefinedProperty,
+        ErrorCode::OperandType,
+        ErrorCode::NotCallable,
+        ErrorCode::ArityMismatch,
+        ErrorCode::PropertyOnNonInstance,
+        ErrorCode::SuperclassNotClass,
+        ErrorCode::InvalidIndex,
+        ErrorCode::UndefinedKey,
+        ErrorCode::NotIndexable,
+        ErrorCode::InvalidMapKey,
+        ErrorCode::NativeArgumentType,
+        ErrorCode::DivisionByZero,
+        ErrorCode::StackOverflow,
+        ErrorCode::InvalidBytecode,
+        ErrorCode::BytecodeVersion,
+        ErrorCode::InternalError,
+        ErrorCode::Warning,
+        ErrorCode::UnusedVariable,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::SyntaxError => "E0100",
+            ErrorCode::UnexpectedCharacter => "E0101",
+            ErrorCode::UnterminatedString => "E0102",
+            ErrorCode::InvalidEscape => "E0103",
+            ErrorCode::UnterminatedComment => "E0104",
+            ErrorCode::ExpectedToken => "E0105",
+            ErrorCode::ExpectedExpression => "E0106",
+            ErrorCode::InvalidAssignmentTarget => "E0107",
+            ErrorCode::TooManyParameters => "E0108",
+            ErrorCode::InvalidLiteral => "E0109",
+            ErrorCode::SelfReferencingInitializer => "E0201",
+            ErrorCode::DuplicateDeclaration => "E0202",
+            ErrorCode::TopLevelReturn => "E0203",
+            ErrorCode::ReturnFromInitializer => "E0204",
+            ErrorCode::ThisOutsideClass => "E0205",
+            ErrorCode::SuperOutsideClass => "E0206",
+            ErrorCode::SuperWithoutSuperclass => "E0207",
+            ErrorCode::InheritFromSelf => "E0208",
+            ErrorCode::LoopControlOutsideLoop => "E0209",
+            ErrorCode::RuntimeError => "E0300",
+            ErrorCode::UndefinedVariable => "E0301",
+            ErrorCode::UndefinedProperty => "E0302",
+            ErrorCode::OperandType => "E0303",
+            ErrorCode::NotCallable => "E0304",
+            ErrorCode::ArityMismatch => "E0305",
+            ErrorCode::PropertyOnNonInstance => "E0306",
+            ErrorCode::SuperclassNotClass => "E0307",
+            ErrorCode::InvalidIndex => "E0308",
+            ErrorCode::UndefinedKey => "E0309",
+            ErrorCode::NotIndexable => "E0310",
+            ErrorCode::InvalidMapKey => "E0311",
+            ErrorCode::NativeArgumentType => "E0312",
+            ErrorCode::DivisionByZero => "E0313",
+            ErrorCode::StackOverflow => "E0314",
+            ErrorCode::InvalidBytecode => "E0401",
+            ErrorCode::BytecodeVersion => "E0402",
+            ErrorCode::InternalError => "E0900",
+            ErrorCode::Warning => "W0200",
+            ErrorCode::UnusedVariable => "W0201",
+        }
+    }
+
+    // one line summary, shown as the heading of `--explain`
+    pub fn title(&self) -> &'static str {
+        match self {
+            ErrorCode::SyntaxError => "Syntax error",
+            ErrorCode::UnexpectedCharacter => "Unexpected character",
+            ErrorCode::UnterminatedString => "Unterminated string",
+            ErrorCode::InvalidEscape => "Invalid escape sequence",
+            ErrorCode::UnterminatedComment => "Unterminated block comment",
+            ErrorCode::ExpectedToken => "Expected a specific token",
+            ErrorCode::ExpectedExpression => "Expected an expression",
+            ErrorCode::InvalidAssignmentTarget => "Invalid assignment target",
+            ErrorCode::TooManyParameters => "Too many parameters",
+            ErrorCode::InvalidLiteral => "Invalid literal",
+            ErrorCode::SelfReferencingInitializer => "Variable read in its own initializer",
+            ErrorCode::DuplicateDeclaration => "Variable declared twice in the same scope",
+            ErrorCode::TopLevelReturn => "Return outside of a function",
+            ErrorCode::ReturnFromInitializer => "Value returned from an initializer",
+            ErrorCode::ThisOutsideClass => "'this' used outside of a class",
+            ErrorCode::SuperOutsideClass => "'super' used outside of a class",
+            ErrorCode::SuperWithoutSuperclass => "'super' used in a class with no superclass",
+            ErrorCode::InheritFromSelf => "Class inherits from itself",
+            ErrorCode::LoopControlOutsideLoop => "'break' or 'continue' outside of a loop",
+            ErrorCode::RuntimeError => "Runtime error",
+            ErrorCode::UndefinedVariable => "Undefined variable",
+            ErrorCode::UndefinedProperty => "Undefined property",
+            ErrorCode::OperandType => "Operand has the wrong type",
+            ErrorCode::NotCallable => "Called a value that isn't a function or class",
+            ErrorCode::ArityMismatch => "Wrong number of arguments",
+            ErrorCode::PropertyOnNonInstance => "Property access on a non-instance",
+            ErrorCode::SuperclassNotClass => "Superclass isn't a class",
+            ErrorCode::InvalidIndex => "Invalid list index",
+            ErrorCode::UndefinedKey => "Map key not found",
+            ErrorCode::NotIndexable => "Indexed a value that isn't a list or map",
+            ErrorCode::InvalidMapKey => "Invalid map key type",
+            ErrorCode::NativeArgumentType => "Built-in function called with the wrong type",
+            ErrorCode::DivisionByZero => "Division by zero",
+            ErrorCode::StackOverflow => "Maximum call depth exceeded",
+            ErrorCode::InvalidBytecode => "Invalid compiled bytecode file",
+            ErrorCode::BytecodeVersion => "Compiled file from a different version",
+            ErrorCode::InternalError => "Internal interpreter error",
+            ErrorCode::Warning => "Warning",
+            ErrorCode::UnusedVariable => "Unused local variable",
+        }
+    }
+
+    // longer description with an example, printed by `--explain`
+    pub fn explanation(&self) -> &'static str {
+        match self {
+            ErrorCode::SyntaxError => {
+                "The source could not be parsed. This is the general code for syntax errors that \
+                 have no more specific code."
+            }
+            ErrorCode::UnexpectedCharacter => {
+                "The scanner found a character that can't start any token.\n\n\
+                 Erroneous example:\n\n    var price = 10 @ 2;\n\n\
+                 Only ASCII letters, digits and Lox's operators and punctuation may appear \
+                 outside of strings and comments."
+            }
+            ErrorCode::UnterminatedString => {
+                "A string literal was opened but the file ended before its closing quote.\n\n\
+                 Erroneous example:\n\n    print \"hello;\n\n\
+                 Add the missing '\"'. Strings may span several lines."
+            }
+            ErrorCode::InvalidEscape => {
+                "A string contains a backslash followed by a character that isn't a known \
+                 escape.\n\nErroneous example:\n\n    print \"C:\\windows\";\n\n\
+                 The supported escapes are \\\\, \\\", \\n, \\r and \\t."
+            }
+            ErrorCode::UnterminatedComment => {
+                "A '/*' block comment was never closed with '*/'. Block comments nest, so every \
+                 '/*' inside one needs its own '*/'.\n\nErroneous example:\n\n    \
+                 /* outer /* inner */\n    print 1;"
+            }
+            ErrorCode::ExpectedToken => {
+                "The parser needed a particular token, such as ';' or ')', and found something \
+                 else. The message says which token was expected.\n\n\
+                 Erroneous example:\n\n    print 1\n    print 2;\n\n\
+                 Statements end with ';'."
+            }
+            ErrorCode::ExpectedExpression => {
+                "An expression was required here but the next token can't start one.\n\n\
+                 Erroneous example:\n\n    var x = ;\n\n\
+                 Either supply a value or leave out the '=' to initialize the variable to nil."
+            }
+            ErrorCode::InvalidAssignmentTarget => {
+                "The left-hand side of '=' must be a variable, a property (`obj.field`) or an \
+                 index (`list[0]`).\n\nErroneous example:\n\n    1 + 2 = 3;"
+            }
+            ErrorCode::TooManyParameters => {
+                "Functions can take at most 255 parameters.\n\n\
+                 Group related values into a list, map or instance instead."
+            }
+            ErrorCode::InvalidLiteral => {
+                "A number or string token had no usable value. This usually means the scanner \
+                 and parser disagree and should be reported as a bug."
+            }
+            ErrorCode::SelfReferencingInitializer => {
+                "A local variable was read inside its own initializer, before it has a value.\n\n\
+                 Erroneous example:\n\n    {\n      var a = a + 1;\n    }\n\n\
+                 If you meant an outer variable with the same name, rename the new one."
+            }
+            ErrorCode::DuplicateDeclaration => {
+                "Two local variables with the same name were declared in the same scope.\n\n\
+                 Erroneous example:\n\n    fun f() {\n      var a = 1;\n      var a = 2;\n    }\n\n\
+                 Assign to the existing variable instead (`a = 2;`) or pick a new name. \
+                 Redeclaring globals is allowed."
+            }
+            ErrorCode::TopLevelReturn => {
+                "'return' can only be used inside a function or method.\n\n\
+                 Erroneous example:\n\n    return 1;"
+            }
+            ErrorCode::ReturnFromInitializer => {
+                "An 'init' method always returns the new instance, so it can't return a value. \
+                 A bare 'return;' is allowed.\n\nErroneous example:\n\n    \
+                 class Point {\n      init() { return 1; }\n    }"
+            }
+            ErrorCode::ThisOutsideClass => {
+                "'this' refers to the instance a method was called on, so it only makes sense \
+                 inside a method.\n\nErroneous example:\n\n    fun f() { print this; }"
+            }
+            ErrorCode::SuperOutsideClass => {
+                "'super' looks up methods on the superclass of the enclosing class, so it can \
+                 only be used inside a method.\n\nErroneous example:\n\n    super.init();"
+            }
+            ErrorCode::SuperWithoutSuperclass => {
+                "'super' was used in a class that doesn't inherit from anything.\n\n\
+                 Erroneous example:\n\n    class A {\n      f() { super.f(); }\n    }\n\n\
+                 Declare a superclass with `class A < Base { ... }`."
+            }
+            ErrorCode::InheritFromSelf => {
+                "A class named itself as its own superclass.\n\n\
+                 Erroneous example:\n\n    class A < A {}"
+            }
+            ErrorCode::LoopControlOutsideLoop => {
+                "'break' and 'continue' must appear inside a 'while' or 'for' loop. A function \
+                 body starts a new context, so they can't jump out of a loop surrounding the \
+                 function either.\n\nErroneous example:\n\n    if (true) break;"
+            }
+            ErrorCode::RuntimeError => "A general runtime error with no more specific code.",
+            ErrorCode::UndefinedVariable => {
+                "A variable was read or assigned before being declared.\n\n\
+                 Erroneous example:\n\n    print total;\n    var total = 1;\n\n\
+                 Declare variables with 'var' before using them."
+            }
+            ErrorCode::UndefinedProperty => {
+                "An instance has no field or method with this name.\n\n\
+                 Erroneous example:\n\n    class A {}\n    print A().missing;"
+            }
+            ErrorCode::OperandType => {
+                "An operator was applied to values of the wrong type. Arithmetic and comparison \
+                 need numbers; '+' also accepts two strings.\n\n\
+                 Erroneous example:\n\n    print \"a\" - 1;"
+            }
+            ErrorCode::NotCallable => {
+                "Only functions and classes can be called.\n\n\
+                 Erroneous example:\n\n    var x = 1;\n    x();"
+            }
+            ErrorCode::ArityMismatch => {
+                "A function was called with a different number of arguments than it declares \
+                 parameters.\n\nErroneous example:\n\n    fun add(a, b) { return a + b; }\n    \
+                 add(1);"
+            }
+            ErrorCode::PropertyOnNonInstance => {
+                "Properties can only be read from or written to class instances.\n\n\
+                 Erroneous example:\n\n    var n = 1;\n    n.field = 2;"
+            }
+            ErrorCode::SuperclassNotClass => {
+                "The value after '<' in a class declaration must be a class.\n\n\
+                 Erroneous example:\n\n    var NotAClass = \"text\";\n    class A < NotAClass {}"
+            }
+            ErrorCode::InvalidIndex => {
+                "A list index must be a whole number between 0 and the list's length minus one \
+                 (or up to the length for insert()). This also covers popping an empty list.\n\n\
+                 Erroneous example:\n\n    var l = [1, 2];\n    print l[2];"
+            }
+            ErrorCode::UndefinedKey => {
+                "A map was indexed with a key it doesn't contain. Use has() to check first.\n\n\
+                 Erroneous example:\n\n    var m = {\"a\": 1};\n    print m[\"b\"];"
+            }
+            ErrorCode::NotIndexable => {
+                "Only lists and maps support '[...]'.\n\n\
+                 Erroneous example:\n\n    var s = 1;\n    print s[0];"
+            }
+            ErrorCode::InvalidMapKey => {
+                "Map keys must be booleans, numbers (other than NaN) or strings.\n\n\
+                 Erroneous example:\n\n    var m = {};\n    m[nil] = 1;"
+            }
+            ErrorCode::NativeArgumentType => {
+                "A built-in function was given an argument of the wrong type. The message says \
+                 what it expects.\n\nErroneous example:\n\n    push(\"text\", 1);"
+            }
+            ErrorCode::DivisionByZero => {
+                "A number was divided by zero.\n\nErroneous example:\n\n    print 1 / 0;"
+            }
+            ErrorCode::StackOverflow => {
+                "Too many function calls were active at once, usually because of recursion \
+                 that never reaches its base case.\n\nErroneous example:\n\n    \
+                 fun f() {\n      f();\n    }\n    f();\n\n\
+                 The limit defaults to 1000 nested calls and can be changed with \
+                 `--max-call-depth`."
+            }
+            ErrorCode::InvalidBytecode => {
+                "A .loxc file couldn't be loaded because it isn't valid compiled Lox: it may be \
+                 truncated, corrupted or not a .loxc file at all. Nothing in it was run.\n\n\
+                 Recompile it from the source with `rlox compile script.lox`."
+            }
+            ErrorCode::BytecodeVersion => {
+                "A .loxc file was compiled by a version of the interpreter that used a \
+                 different bytecode format.\n\n\
+                 Recompile it from the source with `rlox compile script.lox`."
+            }
+            ErrorCode::InternalError => {
+                "Something went wrong inside the interpreter itself. Please report it along \
+                 with the script that triggered it."
+            }
+            ErrorCode::Warning => "A general warning with no more specific code.",
+            ErrorCode::UnusedVariable => {
+                "A local variable is never read after being declared. It may be left over from \
+                 an edit, or a typo in a later use.\n\n\
+                 Example:\n\n    fun f() {\n      var unused = 1;\n    }\n\n\
+                 Remove the variable or use it. Globals are not checked."
+            }
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for ErrorCode {
+    type Err = ();
+
+    // accepts codes case-insensitively, e.g. "e0301"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ErrorCode::ALL
+            .iter()
+            .copied()
+            .find(|code| code.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or(())
+    }
+}
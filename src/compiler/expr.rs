@@ -1,22 +1,34 @@
 use crate::compiler::Result;
+use crate::compiler::control_flow::{FlowResult, ok};
+use crate::compiler::gc::Tracer;
 use crate::compiler::interpreter::Interpreter;
 use crate::compiler::lox_class::LoxClass;
 use crate::compiler::lox_instance::LoxInstance;
-use crate::compiler::token::Token;
+use crate::compiler::lox_map::LoxMap;
+use crate::compiler::lox_string::LoxString;
+use crate::compiler::stmt::Function;
+use crate::compiler::token::{Span, Token};
+use std::any::Any;
+use std::cell::RefCell;
 use std::fmt;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 // Define Object type to represent Lox values
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub enum Object {
     Nil,
     Boolean(bool),
     Number(f64),
-    String(String),
+    // interned, so copies are cheap and equal strings compare by pointer
+    String(LoxString),
     Error(String),
     Function(Rc<dyn LoxCallable>),
     Class(Rc<LoxClass>),
     Instance(Rc<LoxInstance>),
+    // lists are shared and mutable, so copies alias the same storage
+    List(Rc<RefCell<Vec<Object>>>),
+    Map(Rc<RefCell<LoxMap>>),
 }
 
 impl PartialEq for Object {
@@ -31,11 +43,102 @@ impl PartialEq for Object {
             (Object::Function(a), Object::Function(b)) => Rc::ptr_eq(a, b),
             (Object::Class(a), Object::Class(b)) => Rc::ptr_eq(a, b),
             (Object::Instance(a), Object::Instance(b)) => Rc::ptr_eq(a, b),
+            (Object::List(a), Object::List(b)) => Rc::ptr_eq(a, b),
+            (Object::Map(a), Object::Map(b)) => Rc::ptr_eq(a, b),
             _ => false,
         }
     }
 }
 
+thread_local! {
+    // addresses of the lists and maps being printed right now, innermost last
+    static PRINTING: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+}
+
+// Lists and maps can contain themselves, so printing one that's already being printed
+// further up writes `cycle` instead of recursing forever: `var a = [1]; push(a, a);`
+// prints as `[1, [...]]`.
+fn print_once<T: ?Sized>(
+    rc: &Rc<T>,
+    f: &mut fmt::Formatter<'_>,
+    cycle: &str,
+    print: impl FnOnce(&mut fmt::Formatter<'_>) -> fmt::Result,
+) -> fmt::Result {
+    let address = Rc::as_ptr(rc) as *const () as usize;
+    if PRINTING.with(|printing| printing.borrow().contains(&address)) {
+        return f.write_str(cycle);
+    }
+    PRINTING.with(|printing| printing.borrow_mut().push(address));
+    let result = print(f);
+    PRINTING.with(|printing| printing.borrow_mut().pop());
+    result
+}
+
+// `[a, b]`, with the elements printed by Debug or Display to match the list itself
+fn write_list(
+    f: &mut fmt::Formatter<'_>,
+    list: &Rc<RefCell<Vec<Object>>>,
+    debug: bool,
+) -> fmt::Result {
+    print_once(list, f, "[...]", |f| {
+        write!(f, "[")?;
+        for (i, element) in list.borrow().iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            match debug {
+                true => write!(f, "{:?}", element)?,
+                false => write!(f, "{}", element)?,
+            }
+        }
+        write!(f, "]")
+    })
+}
+
+// `{k: v}`, printed the same way as write_list
+fn write_map(f: &mut fmt::Formatter<'_>, map: &Rc<RefCell<LoxMap>>, debug: bool) -> fmt::Result {
+    print_once(map, f, "{...}", |f| {
+        write!(f, "{{")?;
+        for (i, (key, value)) in map.borrow().iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            match debug {
+                true => write!(f, "{:?}: {:?}", key.to_object(), value)?,
+                false => write!(f, "{}: {}", key.to_object(), value)?,
+            }
+        }
+        write!(f, "}}")
+    })
+}
+
+// written by hand so lists and maps print their contents rather than the RefCell and
+// lookup index around them
+impl fmt::Debug for Object {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Object::Nil => write!(f, "Nil"),
+            Object::Boolean(b) => f.debug_tuple("Boolean").field(b).finish(),
+            Object::Number(n) => f.debug_tuple("Number").field(n).finish(),
+            Object::String(s) => f.debug_tuple("String").field(s).finish(),
+            Object::Error(e) => f.debug_tuple("Error").field(e).finish(),
+            Object::Function(func) => f.debug_tuple("Function").field(func).finish(),
+            Object::Class(class) => f.debug_tuple("Class").field(class).finish(),
+            Object::Instance(instance) => f.debug_tuple("Instance").field(instance).finish(),
+            Object::List(list) => {
+                write!(f, "List(")?;
+                write_list(f, list, true)?;
+                write!(f, ")")
+            }
+            Object::Map(map) => {
+                write!(f, "Map(")?;
+                write_map(f, map, true)?;
+                write!(f, ")")
+            }
+        }
+    }
+}
+
 impl fmt::Display for Object {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -47,6 +150,8 @@ impl fmt::Display for Object {
             Object::Function(func) => write!(f, "{}", func.to_string()),
             Object::Class(class) => write!(f, "{:?}", class),
             Object::Instance(instance) => write!(f, "{:?}", instance),
+            Object::List(list) => write_list(f, list, false),
+            Object::Map(map) => write_map(f, map, false),
         }
     }
 }
@@ -63,6 +168,15 @@ pub enum Expr {
     Assign(Box<Assign>),
     Logical(Box<Logical>),
     Call(Box<Call>),
+    Get(Box<Get>),
+    Set(Box<Set>),
+    This(Box<This>),
+    Super(Box<Super>),
+    List(Box<List>),
+    IndexGet(Box<IndexGet>),
+    IndexSet(Box<IndexSet>),
+    Map(Box<Map>),
+    Lambda(Box<Lambda>),
 }
 
 impl Expr {
@@ -77,6 +191,39 @@ impl Expr {
             Expr::Assign(a) => visitor.visit_assign(a),
             Expr::Logical(l) => visitor.visit_logical(l),
             Expr::Call(c) => visitor.visit_call(c),
+            Expr::Get(g) => visitor.visit_get(g),
+            Expr::Set(s) => visitor.visit_set(s),
+            Expr::This(t) => visitor.visit_this(t),
+            Expr::Super(s) => visitor.visit_super(s),
+            Expr::List(l) => visitor.visit_list(l),
+            Expr::IndexGet(i) => visitor.visit_index_get(i),
+            Expr::IndexSet(i) => visitor.visit_index_set(i),
+            Expr::Map(m) => visitor.visit_map(m),
+            Expr::Lambda(l) => visitor.visit_lambda(l),
+        }
+    }
+
+    /// The source text this expression was parsed from.
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Binary(b) => b.span,
+            Expr::Grouping(g) => g.span,
+            Expr::Literal(l) => l.span,
+            Expr::Unary(u) => u.span,
+            Expr::Ternary(t) => t.span,
+            Expr::Variable(v) => v.span,
+            Expr::Assign(a) => a.span,
+            Expr::Logical(l) => l.span,
+            Expr::Call(c) => c.span,
+            Expr::Get(g) => g.span,
+            Expr::Set(s) => s.span,
+            Expr::This(t) => t.span,
+            Expr::Super(s) => s.span,
+            Expr::List(l) => l.span,
+            Expr::IndexGet(i) => i.span,
+            Expr::IndexSet(i) => i.span,
+            Expr::Map(m) => m.span,
+            Expr::Lambda(l) => l.span,
         }
     }
 }
@@ -92,6 +239,15 @@ pub trait ExprVisitor<T> {
     fn visit_assign(&self, assign: &Assign) -> T;
     fn visit_logical(&self, logical: &Logical) -> T;
     fn visit_call(&self, call: &Call) -> T;
+    fn visit_get(&self, get: &Get) -> T;
+    fn visit_set(&self, set: &Set) -> T;
+    fn visit_this(&self, this: &This) -> T;
+    fn visit_super(&self, sup: &Super) -> T;
+    fn visit_list(&self, list: &List) -> T;
+    fn visit_index_get(&self, index_get: &IndexGet) -> T;
+    fn visit_index_set(&self, index_set: &IndexSet) -> T;
+    fn visit_map(&self, map: &Map) -> T;
+    fn visit_lambda(&self, lambda: &Lambda) -> T;
 }
 
 #[derive(Debug, Clone)]
@@ -99,22 +255,26 @@ pub struct Binary {
     pub left: Box<Expr>,
     pub operator: Token,
     pub right: Box<Expr>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct Grouping {
     pub expression: Box<Expr>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct Literal {
     pub value: Object,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct Unary {
     pub operator: Token,
     pub right: Box<Expr>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
@@ -122,17 +282,35 @@ pub struct Ternary {
     pub condition: Box<Expr>,
     pub true_branch: Box<Expr>,
     pub false_branch: Box<Expr>,
+    pub span: Span,
+}
+
+// Identifies an expression the resolver records a scope depth for. Ids come from one
+// process-wide counter so nodes parsed in different REPL lines never share one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExprId(pub usize);
+
+static NEXT_EXPR_ID: AtomicUsize = AtomicUsize::new(0);
+
+impl ExprId {
+    pub fn fresh() -> Self {
+        ExprId(NEXT_EXPR_ID.fetch_add(1, Ordering::Relaxed))
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Variable {
+    pub id: ExprId,
     pub name: Token,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct Assign {
+    pub id: ExprId,
     pub name: Token,
     pub value: Box<Expr>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
@@ -140,6 +318,7 @@ pub struct Logical {
     pub left: Box<Expr>,
     pub operator: Token,
     pub right: Box<Expr>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
@@ -147,15 +326,100 @@ pub struct Call {
     pub callee: Box<Expr>,
     pub paren: Token,
     pub args: Vec<Expr>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct Get {
+    pub object: Box<Expr>,
+    pub name: Token,
+    pub span: Span,
 }
 
-pub trait LoxCallable: std::fmt::Debug {
+#[derive(Debug, Clone)]
+pub struct Set {
+    pub object: Box<Expr>,
+    pub name: Token,
+    pub value: Box<Expr>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct This {
+    pub id: ExprId,
+    pub keyword: Token,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct Super {
+    pub id: ExprId,
+    pub keyword: Token,
+    pub method: Token,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct List {
+    pub bracket: Token,
+    pub elements: Vec<Expr>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct Map {
+    pub brace: Token,
+    pub entries: Vec<(Expr, Expr)>,
+    pub span: Span,
+}
+
+// Anonymous functions reuse the Function declaration; its name token is the `fun` or `=>`
+// keyword that introduced the lambda rather than an identifier.
+#[derive(Debug, Clone)]
+pub struct Lambda {
+    pub declaration: Function,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct IndexGet {
+    pub object: Box<Expr>,
+    pub bracket: Token,
+    pub index: Box<Expr>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct IndexSet {
+    pub object: Box<Expr>,
+    pub bracket: Token,
+    pub index: Box<Expr>,
+    pub value: Box<Expr>,
+    pub span: Span,
+}
+
+// Any lets the bytecode VM recognise the callables it compiled itself
+pub trait LoxCallable: std::fmt::Debug + Any {
     fn call(&self, interpreter: &Interpreter, args: &[Object]) -> Result<Object>;
     fn arity(&self) -> usize;
     // Functions can override this to provide a string representation
     fn to_string(&self) -> String {
         "<fn>".to_string()
     }
+    // Like `call`, but may hand back a call made from tail position as ControlFlow::TailCall
+    // for the interpreter to make, instead of making it on top of this one
+    fn call_tail(&self, interpreter: &Interpreter, args: &[Object]) -> FlowResult<Object> {
+        ok(self.call(interpreter, args)?)
+    }
+    // Reports the heap objects this callable holds on to, for the garbage collector;
+    // natives hold none
+    fn trace(&self, _tracer: &mut Tracer) {}
+}
+
+// A method stored on a class. Each backend binds its own kind of function to the instance
+// it was looked up on, giving the callable that `instance.method` evaluates to.
+pub trait LoxMethod: LoxCallable {
+    fn bind(&self, instance: Rc<LoxInstance>) -> Rc<dyn LoxCallable>;
 }
 
 // We'll implement specific callable types later when needed
@@ -0,0 +1,690 @@
+use crate::compiler::error::{ErrorReporter, LoxError};
+use crate::compiler::error_codes::ErrorCode;
+use crate::compiler::expr::Expr;
+use crate::compiler::parser::Parser;
+use crate::compiler::scanner::Scanner;
+use crate::compiler::stmt::{Block, Function, IfStmt, Stmt, WhileStmt};
+use crate::compiler::token::{Comment, TokenType};
+use std::cell::Cell;
+
+// Reprints Lox source in a canonical layout for `rlox fmt`.
+//
+// The program is parsed as usual, then each statement and expression is turned into a
+// small layout document: text, places where the line may break, and groups that are kept
+// on one line when they fit in the line width and broken at every break point when they
+// don't. Blocks always span several lines; argument lists, list and map literals, binary
+// operators and ternaries only break when they're too long.
+//
+// The parser desugars some syntax, so the formatter reads the source to undo it: a for
+// loop is a block or while statement whose span starts with `for`, an arrow lambda is
+// named after its `=>` token, and literals are copied as written so `1.50` and escapes in
+// strings survive. Comments come from the scanner and are put back by position: before
+// the statement that follows them, or after one that ends on the same line. The bodies of
+// if, else and loops count as statements of their own here, and a comment among a
+// function's parameters stays after the parameter it follows. Runs of blank lines between
+// statements are kept as a single blank line.
+//
+// Other comments inside an expression, such as `var xs = [1, // one` followed by `2];`,
+// move to their own line after the statement. They aren't lost, but the layout around
+// them is not kept.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatOptions {
+    // spaces per indentation level
+    pub indent: usize,
+    // lines are broken to fit in this many columns where the syntax allows it
+    pub line_width: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent: 4,
+            line_width: 80,
+        }
+    }
+}
+
+/// Formats a whole source file, or returns every scan and parse error found in it.
+pub fn format(source: &str, options: &FormatOptions) -> Result<String, Vec<LoxError>> {
+    let mut reporter = CollectingReporter { errors: Vec::new() };
+    let (tokens, comments) = {
+        let mut scanner = Scanner::new(source.to_string(), &mut reporter);
+        scanner.scan_tokens();
+        (scanner.tokens, scanner.comments)
+    };
+    let (statements, parse_errors) = Parser::new(&tokens).parse_all();
+    let mut errors = reporter.errors;
+    errors.extend(parse_errors);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let formatter = Formatter::new(source, comments);
+    let doc = formatter.statements(&statements, 0, source.len(), |s| formatter.statement(s));
+    let mut out = render(&doc, options);
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+struct CollectingReporter {
+    errors: Vec<LoxError>,
+}
+
+impl ErrorReporter for CollectingReporter {
+    fn error(&mut self, line: usize, message: &str) {
+        self.errors.push(LoxError::new_from_line(line, message));
+    }
+
+    fn error_with_code(&mut self, line: usize, code: ErrorCode, message: &str) {
+        self.errors
+            .push(LoxError::new_from_line(line, message).with_code(code));
+    }
+
+    fn runtime_error(&mut self, error: &LoxError) {
+        self.errors.push(error.clone());
+    }
+}
+
+enum Doc {
+    Text(String),
+    // a space, or a line break when its group is broken
+    Line,
+    // nothing, or a line break when its group is broken
+    SoftLine,
+    // always a line break; a group with one directly inside is always broken
+    HardLine,
+    // the contents' line breaks are indented one more level when the group is broken
+    Indent(Box<Doc>),
+    Group(Box<Doc>),
+    Concat(Vec<Doc>),
+}
+
+fn text(s: impl Into<String>) -> Doc {
+    Doc::Text(s.into())
+}
+
+fn concat(docs: Vec<Doc>) -> Doc {
+    Doc::Concat(docs)
+}
+
+fn indent(doc: Doc) -> Doc {
+    Doc::Indent(Box::new(doc))
+}
+
+fn group(doc: Doc) -> Doc {
+    Doc::Group(Box::new(doc))
+}
+
+// `open items... close`, with the items on their own lines if they don't fit on one
+fn bracketed(open: &str, items: Vec<Doc>, close: &str) -> Doc {
+    if items.is_empty() {
+        return text(format!("{}{}", open, close));
+    }
+    let mut inner = vec![Doc::SoftLine];
+    for (i, item) in items.into_iter().enumerate() {
+        if i > 0 {
+            inner.push(text(","));
+            inner.push(Doc::Line);
+        }
+        inner.push(item);
+    }
+    group(concat(vec![
+        text(open),
+        indent(concat(inner)),
+        Doc::SoftLine,
+        text(close),
+    ]))
+}
+
+// whether the group has to break; line breaks inside nested groups are theirs to decide
+fn has_hard_line(doc: &Doc) -> bool {
+    match doc {
+        Doc::HardLine => true,
+        Doc::Text(s) => s.contains('\n'),
+        Doc::Indent(doc) => has_hard_line(doc),
+        Doc::Concat(docs) => docs.iter().any(has_hard_line),
+        Doc::Line | Doc::SoftLine | Doc::Group(_) => false,
+    }
+}
+
+// (indentation, whether the enclosing group fits on one line, doc)
+type Command<'a> = (usize, bool, &'a Doc);
+
+fn render(doc: &Doc, options: &FormatOptions) -> String {
+    let mut out = String::new();
+    let mut column = 0;
+    let mut stack: Vec<Command> = vec![(0, false, doc)];
+    while let Some((level, flat, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(s) => {
+                out.push_str(s);
+                column = match s.rfind('\n') {
+                    Some(i) => s[i + 1..].chars().count(),
+                    None => column + s.chars().count(),
+                };
+            }
+            Doc::Line if flat => {
+                out.push(' ');
+                column += 1;
+            }
+            Doc::SoftLine if flat => {}
+            Doc::Line | Doc::SoftLine | Doc::HardLine => {
+                // blank lines and lines ending in a break get no trailing spaces
+                out.truncate(out.trim_end_matches(' ').len());
+                out.push('\n');
+                out.push_str(&" ".repeat(level));
+                column = level;
+            }
+            Doc::Indent(doc) if flat => stack.push((level, flat, doc)),
+            Doc::Indent(doc) => stack.push((level + options.indent, flat, doc)),
+            Doc::Group(doc) => {
+                let remaining = options.line_width as isize - column as isize;
+                let flat = !has_hard_line(doc) && (flat || fits(remaining, doc, &stack));
+                stack.push((level, flat, doc));
+            }
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (level, flat, doc))),
+        }
+    }
+    out.truncate(out.trim_end().len());
+    out
+}
+
+// whether the group, printed on one line, and whatever follows it up to the next line
+// break leave the line within its width
+fn fits(mut remaining: isize, doc: &Doc, rest: &[Command]) -> bool {
+    let mut pending = vec![(true, doc)];
+    let mut rest = rest.iter().rev();
+    loop {
+        if remaining < 0 {
+            return false;
+        }
+        let (flat, doc) = match pending.pop() {
+            Some(next) => next,
+            None => match rest.next() {
+                Some(&(_, flat, doc)) => (flat, doc),
+                None => return true,
+            },
+        };
+        match doc {
+            Doc::Text(s) => match s.find('\n') {
+                Some(i) => return remaining >= s[..i].chars().count() as isize,
+                None => remaining -= s.chars().count() as isize,
+            },
+            Doc::Line if flat => remaining -= 1,
+            Doc::SoftLine if flat => {}
+            Doc::Line | Doc::SoftLine | Doc::HardLine => return true,
+            Doc::Indent(doc) | Doc::Group(doc) => pending.push((flat, doc)),
+            Doc::Concat(docs) => pending.extend(docs.iter().rev().map(|doc| (flat, doc))),
+        }
+    }
+}
+
+struct Formatter<'a> {
+    source: &'a str,
+    comments: Vec<Comment>,
+    // the first comment not printed yet
+    next_comment: Cell<usize>,
+    // byte offsets where each line starts
+    line_starts: Vec<usize>,
+    // where the last statement or comment printed ends, to find blank lines after it
+    last_end: Cell<usize>,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(source: &'a str, comments: Vec<Comment>) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            source,
+            comments,
+            next_comment: Cell::new(0),
+            line_starts,
+            last_end: Cell::new(0),
+        }
+    }
+
+    fn line_of(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset)
+    }
+
+    fn source_starts_with(&self, offset: usize, prefix: &str) -> bool {
+        self.source[offset..].starts_with(prefix)
+    }
+
+    // the next comment, if it starts before `offset`
+    fn comment_before(&self, offset: usize) -> Option<&Comment> {
+        self.comments
+            .get(self.next_comment.get())
+            .filter(|comment| comment.span.start < offset)
+    }
+
+    fn take_comment(&self) -> Doc {
+        let comment = &self.comments[self.next_comment.get()];
+        self.next_comment.set(self.next_comment.get() + 1);
+        self.last_end.set(comment.span.end);
+        text(comment.text.trim_end())
+    }
+
+    // comments on the same line as `end` that start before `limit`, each after a space
+    fn trailing_comments(&self, end: usize, limit: usize) -> Vec<Doc> {
+        let line = self.line_of(end.saturating_sub(1));
+        let mut docs = Vec::new();
+        while let Some(comment) = self.comment_before(limit) {
+            if self.line_of(comment.span.start) != line {
+                break;
+            }
+            docs.push(text(" "));
+            docs.push(self.take_comment());
+        }
+        docs
+    }
+
+    // Lays out the statements of a program, block or class body one per line, along with
+    // the comments among them. `start` and `end` are where the body's contents begin and
+    // end in the source, e.g. just inside its braces.
+    fn statements(
+        &self,
+        statements: &[Stmt],
+        start: usize,
+        end: usize,
+        print: impl Fn(&Stmt) -> Doc,
+    ) -> Doc {
+        self.last_end.set(start);
+        let mut parts = Vec::new();
+        for (i, statement) in statements.iter().enumerate() {
+            let span = statement.span();
+            while self.comment_before(span.start).is_some() {
+                let comment_start = self.comments[self.next_comment.get()].span.start;
+                self.separate(&mut parts, comment_start);
+                parts.push(self.take_comment());
+            }
+            self.separate(&mut parts, span.start);
+            parts.push(print(statement));
+            self.last_end.set(span.end);
+            let next = statements.get(i + 1).map_or(end, |next| next.span().start);
+            parts.extend(self.trailing_comments(span.end, next));
+        }
+        while self.comment_before(end).is_some() {
+            let comment_start = self.comments[self.next_comment.get()].span.start;
+            self.separate(&mut parts, comment_start);
+            parts.push(self.take_comment());
+        }
+        concat(parts)
+    }
+
+    // starts a new line before anything but the first item, keeping one blank line where
+    // the source had any
+    fn separate(&self, parts: &mut Vec<Doc>, start: usize) {
+        if parts.is_empty() {
+            return;
+        }
+        parts.push(Doc::HardLine);
+        // only the whitespace right before the item counts, not what comes after a comment
+        // or brace that was moved
+        let gap = &self.source[self.last_end.get().min(start)..start];
+        let whitespace = &gap[gap.trim_end().len()..];
+        if whitespace.matches('\n').count() >= 2 {
+            parts.push(Doc::HardLine);
+        }
+    }
+
+    // `{ ... }` around a list of statements whose source spans `start..end`, including the
+    // braces; empty bodies print as `{}`
+    fn braced(
+        &self,
+        statements: &[Stmt],
+        start: usize,
+        end: usize,
+        print: impl Fn(&Stmt) -> Doc,
+    ) -> Doc {
+        let close = end - 1;
+        let first = statements.first().map_or(close, |first| first.span().start);
+        let after_brace = self.trailing_comments(start + 1, first);
+        if statements.is_empty() && after_brace.is_empty() && self.comment_before(close).is_none() {
+            return text("{}");
+        }
+        let mut parts = vec![text("{")];
+        parts.extend(after_brace);
+        if statements.is_empty() && self.comment_before(close).is_none() {
+            // only comments after the brace: a line comment needs the line to itself
+            let last = &self.comments[self.next_comment.get() - 1];
+            match last.text.starts_with("//") {
+                true => parts.push(Doc::HardLine),
+                false => parts.push(text(" ")),
+            }
+        } else {
+            let body = self.statements(statements, start + 1, close, print);
+            parts.push(indent(concat(vec![Doc::HardLine, body])));
+            parts.push(Doc::HardLine);
+        }
+        parts.push(text("}"));
+        group(concat(parts))
+    }
+
+    fn block(&self, block: &Block) -> Doc {
+        self.braced(&block.statements, block.span.start, block.span.end, |s| {
+            self.statement(s)
+        })
+    }
+
+    fn block_or_statement(&self, stmt: &Stmt) -> Doc {
+        match stmt {
+            Stmt::Block(block) => self.block(block),
+            other => self.statement(other),
+        }
+    }
+
+    // the body of an if, while or for: a block follows on the same line, anything else
+    // does too unless it's too long. Comments between the head and the body stay at the
+    // end of the head's line, and the body then starts on the next one.
+    fn branch(&self, stmt: &Stmt) -> Doc {
+        let mut comments = Vec::new();
+        while self.comment_before(stmt.span().start).is_some() {
+            comments.push(match comments.is_empty() {
+                true => text(" "),
+                false => Doc::HardLine,
+            });
+            comments.push(self.take_comment());
+        }
+        match stmt {
+            Stmt::Block(block) if comments.is_empty() => concat(vec![text(" "), self.block(block)]),
+            Stmt::Block(block) => concat(vec![concat(comments), Doc::HardLine, self.block(block)]),
+            other if comments.is_empty() => {
+                group(indent(concat(vec![Doc::Line, self.statement(other)])))
+            }
+            other => indent(concat(vec![
+                concat(comments),
+                Doc::HardLine,
+                self.statement(other),
+            ])),
+        }
+    }
+
+    fn statement(&self, stmt: &Stmt) -> Doc {
+        match stmt {
+            Stmt::Expression(e) => concat(vec![self.expression(&e.expression), text(";")]),
+            Stmt::Print(p) => concat(vec![
+                text("print "),
+                self.expression(&p.expression),
+                text(";"),
+            ]),
+            Stmt::Var(v) => {
+                // `var x;` is parsed as a nil literal spanning just the name
+                let mut parts = vec![text(format!("var {}", v.name.lexeme))];
+                if v.initializer.span() != v.name.span {
+                    parts.push(text(" = "));
+                    parts.push(self.expression(&v.initializer));
+                }
+                parts.push(text(";"));
+                concat(parts)
+            }
+            Stmt::Block(block) if self.source_starts_with(block.span.start, "for") => {
+                match block.statements.as_slice() {
+                    [initializer, Stmt::WhileStmt(w)] => self.for_loop(Some(initializer), w),
+                    _ => self.block(block),
+                }
+            }
+            Stmt::Block(block) => self.block(block),
+            Stmt::IfStmt(i) => self.if_statement(i),
+            Stmt::WhileStmt(w) if self.source_starts_with(w.span.start, "for") => {
+                self.for_loop(None, w)
+            }
+            Stmt::WhileStmt(w) => concat(vec![
+                text("while ("),
+                self.expression(&w.condition),
+                text(")"),
+                self.branch(&w.body),
+            ]),
+            Stmt::Function(f) => concat(vec![text("fun "), self.function(f)]),
+            Stmt::Class(c) => {
+                let mut head = format!("class {}", c.name.lexeme);
+                if let Some(superclass) = &c.superclass {
+                    head = format!("{} < {}", head, self.source_text(superclass));
+                }
+                let methods = self.braced(
+                    &c.methods,
+                    self.brace_after(c.name.span.end),
+                    c.span.end,
+                    |m| match m {
+                        Stmt::Function(method) => self.function(method),
+                        other => self.statement(other),
+                    },
+                );
+                concat(vec![text(head + " "), methods])
+            }
+            Stmt::ReturnStmt(r) => match &r.value {
+                Some(value) => concat(vec![text("return "), self.expression(value), text(";")]),
+                None => text("return;"),
+            },
+            Stmt::Break(_) => text("break;"),
+            Stmt::Continue(_) => text("continue;"),
+        }
+    }
+
+    // offset of the first `{` at or after `offset`
+    fn brace_after(&self, offset: usize) -> usize {
+        offset + self.source[offset..].find('{').unwrap_or(0)
+    }
+
+    fn if_statement(&self, i: &IfStmt) -> Doc {
+        let mut parts = vec![
+            text("if ("),
+            self.expression(&i.condition),
+            text(")"),
+            self.branch(&i.then_branch),
+        ];
+        if let Some(else_branch) = &i.else_branch {
+            // comments after the then branch stay on its last line, so `else` can't follow
+            let after_then =
+                self.trailing_comments(i.then_branch.span().end, else_branch.span().start);
+            parts.push(match i.then_branch.as_ref() {
+                Stmt::Block(_) if after_then.is_empty() => text(" else"),
+                _ => concat(vec![concat(after_then), Doc::HardLine, text("else")]),
+            });
+            parts.push(match else_branch.as_ref() {
+                Stmt::IfStmt(chained) if self.comment_before(chained.span.start).is_none() => {
+                    concat(vec![text(" "), self.if_statement(chained)])
+                }
+                other => self.branch(other),
+            });
+        }
+        concat(parts)
+    }
+
+    // `for (initializer; condition; increment) body`, put back together from the while
+    // loop it was desugared into
+    fn for_loop(&self, initializer: Option<&Stmt>, w: &WhileStmt) -> Doc {
+        let mut parts = vec![text("for (")];
+        match initializer {
+            Some(initializer) => parts.push(self.statement(initializer)),
+            None => parts.push(text(";")),
+        }
+        // a missing condition is filled in with `true` spanning the whole loop
+        if w.condition.span() != w.span {
+            parts.push(text(" "));
+            parts.push(self.expression(&w.condition));
+        }
+        parts.push(text(";"));
+        if let Some(increment) = &w.increment {
+            parts.push(text(" "));
+            parts.push(self.expression(increment));
+        }
+        parts.push(text(")"));
+        // the loop body was wrapped in a block of its own
+        let body = match w.body.as_ref() {
+            Stmt::Block(block) if block.statements.len() == 1 => &block.statements[0],
+            other => other,
+        };
+        parts.push(self.branch(body));
+        concat(parts)
+    }
+
+    // `name(parameters) { body }`, shared by functions and methods
+    fn function(&self, function: &Function) -> Doc {
+        concat(vec![
+            text(function.name.lexeme.clone()),
+            self.parameters(function, function.body.span().start),
+            text(" "),
+            self.block_or_statement(&function.body),
+        ])
+    }
+
+    // `(a, b)`, or one parameter per line when there are comments among them; `end` is
+    // where the list is known to be over, such as the body's brace
+    fn parameters(&self, function: &Function, end: usize) -> Doc {
+        let parameters = &function.parameters;
+        if self.comment_before(end).is_none() {
+            let names: Vec<&str> = parameters.iter().map(|p| p.lexeme.as_str()).collect();
+            return text(format!("({})", names.join(", ")));
+        }
+
+        let mut inner = Vec::new();
+        for (i, parameter) in parameters.iter().enumerate() {
+            while self.comment_before(parameter.span.start).is_some() {
+                inner.push(Doc::HardLine);
+                inner.push(self.take_comment());
+            }
+            inner.push(Doc::HardLine);
+            inner.push(text(parameter.lexeme.clone()));
+            if i + 1 < parameters.len() {
+                inner.push(text(","));
+            }
+            let next = parameters.get(i + 1).map_or(end, |next| next.span.start);
+            inner.extend(self.trailing_comments(parameter.span.end, next));
+        }
+        while self.comment_before(end).is_some() {
+            inner.push(Doc::HardLine);
+            inner.push(self.take_comment());
+        }
+        concat(vec![
+            text("("),
+            indent(concat(inner)),
+            Doc::HardLine,
+            text(")"),
+        ])
+    }
+
+    fn source_text(&self, expr: &Expr) -> &str {
+        let span = expr.span();
+        &self.source[span.start..span.end]
+    }
+
+    // `a + b + c` breaks before every operand once the whole chain doesn't fit
+    fn binary(&self, left: &Expr, operator: &str, right: &Expr) -> Doc {
+        let mut operands = vec![right];
+        let mut first = left;
+        loop {
+            let (next, right) = match first {
+                Expr::Binary(b) if b.operator.lexeme == operator => (&b.left, &b.right),
+                Expr::Logical(l) if l.operator.lexeme == operator => (&l.left, &l.right),
+                _ => break,
+            };
+            operands.push(right);
+            first = next;
+        }
+
+        let mut parts = vec![self.expression(first)];
+        for operand in operands.into_iter().rev() {
+            parts.push(text(format!(" {}", operator)));
+            parts.push(indent(concat(vec![Doc::Line, self.expression(operand)])));
+        }
+        group(concat(parts))
+    }
+
+    fn expression(&self, expr: &Expr) -> Doc {
+        match expr {
+            Expr::Literal(_) | Expr::Variable(_) | Expr::This(_) => text(self.source_text(expr)),
+            Expr::Super(s) => text(format!("super.{}", s.method.lexeme)),
+            Expr::Grouping(g) => concat(vec![text("("), self.expression(&g.expression), text(")")]),
+            Expr::Unary(u) => concat(vec![
+                text(u.operator.lexeme.clone()),
+                self.expression(&u.right),
+            ]),
+            Expr::Binary(b) => self.binary(&b.left, &b.operator.lexeme, &b.right),
+            Expr::Logical(l) => self.binary(&l.left, &l.operator.lexeme, &l.right),
+            Expr::Ternary(t) => group(concat(vec![
+                self.expression(&t.condition),
+                indent(concat(vec![
+                    Doc::Line,
+                    text("? "),
+                    self.expression(&t.true_branch),
+                    Doc::Line,
+                    text(": "),
+                    self.expression(&t.false_branch),
+                ])),
+            ])),
+            Expr::Assign(a) => concat(vec![
+                text(format!("{} = ", a.name.lexeme)),
+                self.expression(&a.value),
+            ]),
+            Expr::Call(c) => {
+                let args = c.args.iter().map(|arg| self.expression(arg)).collect();
+                concat(vec![self.expression(&c.callee), bracketed("(", args, ")")])
+            }
+            Expr::Get(g) => concat(vec![
+                self.expression(&g.object),
+                text(format!(".{}", g.name.lexeme)),
+            ]),
+            Expr::Set(s) => concat(vec![
+                self.expression(&s.object),
+                text(format!(".{} = ", s.name.lexeme)),
+                self.expression(&s.value),
+            ]),
+            Expr::List(l) => {
+                let elements = l.elements.iter().map(|e| self.expression(e)).collect();
+                bracketed("[", elements, "]")
+            }
+            Expr::IndexGet(i) => concat(vec![
+                self.expression(&i.object),
+                text("["),
+                self.expression(&i.index),
+                text("]"),
+            ]),
+            Expr::IndexSet(i) => concat(vec![
+                self.expression(&i.object),
+                text("["),
+                self.expression(&i.index),
+                text("] = "),
+                self.expression(&i.value),
+            ]),
+            Expr::Map(m) => {
+                let entries = m
+                    .entries
+                    .iter()
+                    .map(|(key, value)| {
+                        concat(vec![
+                            self.expression(key),
+                            text(": "),
+                            self.expression(value),
+                        ])
+                    })
+                    .collect();
+                bracketed("{", entries, "}")
+            }
+            Expr::Lambda(l) => {
+                let function = &l.declaration;
+                match (&function.name.token_type, function.body.as_ref()) {
+                    // `(a, b) => value` was desugared into `{ return value; }`
+                    (TokenType::ARROW, Stmt::Block(body)) => match body.statements.as_slice() {
+                        [Stmt::ReturnStmt(r)] if r.value.is_some() => concat(vec![
+                            self.parameters(function, function.name.span.start),
+                            text(" => "),
+                            self.expression(r.value.as_ref().unwrap()),
+                        ]),
+                        _ => self.block(body),
+                    },
+                    (_, body) => concat(vec![
+                        text("fun "),
+                        self.parameters(function, body.span().start),
+                        text(" "),
+                        self.block_or_statement(body),
+                    ]),
+                }
+            }
+        }
+    }
+}
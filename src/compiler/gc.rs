@@ -0,0 +1,358 @@
+use crate::compiler::env::{Env, EnvRef};
+use crate::compiler::expr::{LoxCallable, Object};
+use crate::compiler::lox_instance::LoxInstance;
+use crate::compiler::lox_map::LoxMap;
+use crate::compiler::vm::object::Upvalue;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+// A mark-and-sweep collector for the reference cycles Rc can't free on its own: every
+// function's closure env holds the function, instances can store themselves in their
+// fields, and so on.
+//
+// Objects stay reference counted; the heap just keeps a weak reference to every env,
+// function, class, instance, list, map and upvalue as it's allocated. A collection
+//   1. traces each object's references to other heap objects,
+//   2. marks everything reachable from the roots: the globals, the active env chain, and
+//      any object referenced from outside the heap (a value on the Rust stack or the VM
+//      stack), which shows up as more strong references than the heap accounts for,
+//   3. sweeps by clearing out the unmarked objects, breaking their cycles so Rc frees them.
+//
+// There is one heap per thread, like the Rc objects it tracks.
+
+// collect once the heap tracks twice as many objects as survived the last collection, but
+// never before it tracks this many
+const MIN_THRESHOLD: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GcStats {
+    pub collections: usize,
+    // objects ever tracked
+    pub allocated: usize,
+    // objects freed by the collector, as opposed to by their reference count dropping
+    pub freed: usize,
+    // tracked objects still alive
+    pub live: usize,
+}
+
+impl fmt::Display for GcStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "gc: {} collections, {} objects allocated, {} freed by the collector, {} live",
+            self.collections, self.allocated, self.freed, self.live
+        )
+    }
+}
+
+/// Lets the collector see an object's references to other heap objects.
+pub trait Trace {
+    fn trace(&self, tracer: &mut Tracer);
+}
+
+// Collects the addresses an object refers to. Only strong references the object itself
+// owns may be reported, each once: anything reported is assumed not to be holding the
+// target alive from outside the heap.
+#[derive(Default)]
+pub struct Tracer {
+    edges: Vec<usize>,
+}
+
+impl Tracer {
+    pub fn edge<T: ?Sized>(&mut self, rc: &Rc<T>) {
+        self.edges.push(address(rc));
+    }
+
+    pub fn object(&mut self, object: &Object) {
+        match object {
+            Object::Function(function) => self.edge(function),
+            Object::Class(class) => self.edge(class),
+            Object::Instance(instance) => self.edge(instance),
+            Object::List(list) => self.edge(list),
+            Object::Map(map) => self.edge(map),
+            Object::Nil
+            | Object::Boolean(_)
+            | Object::Number(_)
+            | Object::String(_)
+            | Object::Error(_) => {}
+        }
+    }
+}
+
+fn address<T: ?Sized>(rc: &Rc<T>) -> usize {
+    Rc::as_ptr(rc) as *const () as usize
+}
+
+enum Node {
+    Env(Weak<RefCell<Env>>),
+    Callable(Weak<dyn LoxCallable>),
+    Instance(Weak<LoxInstance>),
+    List(Weak<RefCell<Vec<Object>>>),
+    Map(Weak<RefCell<LoxMap>>),
+    Upvalue(Weak<RefCell<Upvalue>>),
+}
+
+impl Node {
+    fn strong_count(&self) -> usize {
+        match self {
+            Node::Env(weak) => weak.strong_count(),
+            Node::Callable(weak) => weak.strong_count(),
+            Node::Instance(weak) => weak.strong_count(),
+            Node::List(weak) => weak.strong_count(),
+            Node::Map(weak) => weak.strong_count(),
+            Node::Upvalue(weak) => weak.strong_count(),
+        }
+    }
+
+    fn address(&self) -> usize {
+        match self {
+            Node::Env(weak) => weak.as_ptr() as *const () as usize,
+            Node::Callable(weak) => weak.as_ptr() as *const () as usize,
+            Node::Instance(weak) => weak.as_ptr() as *const () as usize,
+            Node::List(weak) => weak.as_ptr() as *const () as usize,
+            Node::Map(weak) => weak.as_ptr() as *const () as usize,
+            Node::Upvalue(weak) => weak.as_ptr() as *const () as usize,
+        }
+    }
+
+    // None when the object is dead, or borrowed mutably right now so its references
+    // can't be read; the collector then treats what it refers to as rooted
+    fn trace(&self) -> Option<Vec<usize>> {
+        let mut tracer = Tracer::default();
+        match self {
+            Node::Env(weak) => weak.upgrade()?.try_borrow().ok()?.trace(&mut tracer),
+            Node::Callable(weak) => weak.upgrade()?.trace(&mut tracer),
+            Node::Instance(weak) => weak.upgrade()?.trace(&mut tracer),
+            Node::List(weak) => {
+                for element in weak.upgrade()?.try_borrow().ok()?.iter() {
+                    tracer.object(element);
+                }
+            }
+            Node::Map(weak) => weak.upgrade()?.try_borrow().ok()?.trace(&mut tracer),
+            Node::Upvalue(weak) => {
+                if let Upvalue::Closed(value) = &*weak.upgrade()?.try_borrow().ok()? {
+                    tracer.object(value);
+                }
+            }
+        }
+        Some(tracer.edges)
+    }
+
+    // drops everything the object refers to; functions and classes can't be changed, but
+    // every cycle through them also runs through one of the containers cleared here
+    fn clear(&self) {
+        match self {
+            Node::Env(weak) => {
+                if let Some(env) = weak.upgrade() {
+                    let contents = std::mem::replace(&mut *env.borrow_mut(), Env::new());
+                    drop(contents);
+                }
+            }
+            Node::Callable(_) => {}
+            Node::Instance(weak) => {
+                if let Some(instance) = weak.upgrade() {
+                    let fields = std::mem::take(&mut *instance.fields.borrow_mut());
+                    drop(fields);
+                }
+            }
+            Node::List(weak) => {
+                if let Some(list) = weak.upgrade() {
+                    let elements = std::mem::take(&mut *list.borrow_mut());
+                    drop(elements);
+                }
+            }
+            Node::Map(weak) => {
+                if let Some(map) = weak.upgrade() {
+                    let entries = std::mem::take(&mut *map.borrow_mut());
+                    drop(entries);
+                }
+            }
+            Node::Upvalue(weak) => {
+                if let Some(upvalue) = weak.upgrade() {
+                    let value =
+                        std::mem::replace(&mut *upvalue.borrow_mut(), Upvalue::Closed(Object::Nil));
+                    drop(value);
+                }
+            }
+        }
+    }
+}
+
+struct Heap {
+    nodes: Vec<Node>,
+    // globals of every interpreter on this thread
+    globals: Vec<Weak<RefCell<Env>>>,
+    // the env each running block or call switched to, innermost last
+    active: Vec<EnvRef>,
+    stats: GcStats,
+    next_collection: usize,
+    stress: bool,
+}
+
+thread_local! {
+    static HEAP: RefCell<Heap> = RefCell::new(Heap {
+        nodes: Vec::new(),
+        globals: Vec::new(),
+        active: Vec::new(),
+        stats: GcStats::default(),
+        next_collection: MIN_THRESHOLD,
+        stress: false,
+    });
+}
+
+fn track(node: Node) {
+    let due = HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.nodes.push(node);
+        heap.stats.allocated += 1;
+        heap.stress || heap.nodes.len() >= heap.next_collection
+    });
+    if due {
+        collect();
+    }
+}
+
+pub fn track_env(env: EnvRef) -> EnvRef {
+    track(Node::Env(Rc::downgrade(&env)));
+    env
+}
+
+// takes the callable itself rather than an Rc so the Rc's type is fixed before the caller
+// coerces it to a trait object
+pub fn track_callable<T: LoxCallable + 'static>(callable: T) -> Rc<T> {
+    let callable = Rc::new(callable);
+    let weak: Weak<dyn LoxCallable> = Rc::downgrade(&callable) as Weak<dyn LoxCallable>;
+    track(Node::Callable(weak));
+    callable
+}
+
+pub fn track_instance(instance: Rc<LoxInstance>) -> Rc<LoxInstance> {
+    track(Node::Instance(Rc::downgrade(&instance)));
+    instance
+}
+
+pub fn track_list(list: Rc<RefCell<Vec<Object>>>) -> Rc<RefCell<Vec<Object>>> {
+    track(Node::List(Rc::downgrade(&list)));
+    list
+}
+
+pub fn track_map(map: Rc<RefCell<LoxMap>>) -> Rc<RefCell<LoxMap>> {
+    track(Node::Map(Rc::downgrade(&map)));
+    map
+}
+
+pub fn track_upvalue(upvalue: Rc<RefCell<Upvalue>>) -> Rc<RefCell<Upvalue>> {
+    track(Node::Upvalue(Rc::downgrade(&upvalue)));
+    upvalue
+}
+
+pub fn add_globals(globals: &EnvRef) {
+    HEAP.with(|heap| heap.borrow_mut().globals.push(Rc::downgrade(globals)));
+}
+
+pub fn push_active(env: EnvRef) {
+    HEAP.with(|heap| heap.borrow_mut().active.push(env));
+}
+
+pub fn pop_active() {
+    HEAP.with(|heap| heap.borrow_mut().active.pop());
+}
+
+/// Collects on every allocation when on, to flush out objects the collector can't see.
+pub fn set_stress(stress: bool) {
+    HEAP.with(|heap| heap.borrow_mut().stress = stress);
+}
+
+pub fn stats() -> GcStats {
+    HEAP.with(|heap| {
+        let heap = heap.borrow();
+        GcStats {
+            live: heap
+                .nodes
+                .iter()
+                .filter(|node| node.strong_count() > 0)
+                .count(),
+            ..heap.stats
+        }
+    })
+}
+
+/// Runs a full collection, returning how many objects it freed.
+pub fn collect() -> usize {
+    // the nodes are taken out of the heap so it isn't borrowed while tracing and clearing
+    let (mut nodes, roots) = HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.globals.retain(|globals| globals.strong_count() > 0);
+        let mut roots: Vec<usize> = heap.globals.iter().map(|g| g.as_ptr() as usize).collect();
+        roots.extend(heap.active.iter().map(address));
+        (std::mem::take(&mut heap.nodes), roots)
+    });
+    nodes.retain(|node| node.strong_count() > 0);
+    let before = nodes.len();
+
+    // a node tracked twice would have its references counted twice
+    let mut index = HashMap::with_capacity(nodes.len());
+    let mut unique = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        if let Entry::Vacant(entry) = index.entry(node.address()) {
+            entry.insert(unique.len());
+            unique.push(node);
+        }
+    }
+    let nodes = unique;
+
+    let edges: Vec<Option<Vec<usize>>> = nodes.iter().map(Node::trace).collect();
+    let mut internal = vec![0; nodes.len()];
+    for target in edges.iter().flatten().flatten() {
+        if let Some(&i) = index.get(target) {
+            internal[i] += 1;
+        }
+    }
+
+    let mut marked = vec![false; nodes.len()];
+    let mut pending: Vec<usize> = roots
+        .iter()
+        .filter_map(|root| index.get(root).copied())
+        .collect();
+    for (i, node) in nodes.iter().enumerate() {
+        let held_from_outside = node.strong_count() > internal[i];
+        if held_from_outside || edges[i].is_none() {
+            pending.push(i);
+        }
+    }
+    while let Some(i) = pending.pop() {
+        if marked[i] {
+            continue;
+        }
+        marked[i] = true;
+        for target in edges[i].iter().flatten() {
+            if let Some(&j) = index.get(target) {
+                pending.push(j);
+            }
+        }
+    }
+
+    for (node, marked) in nodes.iter().zip(&marked) {
+        if !marked {
+            node.clear();
+        }
+    }
+    let mut nodes = nodes;
+    nodes.retain(|node| node.strong_count() > 0);
+    let freed = before - nodes.len();
+
+    HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        let live = nodes.len();
+        // objects allocated while sweeping were tracked in the meantime
+        nodes.append(&mut heap.nodes);
+        heap.nodes = nodes;
+        heap.stats.collections += 1;
+        heap.stats.freed += freed;
+        heap.next_collection = (live * 2).max(MIN_THRESHOLD);
+    });
+    freed
+}
@@ -1,57 +1,189 @@
-use crate::compiler::control_flow::{ControlFlow, FlowResult, extract_value, ok, return_value};
-use crate::compiler::env::{Env, EnvGuard, EnvRef};
-use crate::compiler::error::{LoxError, Result};
-use crate::compiler::expr::ExprVisitor;
-use crate::compiler::expr::Object;
-use crate::compiler::expr::{Binary, Grouping, Literal, LoxCallable, Ternary, Unary, Variable};
+use crate::compiler::control_flow::{
+    ControlFlow, FlowResult, PendingCall, break_loop, continue_loop, extract_value, ok,
+    return_value, tail_call,
+};
+use crate::compiler::env::{Env, EnvGuard, EnvRef, Slot};
+use crate::compiler::error::{LoxError, LoxErrorKind, Result, StackFrame};
+use crate::compiler::error_codes::ErrorCode;
+use crate::compiler::expr::{Binary, Grouping, Literal, LoxCallable, LoxMethod, Ternary, Unary};
+use crate::compiler::expr::{Expr, ExprVisitor};
+use crate::compiler::expr::{ExprId, Object};
+use crate::compiler::gc;
 use crate::compiler::lox_class::LoxClass;
 use crate::compiler::lox_function::LoxFunction;
-use crate::compiler::natives::ClockFunction;
+use crate::compiler::lox_map::LoxMap;
+use crate::compiler::natives::{
+    ClockFunction, DeleteFunction, HasFunction, InsertFunction, KeysFunction, LenFunction,
+    PopFunction, PushFunction, RemoveFunction, ValuesFunction, list_index, map_key,
+};
 use crate::compiler::stmt::Stmt;
 use crate::compiler::stmt::StmtVisitor;
 use crate::compiler::token::{Token, TokenType};
-use std::cell::RefCell;
+use crate::compiler::vm::{FunctionProto, Vm, compile, compile_expression};
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::rc::Rc;
 
+// How many calls may be active at once before a call fails with "Stack overflow."
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+// The deepest limit the command line accepts. Its thread already asks for ~650MB of stack;
+// much more and the OS refuses to start it.
+pub const MAX_CALL_DEPTH_LIMIT: usize = 10_000;
+
+// Generous estimate of the native stack one Lox call needs; unoptimized builds use ~20KB
+// per call, plus whatever deeply nested expressions inside the body take
+const STACK_BYTES_PER_CALL: usize = 64 * 1024;
+const BASE_STACK_BYTES: usize = 8 * 1024 * 1024;
+
+// The stack an interpreter assumes its thread has until told otherwise: what Rust gives
+// threads it spawns, and less than a main thread gets
+const DEFAULT_STACK_SIZE: usize = 2 * 1024 * 1024;
+// left free for whatever a call evaluates before it makes the next one
+const STACK_HEADROOM: usize = 256 * 1024;
+
+// A call in progress. Naming the callee means formatting a string, so that only happens
+// when an error actually needs a StackFrame.
+pub struct ActiveCall {
+    pub callable: Rc<dyn LoxCallable>,
+    pub call_site: Token,
+}
+
+impl ActiveCall {
+    pub fn to_frame(&self) -> StackFrame {
+        StackFrame {
+            function: self.callable.to_string(),
+            call_site: self.call_site.clone(),
+            line: self.call_site.line,
+        }
+    }
+}
+
+// How programs get run: by walking the AST, or by compiling it to bytecode for the stack
+// VM in `vm`. Both share the globals, natives and call depth limit of an Interpreter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    TreeWalk,
+    Bytecode,
+}
+
+impl Backend {
+    /// Parses the names used by `--backend=` and `LOX_BACKEND`.
+    pub fn from_name(name: &str) -> Option<Backend> {
+        match name {
+            "tree" => Some(Backend::TreeWalk),
+            "vm" => Some(Backend::Bytecode),
+            _ => None,
+        }
+    }
+}
+
 pub struct Interpreter {
     // Interpreter state will go here
     pub _globals: EnvRef,
     pub env: RefCell<EnvRef>, // allows for us to mutate the environment by borrowing it mutably
-    pub locals: RefCell<HashMap<Token, usize>>,
+    // where each resolved local lives, keyed by the expression that refers to it;
+    // anything missing is a global
+    pub locals: RefCell<HashMap<ExprId, Slot>>,
+    // calls currently being executed, outermost first; natives get a frame too
+    pub frames: RefCell<Vec<ActiveCall>>,
+    max_call_depth: Cell<usize>,
+    // native stack calls may use, and where it was when the outermost call started
+    stack_size: Cell<usize>,
+    stack_base: Cell<usize>,
+    backend: Cell<Backend>,
 }
 
 impl Interpreter {
     pub fn new() -> Self {
         let globals = Env::new_global();
+        gc::add_globals(&globals);
 
         // add native functions here
         // let's add one for counting time
-        globals.borrow_mut().define(
-            "clock".to_string(),
-            Object::Function(Rc::new(ClockFunction)),
-        );
+        globals
+            .borrow_mut()
+            .define("clock", Object::Function(Rc::new(ClockFunction)));
+
+        // list and map helpers
+        let collection_natives: [(&str, Rc<dyn LoxCallable>); 9] = [
+            ("len", Rc::new(LenFunction)),
+            ("push", Rc::new(PushFunction)),
+            ("pop", Rc::new(PopFunction)),
+            ("insert", Rc::new(InsertFunction)),
+            ("remove", Rc::new(RemoveFunction)),
+            ("keys", Rc::new(KeysFunction)),
+            ("values", Rc::new(ValuesFunction)),
+            ("has", Rc::new(HasFunction)),
+            ("delete", Rc::new(DeleteFunction)),
+        ];
+        for (name, native) in collection_natives {
+            globals.borrow_mut().define(name, Object::Function(native));
+        }
 
         Interpreter {
             _globals: globals.clone(),
             env: RefCell::new(globals),
             locals: RefCell::new(HashMap::new()),
+            frames: RefCell::new(Vec::new()),
+            max_call_depth: Cell::new(DEFAULT_MAX_CALL_DEPTH),
+            stack_size: Cell::new(DEFAULT_STACK_SIZE),
+            stack_base: Cell::new(0),
+            backend: Cell::new(Backend::TreeWalk),
         }
     }
 
-    pub fn look_up_variable(&self, name: &Token, expr: &Variable) -> Result<Object> {
-        let locals = self.locals.borrow();
-        let distance = locals.get(name);
+    pub fn max_call_depth(&self) -> usize {
+        self.max_call_depth.get()
+    }
+
+    /// Limits how deeply calls may nest. Calls are evaluated recursively, so the thread
+    /// running the interpreter needs `Interpreter::stack_size_for(depth)` bytes of stack,
+    /// passed on with `set_stack_size`, to reach the limit. On a smaller stack, calls fail
+    /// with "Stack overflow." once they run short of it instead.
+    pub fn set_max_call_depth(&self, depth: usize) {
+        self.max_call_depth.set(depth);
+    }
+
+    /// Tells the interpreter how much native stack its thread has. Calls fail with "Stack
+    /// overflow." when they come close to using it up, so the thread never overflows. The
+    /// default suits a thread spawned with Rust's default stack size.
+    pub fn set_stack_size(&self, bytes: usize) {
+        self.stack_size.set(bytes);
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.backend.get()
+    }
+
+    pub fn set_backend(&self, backend: Backend) {
+        self.backend.set(backend);
+    }
+
+    /// Stack size for a thread that runs Lox code with the given maximum call depth.
+    pub fn stack_size_for(depth: usize) -> usize {
+        BASE_STACK_BYTES.saturating_add(depth.saturating_mul(STACK_BYTES_PER_CALL))
+    }
 
-        if let Some(distance) = distance {
-            self.env.borrow().borrow().get_at(*distance, &name.lexeme)
+    pub fn look_up_variable(&self, id: ExprId, name: &Token) -> Result<Object> {
+        let slot = self.locals.borrow().get(&id).copied();
+
+        if let Some(slot) = slot {
+            self.env.borrow().borrow().get_at(slot.depth, slot.index)
         } else {
             self._globals.borrow().get(&name.lexeme, name)
         }
     }
 
-    pub fn resolve(&self, expr: &Token, depth: usize) {
-        self.locals.borrow_mut().insert(expr.clone(), depth);
+    pub fn resolve(&self, id: ExprId, slot: Slot) {
+        self.locals.borrow_mut().insert(id, slot);
+    }
+
+    // evaluates a lone expression, used by the REPL to echo expression statements
+    pub fn evaluate(&self, expr: &Expr) -> Result<Object> {
+        match self.backend.get() {
+            Backend::TreeWalk => extract_value(expr.accept(self)),
+            Backend::Bytecode => Vm::new(self).run(compile_expression(expr)?),
+        }
     }
 
     fn execute(&mut self, statement: &Stmt) -> FlowResult<Object> {
@@ -59,6 +191,11 @@ impl Interpreter {
     }
 
     pub fn interpret(&mut self, statements: Vec<Stmt>) -> Result<()> {
+        if self.backend.get() == Backend::Bytecode {
+            let script = compile(&statements)?;
+            return Vm::new(self).run(script).map(|_| ());
+        }
+
         for statement in statements.iter() {
             let (_, flow) = self.execute(statement)?;
 
@@ -68,13 +205,20 @@ impl Interpreter {
                     // You'll need a dummy token here
                     Token::new(TokenType::RETURN, "return".to_string(), 0, None),
                     "Cannot return from top-level code.",
-                ));
+                )
+                .with_code(ErrorCode::TopLevelReturn));
             }
         }
         Ok(())
     }
 
-    fn is_truthy(object: Object) -> bool {
+    /// Runs an already compiled script on the VM, whichever backend is selected; used for
+    /// `.loxc` files.
+    pub fn run_compiled(&self, script: Rc<FunctionProto>) -> Result<()> {
+        Vm::new(self).run(script).map(|_| ())
+    }
+
+    pub fn is_truthy(object: Object) -> bool {
         match object {
             Object::Nil => false,
             Object::Boolean(b) => b,
@@ -82,12 +226,258 @@ impl Interpreter {
         }
     }
 
+    /// Applies a unary operator to an evaluated operand. Shared with the bytecode VM so
+    /// both backends agree on results and error messages.
+    pub fn unary_op(operator: &Token, right: Object) -> Result<Object> {
+        match operator.token_type {
+            TokenType::MINUS => {
+                if let Object::Number(n) = right {
+                    Ok(Object::Number(-n))
+                } else {
+                    Err(LoxError::new_runtime(
+                        operator.clone(),
+                        "Unary minus can only be applied to numbers",
+                    )
+                    .with_code(ErrorCode::OperandType))
+                }
+            }
+            TokenType::BANG => Ok(Object::Boolean(!Interpreter::is_truthy(right))),
+            _ => Err(LoxError::new_runtime(
+                operator.clone(),
+                &format!("Unknown unary operator: {:?}", operator.token_type),
+            )
+            .with_code(ErrorCode::OperandType)),
+        }
+    }
+
+    /// Applies a binary operator to evaluated operands, see `unary_op`.
+    pub fn binary_op(operator: &Token, left: Object, right: Object) -> Result<Object> {
+        match operator.token_type {
+            // basic arithmetic ops
+            TokenType::MINUS => {
+                if let (Object::Number(l), Object::Number(r)) = (left, right) {
+                    Ok(Object::Number(l - r))
+                } else {
+                    Err(LoxError::new_runtime(
+                        operator.clone(),
+                        "Binary minus can only be applied to numbers",
+                    )
+                    .with_code(ErrorCode::OperandType))
+                }
+            }
+            TokenType::PLUS => match (&left, &right) {
+                (Object::Number(l), Object::Number(r)) => Ok(Object::Number(*l + *r)),
+                // the result is interned like any other string
+                (Object::String(l), Object::String(r)) => {
+                    Ok(Object::String(format!("{}{}", l, r).into()))
+                }
+                (Object::String(l), Object::Number(r)) => {
+                    Ok(Object::String(format!("{}{}", l, r).into()))
+                }
+                (Object::Number(l), Object::String(r)) => {
+                    Ok(Object::String(format!("{}{}", l, r).into()))
+                }
+                _ => Err(LoxError::new_runtime(
+                    operator.clone(),
+                    "Binary plus can only be applied to numbers or strings",
+                )
+                .with_code(ErrorCode::OperandType)),
+            },
+            TokenType::SLASH => {
+                if let (Object::Number(l), Object::Number(r)) = (left, right) {
+                    if r != 0.0 {
+                        Ok(Object::Number(l / r))
+                    } else {
+                        Err(LoxError::new_runtime(operator.clone(), "Division by zero")
+                            .with_code(ErrorCode::DivisionByZero))
+                    }
+                } else {
+                    Err(LoxError::new_runtime(
+                        operator.clone(),
+                        "Binary slash can only be applied to numbers",
+                    )
+                    .with_code(ErrorCode::OperandType))
+                }
+            }
+
+            TokenType::STAR => {
+                if let (Object::Number(l), Object::Number(r)) = (left, right) {
+                    Ok(Object::Number(l * r))
+                } else {
+                    Err(LoxError::new_runtime(
+                        operator.clone(),
+                        "Binary star can only be applied to numbers",
+                    )
+                    .with_code(ErrorCode::OperandType))
+                }
+            }
+
+            // comparison ops
+            //
+            TokenType::GREATER => {
+                if let (Object::Number(l), Object::Number(r)) = (left, right) {
+                    Ok(Object::Boolean(l > r))
+                } else {
+                    Err(LoxError::new_runtime(
+                        operator.clone(),
+                        "Binary greater can only be applied to numbers",
+                    )
+                    .with_code(ErrorCode::OperandType))
+                }
+            }
+
+            TokenType::GREATER_EQUAL => {
+                if let (Object::Number(l), Object::Number(r)) = (left, right) {
+                    Ok(Object::Boolean(l >= r))
+                } else {
+                    Err(LoxError::new_runtime(
+                        operator.clone(),
+                        "Binary greater equal can only be applied to numbers",
+                    )
+                    .with_code(ErrorCode::OperandType))
+                }
+            }
+
+            TokenType::LESS => {
+                if let (Object::Number(l), Object::Number(r)) = (left, right) {
+                    Ok(Object::Boolean(l < r))
+                } else {
+                    Err(LoxError::new_runtime(
+                        operator.clone(),
+                        "Binary less can only be applied to numbers",
+                    )
+                    .with_code(ErrorCode::OperandType))
+                }
+            }
+
+            TokenType::LESS_EQUAL => {
+                if let (Object::Number(l), Object::Number(r)) = (left, right) {
+                    Ok(Object::Boolean(l <= r))
+                } else {
+                    Err(LoxError::new_runtime(
+                        operator.clone(),
+                        "Binary less equal can only be applied to numbers",
+                    )
+                    .with_code(ErrorCode::OperandType))
+                }
+            }
+
+            TokenType::EQUAL_EQUAL => Ok(Object::Boolean(left == right)),
+
+            TokenType::BANG_EQUAL => Ok(Object::Boolean(left != right)),
+
+            _ => Err(LoxError::new_runtime(
+                operator.clone(),
+                &format!("Unknown binary operator: {:?}", operator.token_type),
+            )
+            .with_code(ErrorCode::OperandType)),
+        }
+    }
+
+    // evaluates the callee and arguments of a call expression and checks they go together
+    fn prepare_call(&self, call: &super::expr::Call) -> Result<PendingCall> {
+        let callee = call.callee.accept(self)?.0;
+        let mut args = Vec::new();
+        for arg in &call.args {
+            args.push(arg.accept(self)?.0);
+        }
+        let callable: Rc<dyn LoxCallable> = match callee {
+            Object::Function(function) => function,
+            Object::Class(class) => class,
+            _ => {
+                return Err(LoxError::new_runtime(
+                    call.paren.clone(),
+                    "Can only call functions and classes.",
+                )
+                .with_code(ErrorCode::NotCallable));
+            }
+        };
+
+        // Check arity first
+        if args.len() != callable.arity() {
+            return Err(LoxError::new_runtime(
+                call.paren.clone(),
+                &format!(
+                    "Expected {} arguments but got {}.",
+                    callable.arity(),
+                    args.len()
+                ),
+            )
+            .with_code(ErrorCode::ArityMismatch));
+        }
+
+        Ok(PendingCall {
+            callable,
+            args,
+            call_site: call.paren.clone(),
+        })
+    }
+
+    /// Makes a call, then keeps making the calls it hands back from a `return f(...);` in
+    /// tail position. This trampoline replaces the returning frame instead of nesting a new
+    /// one, so tail-recursive functions run in constant stack space.
+    pub fn call_function(&self, mut call: PendingCall) -> Result<Object> {
+        loop {
+            if self.frames.borrow().len() >= self.max_call_depth.get() || self.stack_exhausted() {
+                return Err(LoxError::new_runtime(call.call_site, "Stack overflow.")
+                    .with_code(ErrorCode::StackOverflow));
+            }
+
+            let PendingCall {
+                callable,
+                args,
+                call_site,
+            } = call;
+            self.frames.borrow_mut().push(ActiveCall {
+                callable: callable.clone(),
+                call_site,
+            });
+            let result = callable
+                .call_tail(self, &args)
+                .map_err(|error| self.attach_trace(error));
+            self.frames.borrow_mut().pop();
+
+            match result? {
+                (_, ControlFlow::TailCall(next)) => call = next,
+                (value, _) => return Ok(value),
+            }
+        }
+    }
+
+    // Whether the native stack used since the outermost call leaves too little room for
+    // another. The address of a local stands in for the stack pointer.
+    fn stack_exhausted(&self) -> bool {
+        let marker = 0u8;
+        let here = std::ptr::addr_of!(marker) as usize;
+        if self.frames.borrow().is_empty() {
+            self.stack_base.set(here);
+        }
+        let used = self.stack_base.get().abs_diff(here);
+        used.saturating_add(STACK_HEADROOM) > self.stack_size.get()
+    }
+
+    // the innermost call an error escapes from records the whole stack; outer calls see
+    // the trace already filled in and leave it alone
+    fn attach_trace(&self, mut error: LoxError) -> LoxError {
+        if error.kind == LoxErrorKind::Runtime && error.trace.is_empty() {
+            error.trace = self
+                .frames
+                .borrow()
+                .iter()
+                .rev()
+                .map(ActiveCall::to_frame)
+                .collect();
+        }
+        error
+    }
+
     pub fn execute_block(&self, statements: &Vec<Stmt>, new_env: EnvRef) -> FlowResult<Object> {
         let _guard = EnvGuard::new(self, new_env);
         for statement in statements.iter() {
             let (_, flow) = statement.accept(self)?;
-            if let ControlFlow::Return(value) = flow {
-                return return_value(value);
+            // returns, breaks and continues all unwind out of the block
+            if !matches!(flow, ControlFlow::None) {
+                return Ok((Object::Nil, flow));
             }
         }
         ok(Object::Nil)
@@ -96,47 +486,88 @@ impl Interpreter {
 
 impl StmtVisitor<FlowResult<Object>> for Interpreter {
     fn visit_class(&self, class: &super::stmt::Class) -> FlowResult<Object> {
-        self.env
-            .borrow()
-            .borrow_mut()
-            .define(class.name.lexeme.clone(), Object::Nil);
-        let lox_class = LoxClass {
-            name: class.name.lexeme.clone(),
+        let superclass = match &class.superclass {
+            Some(superclass_expr) => match superclass_expr.accept(self)?.0 {
+                Object::Class(superclass) => Some(superclass),
+                _ => {
+                    let token = match superclass_expr.as_ref() {
+                        Expr::Variable(variable) => variable.name.clone(),
+                        _ => class.name.as_ref().clone(),
+                    };
+                    return Err(LoxError::new_runtime(token, "Superclass must be a class.")
+                        .with_code(ErrorCode::SuperclassNotClass));
+                }
+            },
+            None => None,
+        };
+
+        // subclasses get an extra env layer holding "super" between the methods and the
+        // declaring env, matching the scope the resolver introduces
+        let method_env = match &superclass {
+            Some(superclass) => {
+                let env = Env::new_enclosed(self.env.borrow().clone());
+                env.borrow_mut()
+                    .define("super", Object::Class(superclass.clone()));
+                env
+            }
+            None => self.env.borrow().clone(),
         };
-        let class_obj = Object::Class(Rc::new(lox_class));
+
+        let mut methods: HashMap<String, Rc<dyn LoxMethod>> = HashMap::new();
+        for method in &class.methods {
+            if let Stmt::Function(function) = method {
+                // methods close over the env the class is declared in
+                let lox_function = gc::track_callable(LoxFunction::new(
+                    function.as_ref().clone(),
+                    method_env.clone(),
+                    function.name.lexeme == "init",
+                ));
+                methods.insert(function.name.lexeme.clone(), lox_function);
+            }
+        }
+        let lox_class = LoxClass::new(class.name.lexeme.clone(), superclass, methods);
+        let class_obj = Object::Class(gc::track_callable(lox_class));
+        // defined once everything else is evaluated so it takes exactly one slot; methods
+        // only look the name up when they run
         self.env
             .borrow()
             .borrow_mut()
-            .define(class.name.lexeme.clone(), class_obj);
+            .define(&class.name.lexeme, class_obj);
         ok(Object::Nil)
     }
 
+    fn visit_break(&self, _break_stmt: &super::stmt::Break) -> FlowResult<Object> {
+        break_loop()
+    }
+
+    fn visit_continue(&self, _continue_stmt: &super::stmt::Continue) -> FlowResult<Object> {
+        continue_loop()
+    }
+
     fn visit_return_stmt(&self, return_stmt: &super::stmt::ReturnStmt) -> FlowResult<Object> {
         // critical point where we have a different return type
-        return_value(return_stmt.value.accept(self)?.0)
+        match &return_stmt.value {
+            // hand the call to the enclosing call_function instead of making it here
+            Some(value) if return_stmt.tail_call.get() => match value.as_ref() {
+                Expr::Call(call) => tail_call(self.prepare_call(call)?),
+                _ => return_value(value.accept(self)?.0),
+            },
+            Some(value) => return_value(value.accept(self)?.0),
+            None => return_value(Object::Nil),
+        }
     }
 
     fn visit_function(&self, function: &super::stmt::Function) -> FlowResult<Object> {
-        // First, define the function in the current environment with a placeholder
-        // This allows recursive functions to find themselves in their closure
-        self.env
-            .borrow()
-            .borrow_mut()
-            .define(function.name.lexeme.clone(), Object::Nil);
-
-        // Now create function object using current env as closure (which includes the function)
-        let lox_function = LoxFunction {
-            declaration: function.clone(),
-            closure: self.env.borrow().clone(),
-        };
+        // the closure is the current env itself, so recursive calls find the function once
+        // it's defined below
+        let lox_function = LoxFunction::new(function.clone(), self.env.borrow().clone(), false);
         // make sure to create a new shared reference to the function object
-        let function_obj = Object::Function(Rc::new(lox_function));
+        let function_obj = Object::Function(gc::track_callable(lox_function));
 
-        // Replace the placeholder with the actual function object
         self.env
             .borrow()
             .borrow_mut()
-            .define(function.name.lexeme.clone(), function_obj);
+            .define(&function.name.lexeme, function_obj);
 
         ok(Object::Nil)
     }
@@ -144,8 +575,16 @@ impl StmtVisitor<FlowResult<Object>> for Interpreter {
     fn visit_while_stmt(&self, while_stmt: &super::stmt::WhileStmt) -> FlowResult<Object> {
         while Interpreter::is_truthy(while_stmt.condition.accept(self)?.0) {
             let (_, flow) = while_stmt.body.accept(self)?;
-            if let ControlFlow::Return(value) = flow {
-                return return_value(value);
+            match flow {
+                ControlFlow::Return(value) => return return_value(value),
+                flow @ ControlFlow::TailCall(_) => return Ok((Object::Nil, flow)),
+                ControlFlow::Break => break,
+                // continue falls through so the for-loop increment still runs
+                ControlFlow::Continue | ControlFlow::None => {}
+            }
+
+            if let Some(increment) = &while_stmt.increment {
+                increment.accept(self)?;
             }
         }
         ok(Object::Nil)
@@ -155,14 +594,10 @@ impl StmtVisitor<FlowResult<Object>> for Interpreter {
         let cond = if_stmt.condition.accept(self)?;
         if Interpreter::is_truthy(cond.0) {
             let result = if_stmt.then_branch.accept(self)?;
-            if let ControlFlow::Return(value) = result.1 {
-                return return_value(value);
-            }
+            return Ok((Object::Nil, result.1));
         } else if let Some(else_branch) = &if_stmt.else_branch {
             let result = else_branch.accept(self)?;
-            if let ControlFlow::Return(value) = result.1 {
-                return return_value(value);
-            }
+            return Ok((Object::Nil, result.1));
         }
         ok(Object::Nil)
     }
@@ -178,7 +613,7 @@ impl StmtVisitor<FlowResult<Object>> for Interpreter {
         self.env
             .borrow() //immutable borrow for reading the environment
             .borrow_mut() //mutable borrow for the environment inside pointer
-            .define(var.name.lexeme.clone(), value.0);
+            .define(&var.name.lexeme, value.0);
         ok(Object::Nil)
     }
 
@@ -194,35 +629,168 @@ impl StmtVisitor<FlowResult<Object>> for Interpreter {
 }
 
 impl ExprVisitor<FlowResult<Object>> for Interpreter {
-    fn visit_call(&self, call: &super::expr::Call) -> FlowResult<Object> {
-        let callee = call.callee.accept(self)?.0;
-        let mut args = Vec::new();
-        for arg in &call.args {
-            args.push(arg.accept(self)?.0);
+    fn visit_get(&self, get: &super::expr::Get) -> FlowResult<Object> {
+        let object = get.object.accept(self)?.0;
+        if let Object::Instance(instance) = object {
+            return ok(instance.get(&get.name)?);
+        }
+
+        Err(
+            LoxError::new_runtime(get.name.clone(), "Only instances have properties.")
+                .with_code(ErrorCode::PropertyOnNonInstance),
+        )
+    }
+
+    fn visit_super(&self, sup: &super::expr::Super) -> FlowResult<Object> {
+        let slot = match self.locals.borrow().get(&sup.id) {
+            Some(slot) => *slot,
+            None => {
+                return Err(LoxError::new_runtime(
+                    sup.keyword.clone(),
+                    "Unresolved 'super' expression.",
+                ));
+            }
+        };
+
+        let env = self.env.borrow().clone();
+        let superclass = match env.borrow().get_at(slot.depth, slot.index)? {
+            Object::Class(superclass) => superclass,
+            _ => {
+                return Err(LoxError::new_runtime(
+                    sup.keyword.clone(),
+                    "Superclass must be a class.",
+                )
+                .with_code(ErrorCode::SuperclassNotClass));
+            }
+        };
+
+        // "this" is always the only slot of the env just inside the one holding "super"
+        let instance = match env.borrow().get_at(slot.depth - 1, 0)? {
+            Object::Instance(instance) => instance,
+            _ => {
+                return Err(LoxError::new_runtime(
+                    sup.keyword.clone(),
+                    "Can't use 'super' without an instance.",
+                ));
+            }
+        };
+
+        match superclass.find_method(&sup.method.lexeme) {
+            Some(method) => ok(Object::Function(method.bind(instance))),
+            None => Err(LoxError::new_runtime(
+                sup.method.clone(),
+                &format!("Undefined property '{}'.", sup.method.lexeme),
+            )
+            .with_code(ErrorCode::UndefinedProperty)),
+        }
+    }
+
+    fn visit_list(&self, list: &super::expr::List) -> FlowResult<Object> {
+        let mut elements = Vec::with_capacity(list.elements.len());
+        for element in &list.elements {
+            elements.push(element.accept(self)?.0);
         }
-        match callee {
-            Object::Function(function) => {
-                // Check arity first
-                if args.len() != function.arity() {
-                    return Err(LoxError::new_runtime(
-                        call.paren.clone(),
-                        &format!(
-                            "Expected {} arguments but got {}.",
-                            function.arity(),
-                            args.len()
-                        ),
-                    ));
+        let list = gc::track_list(Rc::new(RefCell::new(elements)));
+        ok(Object::List(list))
+    }
+
+    fn visit_lambda(&self, lambda: &super::expr::Lambda) -> FlowResult<Object> {
+        // lambdas close over the env they are evaluated in, just like named functions
+        let lox_function =
+            LoxFunction::new(lambda.declaration.clone(), self.env.borrow().clone(), false);
+        ok(Object::Function(gc::track_callable(lox_function)))
+    }
+
+    fn visit_map(&self, map: &super::expr::Map) -> FlowResult<Object> {
+        let mut lox_map = LoxMap::new();
+        for (key_expr, value_expr) in &map.entries {
+            let key = key_expr.accept(self)?.0;
+            let value = value_expr.accept(self)?.0;
+            lox_map.insert(map_key(&key, &map.brace)?, value);
+        }
+        ok(Object::Map(gc::track_map(Rc::new(RefCell::new(lox_map)))))
+    }
+
+    fn visit_index_get(&self, index_get: &super::expr::IndexGet) -> FlowResult<Object> {
+        let object = index_get.object.accept(self)?.0;
+        let index = index_get.index.accept(self)?.0;
+
+        match object {
+            Object::List(list) => {
+                let list = list.borrow();
+                let i = list_index(&index, list.len(), &index_get.bracket)?;
+                ok(list[i].clone())
+            }
+            Object::Map(map) => {
+                let key = map_key(&index, &index_get.bracket)?;
+                match map.borrow().get(&key) {
+                    Some(value) => ok(value.clone()),
+                    None => Err(LoxError::new_runtime(
+                        index_get.bracket.clone(),
+                        &format!("Undefined key '{}'.", index),
+                    )
+                    .with_code(ErrorCode::UndefinedKey)),
                 }
-                ok(function.call(self, &args)?)
             }
-            Object::Class(class) => ok(class.call(self, &args)?),
             _ => Err(LoxError::new_runtime(
-                call.paren.clone(),
-                "Can only call functions.",
-            )),
+                index_get.bracket.clone(),
+                "Only lists and maps can be indexed.",
+            )
+            .with_code(ErrorCode::NotIndexable)),
+        }
+    }
+
+    fn visit_index_set(&self, index_set: &super::expr::IndexSet) -> FlowResult<Object> {
+        let object = index_set.object.accept(self)?.0;
+        let index = index_set.index.accept(self)?.0;
+        let value = index_set.value.accept(self)?.0;
+
+        match object {
+            Object::List(list) => {
+                let mut list = list.borrow_mut();
+                let i = list_index(&index, list.len(), &index_set.bracket)?;
+                list[i] = value.clone();
+                ok(value)
+            }
+            Object::Map(map) => {
+                let key = map_key(&index, &index_set.bracket)?;
+                map.borrow_mut().insert(key, value.clone());
+                ok(value)
+            }
+            _ => Err(LoxError::new_runtime(
+                index_set.bracket.clone(),
+                "Only lists and maps can be indexed.",
+            )
+            .with_code(ErrorCode::NotIndexable)),
         }
     }
 
+    fn visit_this(&self, this: &super::expr::This) -> FlowResult<Object> {
+        ok(self.look_up_variable(this.id, &this.keyword)?)
+    }
+
+    fn visit_set(&self, set: &super::expr::Set) -> FlowResult<Object> {
+        let object = set.object.accept(self)?.0;
+        let instance = match object {
+            Object::Instance(instance) => instance,
+            _ => {
+                return Err(
+                    LoxError::new_runtime(set.name.clone(), "Only instances have fields.")
+                        .with_code(ErrorCode::PropertyOnNonInstance),
+                );
+            }
+        };
+
+        let value = set.value.accept(self)?.0;
+        instance.set(&set.name, value.clone());
+        ok(value)
+    }
+
+    fn visit_call(&self, call: &super::expr::Call) -> FlowResult<Object> {
+        let call = self.prepare_call(call)?;
+        ok(self.call_function(call)?)
+    }
+
     fn visit_logical(&self, logical: &super::expr::Logical) -> FlowResult<Object> {
         // need to short circuit after evaluating left
         let left = logical.left.accept(self)?;
@@ -244,30 +812,25 @@ impl ExprVisitor<FlowResult<Object>> for Interpreter {
     fn visit_assign(&self, assign: &super::expr::Assign) -> FlowResult<Object> {
         let value = assign.value.accept(self)?;
 
-        let distance = self.locals.borrow();
-        let distance = distance.get(&assign.name);
-        if let Some(distance) = distance {
-            self.env
-                .borrow()
-                .borrow_mut()
-                .assign_at(*distance, &assign.name, value.0.clone())?;
-        } else {
-            self.env
-                .borrow()
-                .borrow_mut()
-                .assign(&assign.name, value.0.clone())?;
+        let slot = self.locals.borrow().get(&assign.id).copied();
+        match slot {
+            Some(slot) => {
+                self.env
+                    .borrow()
+                    .borrow_mut()
+                    .assign_at(slot.depth, slot.index, value.0.clone())?
+            }
+            None => {
+                self._globals
+                    .borrow_mut()
+                    .assign(&assign.name, value.0.clone())?;
+            }
         }
-
-        let cloned_value = value.0.clone();
-        self.env
-            .borrow()
-            .borrow_mut()
-            .assign(&assign.name, value.0)?;
-        ok(cloned_value)
+        ok(value.0)
     }
 
     fn visit_variable(&self, variable: &super::expr::Variable) -> FlowResult<Object> {
-        ok(self.look_up_variable(&variable.name, variable)?)
+        ok(self.look_up_variable(variable.id, &variable.name)?)
     }
 
     fn visit_literal(&self, literal: &Literal) -> FlowResult<Object> {
@@ -279,6 +842,8 @@ impl ExprVisitor<FlowResult<Object>> for Interpreter {
             Object::Function(ref f) => ok(Object::Function(f.clone())),
             Object::Class(ref c) => ok(Object::Class(c.clone())),
             Object::Instance(ref i) => ok(Object::Instance(i.clone())),
+            Object::List(ref l) => ok(Object::List(l.clone())),
+            Object::Map(ref m) => ok(Object::Map(m.clone())),
             // Use a dummy token since Literal has no operator
             Object::Error(ref msg) => {
                 use crate::compiler::token::{Token, TokenType};
@@ -290,24 +855,7 @@ impl ExprVisitor<FlowResult<Object>> for Interpreter {
 
     fn visit_unary(&self, unary: &Unary) -> FlowResult<Object> {
         let right = unary.right.accept(self)?.0;
-
-        match unary.operator.token_type {
-            TokenType::MINUS => {
-                if let Object::Number(n) = right {
-                    ok(Object::Number(-n))
-                } else {
-                    Err(LoxError::new_runtime(
-                        unary.operator.clone(),
-                        "Unary minus can only be applied to numbers",
-                    ))
-                }
-            }
-            TokenType::BANG => ok(Object::Boolean(!Interpreter::is_truthy(right))),
-            _ => Err(LoxError::new_runtime(
-                unary.operator.clone(),
-                &format!("Unknown unary operator: {:?}", unary.operator.token_type),
-            )),
-        }
+        ok(Interpreter::unary_op(&unary.operator, right)?)
     }
 
     fn visit_grouping(&self, grouping: &Grouping) -> FlowResult<Object> {
@@ -317,115 +865,7 @@ impl ExprVisitor<FlowResult<Object>> for Interpreter {
     fn visit_binary(&self, binary: &Binary) -> FlowResult<Object> {
         let left = binary.left.accept(self)?.0;
         let right = binary.right.accept(self)?.0;
-
-        match binary.operator.token_type {
-            // basic arithmetic ops
-            TokenType::MINUS => {
-                if let (Object::Number(l), Object::Number(r)) = (left, right) {
-                    ok(Object::Number(l - r))
-                } else {
-                    Err(LoxError::new_runtime(
-                        binary.operator.clone(),
-                        "Binary minus can only be applied to numbers",
-                    ))
-                }
-            }
-            TokenType::PLUS => match (&left, &right) {
-                (Object::Number(l), Object::Number(r)) => ok(Object::Number(*l + *r)),
-                (Object::String(l), Object::String(r)) => ok(Object::String(l.clone() + r)),
-                (Object::String(l), Object::Number(r)) => {
-                    ok(Object::String(l.clone() + &r.to_string()))
-                }
-                (Object::Number(l), Object::String(r)) => ok(Object::String(l.to_string() + r)),
-                _ => Err(LoxError::new_runtime(
-                    binary.operator.clone(),
-                    "Binary plus can only be applied to numbers or strings",
-                )),
-            },
-            TokenType::SLASH => {
-                if let (Object::Number(l), Object::Number(r)) = (left, right) {
-                    if r != 0.0 {
-                        ok(Object::Number(l / r))
-                    } else {
-                        Err(LoxError::new_runtime(
-                            binary.operator.clone(),
-                            "Division by zero",
-                        ))
-                    }
-                } else {
-                    Err(LoxError::new_runtime(
-                        binary.operator.clone(),
-                        "Binary slash can only be applied to numbers",
-                    ))
-                }
-            }
-
-            TokenType::STAR => {
-                if let (Object::Number(l), Object::Number(r)) = (left, right) {
-                    ok(Object::Number(l * r))
-                } else {
-                    Err(LoxError::new_runtime(
-                        binary.operator.clone(),
-                        "Binary star can only be applied to numbers",
-                    ))
-                }
-            }
-
-            // comparison ops
-            //
-            TokenType::GREATER => {
-                if let (Object::Number(l), Object::Number(r)) = (left, right) {
-                    ok(Object::Boolean(l > r))
-                } else {
-                    Err(LoxError::new_runtime(
-                        binary.operator.clone(),
-                        "Binary greater can only be applied to numbers",
-                    ))
-                }
-            }
-
-            TokenType::GREATER_EQUAL => {
-                if let (Object::Number(l), Object::Number(r)) = (left, right) {
-                    ok(Object::Boolean(l >= r))
-                } else {
-                    Err(LoxError::new_runtime(
-                        binary.operator.clone(),
-                        "Binary greater equal can only be applied to numbers",
-                    ))
-                }
-            }
-
-            TokenType::LESS => {
-                if let (Object::Number(l), Object::Number(r)) = (left, right) {
-                    ok(Object::Boolean(l < r))
-                } else {
-                    Err(LoxError::new_runtime(
-                        binary.operator.clone(),
-                        "Binary less can only be applied to numbers",
-                    ))
-                }
-            }
-
-            TokenType::LESS_EQUAL => {
-                if let (Object::Number(l), Object::Number(r)) = (left, right) {
-                    ok(Object::Boolean(l <= r))
-                } else {
-                    Err(LoxError::new_runtime(
-                        binary.operator.clone(),
-                        "Binary less equal can only be applied to numbers",
-                    ))
-                }
-            }
-
-            TokenType::EQUAL_EQUAL => ok(Object::Boolean(left == right)),
-
-            TokenType::BANG_EQUAL => ok(Object::Boolean(left != right)),
-
-            _ => Err(LoxError::new_runtime(
-                binary.operator.clone(),
-                &format!("Unknown binary operator: {:?}", binary.operator.token_type),
-            )),
-        }
+        ok(Interpreter::binary_op(&binary.operator, left, right)?)
     }
 
     fn visit_ternary(&self, _ternary: &Ternary) -> FlowResult<Object> {
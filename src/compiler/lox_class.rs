@@ -1,28 +1,79 @@
-use crate::compiler::expr::{LoxCallable, Object};
+use crate::compiler::expr::{LoxCallable, LoxMethod, Object};
+use crate::compiler::gc::{self, Tracer};
 use crate::compiler::lox_instance::LoxInstance;
+use std::collections::HashMap;
 use std::fmt;
 use std::rc::Rc;
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct LoxClass {
     pub name: String,
+    pub superclass: Option<Rc<LoxClass>>,
+    pub methods: HashMap<String, Rc<dyn LoxMethod>>,
 }
 
 impl LoxClass {
-    fn new(name: String) -> Self {
-        LoxClass { name }
+    pub fn new(
+        name: String,
+        superclass: Option<Rc<LoxClass>>,
+        methods: HashMap<String, Rc<dyn LoxMethod>>,
+    ) -> Self {
+        LoxClass {
+            name,
+            superclass,
+            methods,
+        }
+    }
+
+    // walk up the superclass chain until some class defines the method
+    pub fn find_method(&self, name: &str) -> Option<Rc<dyn LoxMethod>> {
+        if let Some(method) = self.methods.get(name) {
+            return Some(method.clone());
+        }
+
+        match &self.superclass {
+            Some(superclass) => superclass.find_method(name),
+            None => None,
+        }
+    }
+}
+
+// methods close over the env the class lives in, so a derived Debug would recurse forever
+impl fmt::Debug for LoxClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
     }
 }
 
 impl LoxCallable for LoxClass {
     fn call(&self, interpreter: &super::Interpreter, args: &[Object]) -> super::Result<Object> {
-        let instance: LoxInstance = LoxInstance {
-            klass: Box::new(self.clone()),
-        };
-        Ok(Object::Instance(Rc::new(instance)))
+        let instance = gc::track_instance(Rc::new(LoxInstance::new(Box::new(self.clone()))));
+
+        // run the initializer against the fresh instance if the class declares one
+        if let Some(initializer) = self.find_method("init") {
+            initializer.bind(instance.clone()).call(interpreter, args)?;
+        }
+
+        Ok(Object::Instance(instance))
     }
 
     fn arity(&self) -> usize {
-        return 0;
+        match self.find_method("init") {
+            Some(initializer) => initializer.arity(),
+            None => 0,
+        }
+    }
+
+    fn to_string(&self) -> String {
+        self.name.clone()
+    }
+
+    fn trace(&self, tracer: &mut Tracer) {
+        if let Some(superclass) = &self.superclass {
+            tracer.edge(superclass);
+        }
+        for method in self.methods.values() {
+            tracer.edge(method);
+        }
     }
 }
@@ -1,59 +1,102 @@
-use crate::compiler::Interpreter;
 use crate::compiler::env::{Env, EnvGuard, EnvRef};
-use crate::compiler::expr::{LoxCallable, Object};
-use crate::compiler::stmt::{Function, ReturnStmt, Stmt};
+use crate::compiler::expr::{LoxCallable, LoxMethod, Object};
+use crate::compiler::gc::{self, Tracer};
+use crate::compiler::lox_instance::LoxInstance;
+use crate::compiler::stmt::Function;
+use crate::compiler::token::TokenType;
+use std::fmt;
+use std::rc::Rc;
 
-use super::ControlFlow;
+use super::{ControlFlow, FlowResult, ok};
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct LoxFunction {
     pub declaration: Function,
     pub closure: EnvRef,
+    pub is_initializer: bool,
 }
 
 impl LoxFunction {
-    fn new(declaration: Function, closure: EnvRef) -> Self {
+    pub fn new(declaration: Function, closure: EnvRef, is_initializer: bool) -> Self {
         LoxFunction {
             declaration,
             closure,
+            is_initializer,
         }
     }
+
+    // wrap the closure in a new env layer holding "this" so the method sees its instance
+    pub fn bind(&self, instance: Rc<LoxInstance>) -> LoxFunction {
+        let env = Env::new_enclosed(self.closure.clone());
+        env.borrow_mut().define("this", Object::Instance(instance));
+        LoxFunction::new(self.declaration.clone(), env, self.is_initializer)
+    }
+
+    fn this_value(&self) -> super::Result<Object> {
+        // bind() makes "this" the only slot of the closure's innermost env
+        self.closure.borrow().get_at(0, 0)
+    }
 }
 
-impl LoxCallable for LoxFunction {
-    fn call(&self, interpreter: &super::Interpreter, args: &[Object]) -> super::Result<Object> {
+// the closure env usually contains the function itself, so a derived Debug would recurse forever
+impl fmt::Debug for LoxFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", LoxCallable::to_string(self))
+    }
+}
+
+impl LoxFunction {
+    // runs the body; a tail call comes back as ControlFlow::TailCall, any other outcome as
+    // the value the call returns
+    fn run_body(&self, interpreter: &super::Interpreter, args: &[Object]) -> FlowResult<Object> {
         // need to create a new env and bind the variables to it
         let env = Env::new_enclosed(self.closure.clone());
 
         for (i, param) in self.declaration.parameters.iter().enumerate() {
-            env.borrow_mut()
-                .define(param.lexeme.clone(), args[i].clone());
+            env.borrow_mut().define(&param.lexeme, args[i].clone());
         }
 
         // execute body
         let _guard = EnvGuard::new(interpreter, env);
 
         // The function body is a Block statement, so we need to extract its statements
-        match self.declaration.body.as_ref() {
-            crate::compiler::stmt::Stmt::Block(block) => {
-                for stmt in &block.statements {
-                    let (_, flow) = stmt.accept(interpreter)?;
-
-                    if let ControlFlow::Return(value) = flow {
-                        return Ok(value);
+        let statements = match self.declaration.body.as_ref() {
+            crate::compiler::stmt::Stmt::Block(block) => block.statements.as_slice(),
+            // If it's not a block, just execute the single statement
+            body => std::slice::from_ref(body),
+        };
+        for stmt in statements {
+            match stmt.accept(interpreter)?.1 {
+                ControlFlow::Return(value) => {
+                    if self.is_initializer {
+                        return ok(self.this_value()?);
                     }
+                    return ok(value);
                 }
+                flow @ ControlFlow::TailCall(_) => return Ok((Object::Nil, flow)),
+                _ => {}
             }
-            _ => {
-                // If it's not a block, just execute the single statement
-                let (_, flow) = self.declaration.body.accept(interpreter)?;
-                if let ControlFlow::Return(value) = flow {
-                    return Ok(value);
-                }
-            }
         }
 
-        Ok(Object::Nil)
+        // initializers always hand back the instance, even when called directly
+        if self.is_initializer {
+            return ok(self.this_value()?);
+        }
+
+        ok(Object::Nil)
+    }
+}
+
+impl LoxCallable for LoxFunction {
+    fn call(&self, interpreter: &super::Interpreter, args: &[Object]) -> super::Result<Object> {
+        match self.run_body(interpreter, args)? {
+            (_, ControlFlow::TailCall(call)) => interpreter.call_function(call),
+            (value, _) => Ok(value),
+        }
+    }
+
+    fn call_tail(&self, interpreter: &super::Interpreter, args: &[Object]) -> FlowResult<Object> {
+        self.run_body(interpreter, args)
     }
 
     fn arity(&self) -> usize {
@@ -61,6 +104,19 @@ impl LoxCallable for LoxFunction {
     }
 
     fn to_string(&self) -> String {
+        if self.declaration.name.token_type != TokenType::IDENTIFIER {
+            return "<fn lambda>".to_string();
+        }
         format!("<fn {}>", self.declaration.name.lexeme)
     }
+
+    fn trace(&self, tracer: &mut Tracer) {
+        tracer.edge(&self.closure);
+    }
+}
+
+impl LoxMethod for LoxFunction {
+    fn bind(&self, instance: Rc<LoxInstance>) -> Rc<dyn LoxCallable> {
+        gc::track_callable(LoxFunction::bind(self, instance))
+    }
 }
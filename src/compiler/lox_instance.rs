@@ -1,12 +1,64 @@
+use crate::compiler::error::{LoxError, Result};
+use crate::compiler::error_codes::ErrorCode;
+use crate::compiler::expr::{LoxCallable, Object};
+use crate::compiler::gc::{Trace, Tracer};
 use crate::compiler::lox_class::LoxClass;
+use crate::compiler::token::Token;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
 
-#[derive(Debug)]
 pub struct LoxInstance {
     pub klass: Box<LoxClass>,
+    // fields live behind a RefCell since instances are shared through Rc
+    pub fields: RefCell<HashMap<String, Object>>,
 }
 
 impl LoxInstance {
     pub fn new(klass: Box<LoxClass>) -> Self {
-        Self { klass }
+        Self {
+            klass,
+            fields: RefCell::new(HashMap::new()),
+        }
+    }
+
+    // takes the Rc so methods can be bound to this exact instance
+    pub fn get(self: &Rc<Self>, name: &Token) -> Result<Object> {
+        if let Some(value) = self.fields.borrow().get(&name.lexeme) {
+            return Ok(value.clone());
+        }
+
+        // fields shadow methods, so only fall back to the class afterwards
+        if let Some(method) = self.klass.find_method(&name.lexeme) {
+            return Ok(Object::Function(method.bind(self.clone())));
+        }
+
+        Err(LoxError::new_runtime(
+            name.clone(),
+            &format!("Undefined property '{}'.", name.lexeme),
+        )
+        .with_code(ErrorCode::UndefinedProperty))
+    }
+
+    pub fn set(&self, name: &Token, value: Object) {
+        self.fields.borrow_mut().insert(name.lexeme.clone(), value);
+    }
+}
+
+impl Trace for LoxInstance {
+    fn trace(&self, tracer: &mut Tracer) {
+        // the instance owns its copy of the class, and so the class's methods
+        LoxCallable::trace(self.klass.as_ref(), tracer);
+        for value in self.fields.borrow().values() {
+            tracer.object(value);
+        }
+    }
+}
+
+// fields can point back at the instance, so avoid the derived recursive Debug
+impl fmt::Debug for LoxInstance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} instance", self.klass.name)
     }
 }
@@ -0,0 +1,100 @@
+use crate::compiler::expr::Object;
+use crate::compiler::gc::{Trace, Tracer};
+use crate::compiler::lox_string::LoxString;
+use std::collections::HashMap;
+
+// Object only has PartialEq (numbers are f64), so map keys go through this hashable
+// mirror of the variants that can be used as keys: booleans, numbers and strings.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HashKey {
+    Boolean(bool),
+    // stored as raw bits; -0.0 is folded into 0.0 and NaN is rejected
+    Number(u64),
+    String(LoxString),
+}
+
+impl HashKey {
+    pub fn from_object(value: &Object) -> Option<HashKey> {
+        match value {
+            Object::Boolean(b) => Some(HashKey::Boolean(*b)),
+            Object::Number(n) if n.is_nan() => None,
+            Object::Number(n) => {
+                let n = if *n == 0.0 { 0.0 } else { *n };
+                Some(HashKey::Number(n.to_bits()))
+            }
+            Object::String(s) => Some(HashKey::String(s.clone())),
+            _ => None,
+        }
+    }
+
+    pub fn to_object(&self) -> Object {
+        match self {
+            HashKey::Boolean(b) => Object::Boolean(*b),
+            HashKey::Number(bits) => Object::Number(f64::from_bits(*bits)),
+            HashKey::String(s) => Object::String(s.clone()),
+        }
+    }
+}
+
+// Insertion-ordered map so keys()/values() and printing are deterministic
+#[derive(Debug, Clone, Default)]
+pub struct LoxMap {
+    entries: Vec<(HashKey, Object)>,
+    index: HashMap<HashKey, usize>,
+}
+
+impl LoxMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &HashKey) -> Option<&Object> {
+        self.index.get(key).map(|&i| &self.entries[i].1)
+    }
+
+    pub fn contains_key(&self, key: &HashKey) -> bool {
+        self.index.contains_key(key)
+    }
+
+    pub fn insert(&mut self, key: HashKey, value: Object) {
+        match self.index.get(&key) {
+            Some(&i) => self.entries[i].1 = value,
+            None => {
+                self.index.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &HashKey) -> Option<Object> {
+        let i = self.index.remove(key)?;
+        let (_, value) = self.entries.remove(i);
+        // everything after the removed entry shifted down by one
+        for (_, position) in self.index.iter_mut() {
+            if *position > i {
+                *position -= 1;
+            }
+        }
+        Some(value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(HashKey, Object)> {
+        self.entries.iter()
+    }
+}
+
+impl Trace for LoxMap {
+    fn trace(&self, tracer: &mut Tracer) {
+        for (_, value) in &self.entries {
+            tracer.object(value);
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::borrow::Borrow;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::rc::Rc;
+
+// Lox strings are immutable, so every distinct string is stored once and shared. Copying a
+// string value only bumps a reference count, and since equal strings are always the same
+// allocation, comparing two of them is a pointer comparison.
+//
+// The intern table holds a reference to each string too. Strings nothing else refers to
+// any more are pruned once the table has doubled in size since the last prune.
+const MIN_PRUNE: usize = 1024;
+
+struct Interner {
+    strings: HashSet<Rc<str>>,
+    next_prune: usize,
+}
+
+thread_local! {
+    static INTERNER: RefCell<Interner> = RefCell::new(Interner {
+        strings: HashSet::new(),
+        next_prune: MIN_PRUNE,
+    });
+}
+
+#[derive(Clone)]
+pub struct LoxString(Rc<str>);
+
+impl LoxString {
+    /// Returns the interned copy of `s`, adding it to the table if it isn't there yet.
+    pub fn new(s: &str) -> Self {
+        INTERNER.with(|interner| {
+            let mut interner = interner.borrow_mut();
+            if let Some(existing) = interner.strings.get(s) {
+                return LoxString(existing.clone());
+            }
+            if interner.strings.len() >= interner.next_prune {
+                interner.strings.retain(|s| Rc::strong_count(s) > 1);
+                interner.next_prune = (interner.strings.len() * 2).max(MIN_PRUNE);
+            }
+            let string: Rc<str> = Rc::from(s);
+            interner.strings.insert(string.clone());
+            LoxString(string)
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn ptr_eq(a: &LoxString, b: &LoxString) -> bool {
+        Rc::ptr_eq(&a.0, &b.0)
+    }
+}
+
+/// How many strings the current thread's intern table holds, including any not yet pruned.
+pub fn interned_count() -> usize {
+    INTERNER.with(|interner| interner.borrow().strings.len())
+}
+
+impl From<&str> for LoxString {
+    fn from(s: &str) -> Self {
+        LoxString::new(s)
+    }
+}
+
+impl From<String> for LoxString {
+    fn from(s: String) -> Self {
+        LoxString::new(&s)
+    }
+}
+
+impl Deref for LoxString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+// lets maps keyed by LoxString be looked up with a plain &str; hashing the contents rather
+// than the pointer keeps this consistent with str's own Hash
+impl Borrow<str> for LoxString {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for LoxString {
+    fn eq(&self, other: &Self) -> bool {
+        LoxString::ptr_eq(self, other)
+    }
+}
+
+impl Eq for LoxString {}
+
+impl Hash for LoxString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+// prints like a String, so `String("hi")` still shows up as such in Object's Debug output
+impl fmt::Debug for LoxString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for LoxString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
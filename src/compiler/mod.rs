@@ -1,26 +1,40 @@
 pub mod astPrinter;
 pub mod control_flow;
+pub mod diagnostic;
 pub mod env;
 pub mod error;
+pub mod error_codes;
 pub mod expr;
+pub mod formatter;
+pub mod gc;
 pub mod interpreter;
 pub mod lox_class;
 pub mod lox_function;
 pub mod lox_instance;
+pub mod lox_map;
+pub mod lox_string;
 pub mod natives;
+pub mod optimizer;
 pub mod parser;
+pub mod repl;
 pub mod resolver;
 pub mod scanner;
 pub mod stmt;
 pub mod token;
+pub mod vm;
 
 pub use astPrinter::*;
 pub use control_flow::*;
+pub use diagnostic::{DiagnosticRenderer, ErrorFormat};
 pub use env::Env;
-pub use error::{ErrorReporter, LoxError, LoxErrorKind, Result};
+pub use error::{ErrorReporter, LoxError, LoxErrorKind, Note, Result, StackFrame};
+pub use error_codes::ErrorCode;
 pub use expr::Expr;
 pub use interpreter::Interpreter;
-pub use natives::ClockFunction;
+pub use natives::{
+    ClockFunction, DeleteFunction, HasFunction, InsertFunction, KeysFunction, LenFunction,
+    PopFunction, PushFunction, RemoveFunction, ValuesFunction,
+};
 pub use parser::Parser;
 pub use resolver::Resolver;
 pub use scanner::Scanner;
@@ -1,21 +1,70 @@
 use crate::compiler::error::{LoxError, Result};
+use crate::compiler::error_codes::ErrorCode;
 use crate::compiler::expr::{LoxCallable, Object};
+use crate::compiler::gc;
 use crate::compiler::interpreter::Interpreter;
+use crate::compiler::lox_map::HashKey;
 use crate::compiler::token::{Token, TokenType};
+use std::cell::RefCell;
+use std::rc::Rc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+// natives have no token of their own, so errors point at the paren of the call the
+// interpreter is making; without one (as under the VM) they fall back to the function name
+fn native_token(interpreter: &Interpreter, name: &str) -> Token {
+    match interpreter.frames.borrow().last() {
+        Some(frame) => frame.call_site.clone(),
+        None => Token::new(TokenType::EOF, name.to_string(), 0, None),
+    }
+}
+
+fn native_error(interpreter: &Interpreter, name: &str, message: &str) -> LoxError {
+    LoxError::new_runtime(native_token(interpreter, name), message)
+        .with_code(ErrorCode::NativeArgumentType)
+}
+
+// Converts a Lox number into a list position. `len` is the exclusive upper bound, so
+// callers that allow appending (insert) pass one past the end.
+pub fn list_index(index: &Object, len: usize, token: &Token) -> Result<usize> {
+    match index {
+        Object::Number(n) if n.fract() == 0.0 => {
+            if *n < 0.0 || *n >= len as f64 {
+                Err(LoxError::new_runtime(
+                    token.clone(),
+                    &format!("List index {} out of bounds for length {}.", n, len),
+                )
+                .with_code(ErrorCode::InvalidIndex))
+            } else {
+                Ok(*n as usize)
+            }
+        }
+        _ => Err(
+            LoxError::new_runtime(token.clone(), "List index must be an integer.")
+                .with_code(ErrorCode::InvalidIndex),
+        ),
+    }
+}
+
+// Converts a Lox value into a map key, rejecting values that can't be hashed
+pub fn map_key(key: &Object, token: &Token) -> Result<HashKey> {
+    HashKey::from_object(key).ok_or_else(|| {
+        LoxError::new_runtime(
+            token.clone(),
+            "Map keys must be booleans, numbers or strings.",
+        )
+        .with_code(ErrorCode::InvalidMapKey)
+    })
+}
+
 #[derive(Debug)]
 pub struct ClockFunction;
 
 impl LoxCallable for ClockFunction {
-    fn call(&self, _interpreter: &Interpreter, _args: &[Object]) -> Result<Object> {
+    fn call(&self, interpreter: &Interpreter, _args: &[Object]) -> Result<Object> {
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .map_err(|_| {
-                LoxError::new_runtime(
-                    Token::new(TokenType::EOF, "clock".to_string(), 0, None),
-                    "System time error",
-                )
+                LoxError::new_runtime(native_token(interpreter, "clock"), "System time error")
             })?
             .as_secs_f64();
         Ok(Object::Number(now))
@@ -29,3 +78,242 @@ impl LoxCallable for ClockFunction {
         "<native fn clock>".to_string()
     }
 }
+
+#[derive(Debug)]
+pub struct LenFunction;
+
+impl LoxCallable for LenFunction {
+    fn call(&self, interpreter: &Interpreter, args: &[Object]) -> Result<Object> {
+        match &args[0] {
+            Object::List(list) => Ok(Object::Number(list.borrow().len() as f64)),
+            Object::Map(map) => Ok(Object::Number(map.borrow().len() as f64)),
+            Object::String(s) => Ok(Object::Number(s.chars().count() as f64)),
+            _ => Err(native_error(
+                interpreter,
+                "len",
+                "len() expects a list, map or string.",
+            )),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn to_string(&self) -> String {
+        "<native fn len>".to_string()
+    }
+}
+
+#[derive(Debug)]
+pub struct PushFunction;
+
+impl LoxCallable for PushFunction {
+    fn call(&self, interpreter: &Interpreter, args: &[Object]) -> Result<Object> {
+        match &args[0] {
+            Object::List(list) => {
+                list.borrow_mut().push(args[1].clone());
+                Ok(Object::Nil)
+            }
+            _ => Err(native_error(interpreter, "push", "push() expects a list.")),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn to_string(&self) -> String {
+        "<native fn push>".to_string()
+    }
+}
+
+#[derive(Debug)]
+pub struct PopFunction;
+
+impl LoxCallable for PopFunction {
+    fn call(&self, interpreter: &Interpreter, args: &[Object]) -> Result<Object> {
+        match &args[0] {
+            Object::List(list) => list.borrow_mut().pop().ok_or_else(|| {
+                native_error(interpreter, "pop", "Can't pop from an empty list.")
+                    .with_code(ErrorCode::InvalidIndex)
+            }),
+            _ => Err(native_error(interpreter, "pop", "pop() expects a list.")),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn to_string(&self) -> String {
+        "<native fn pop>".to_string()
+    }
+}
+
+#[derive(Debug)]
+pub struct InsertFunction;
+
+impl LoxCallable for InsertFunction {
+    fn call(&self, interpreter: &Interpreter, args: &[Object]) -> Result<Object> {
+        match &args[0] {
+            Object::List(list) => {
+                let len = list.borrow().len();
+                // inserting at len appends, so allow one past the last element
+                let index = list_index(&args[1], len + 1, &native_token(interpreter, "insert"))?;
+                list.borrow_mut().insert(index, args[2].clone());
+                Ok(Object::Nil)
+            }
+            _ => Err(native_error(
+                interpreter,
+                "insert",
+                "insert() expects a list.",
+            )),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        3
+    }
+
+    fn to_string(&self) -> String {
+        "<native fn insert>".to_string()
+    }
+}
+
+#[derive(Debug)]
+pub struct RemoveFunction;
+
+impl LoxCallable for RemoveFunction {
+    fn call(&self, interpreter: &Interpreter, args: &[Object]) -> Result<Object> {
+        match &args[0] {
+            Object::List(list) => {
+                let len = list.borrow().len();
+                let index = list_index(&args[1], len, &native_token(interpreter, "remove"))?;
+                Ok(list.borrow_mut().remove(index))
+            }
+            _ => Err(native_error(
+                interpreter,
+                "remove",
+                "remove() expects a list.",
+            )),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn to_string(&self) -> String {
+        "<native fn remove>".to_string()
+    }
+}
+
+#[derive(Debug)]
+pub struct KeysFunction;
+
+impl LoxCallable for KeysFunction {
+    fn call(&self, interpreter: &Interpreter, args: &[Object]) -> Result<Object> {
+        match &args[0] {
+            Object::Map(map) => {
+                let keys = map
+                    .borrow()
+                    .iter()
+                    .map(|(key, _)| key.to_object())
+                    .collect();
+                Ok(Object::List(gc::track_list(Rc::new(RefCell::new(keys)))))
+            }
+            _ => Err(native_error(interpreter, "keys", "keys() expects a map.")),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn to_string(&self) -> String {
+        "<native fn keys>".to_string()
+    }
+}
+
+#[derive(Debug)]
+pub struct ValuesFunction;
+
+impl LoxCallable for ValuesFunction {
+    fn call(&self, interpreter: &Interpreter, args: &[Object]) -> Result<Object> {
+        match &args[0] {
+            Object::Map(map) => {
+                let values = map
+                    .borrow()
+                    .iter()
+                    .map(|(_, value)| value.clone())
+                    .collect();
+                Ok(Object::List(gc::track_list(Rc::new(RefCell::new(values)))))
+            }
+            _ => Err(native_error(
+                interpreter,
+                "values",
+                "values() expects a map.",
+            )),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn to_string(&self) -> String {
+        "<native fn values>".to_string()
+    }
+}
+
+#[derive(Debug)]
+pub struct HasFunction;
+
+impl LoxCallable for HasFunction {
+    fn call(&self, interpreter: &Interpreter, args: &[Object]) -> Result<Object> {
+        match &args[0] {
+            Object::Map(map) => {
+                let key = map_key(&args[1], &native_token(interpreter, "has"))?;
+                Ok(Object::Boolean(map.borrow().contains_key(&key)))
+            }
+            _ => Err(native_error(interpreter, "has", "has() expects a map.")),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn to_string(&self) -> String {
+        "<native fn has>".to_string()
+    }
+}
+
+#[derive(Debug)]
+pub struct DeleteFunction;
+
+impl LoxCallable for DeleteFunction {
+    fn call(&self, interpreter: &Interpreter, args: &[Object]) -> Result<Object> {
+        match &args[0] {
+            Object::Map(map) => {
+                let key = map_key(&args[1], &native_token(interpreter, "delete"))?;
+                // hand back the removed value, or nil if the key wasn't there
+                Ok(map.borrow_mut().remove(&key).unwrap_or(Object::Nil))
+            }
+            _ => Err(native_error(
+                interpreter,
+                "delete",
+                "delete() expects a map.",
+            )),
+        }
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn to_string(&self) -> String {
+        "<native fn delete>".to_string()
+    }
+}
@@ -0,0 +1,257 @@
+use crate::compiler::expr::{
+    Assign, Binary, Call, Expr, Get, Grouping, IndexGet, IndexSet, Lambda, List, Literal, Logical,
+    Map, Object, Set, Ternary, Unary,
+};
+use crate::compiler::interpreter::Interpreter;
+use crate::compiler::stmt::{
+    Block, Class, Expression, Function, IfStmt, Print, ReturnStmt, Stmt, Var, WhileStmt,
+};
+use crate::compiler::token::Span;
+
+// An optional pass between resolving and running a program that does work ahead of time:
+//   - arithmetic, comparisons, concatenation and `!`/`-` on literals are folded into a
+//     single literal, e.g. `2 * (3 + 4)` becomes `14`
+//   - ternaries and if statements with a literal condition are replaced by the branch that
+//     would run, and `while` loops whose condition is a literal false or nil are dropped
+//
+// Folding uses the interpreter's own operators, so results are exactly what running the
+// code would give. An operation that would fail, like `1 / 0` or `-"a"`, is left alone to
+// raise its error at the original token when it runs.
+//
+// The pass runs after the resolver so diagnostics still cover the code it removes. It only
+// drops statements and expressions and never moves a declaration, so the slots and
+// expression ids the resolver recorded stay valid.
+
+/// Folds constants and removes dead branches from a resolved program.
+pub fn optimize(statements: Vec<Stmt>) -> Vec<Stmt> {
+    statements.into_iter().filter_map(statement).collect()
+}
+
+// None when the statement can never do anything
+fn statement(stmt: Stmt) -> Option<Stmt> {
+    let stmt = match stmt {
+        Stmt::Expression(e) => Stmt::Expression(Box::new(Expression {
+            expression: boxed(e.expression),
+            span: e.span,
+        })),
+        Stmt::Print(p) => Stmt::Print(Box::new(Print {
+            expression: boxed(p.expression),
+            span: p.span,
+        })),
+        Stmt::Var(v) => Stmt::Var(Box::new(Var {
+            name: v.name,
+            initializer: boxed(v.initializer),
+            span: v.span,
+        })),
+        Stmt::Block(b) => Stmt::Block(Box::new(Block {
+            statements: optimize(b.statements),
+            span: b.span,
+        })),
+        Stmt::IfStmt(i) => {
+            let condition = expression(*i.condition);
+            if let Some(value) = literal(&condition) {
+                return match Interpreter::is_truthy(value.clone()) {
+                    true => statement(*i.then_branch),
+                    false => i.else_branch.and_then(|branch| statement(*branch)),
+                };
+            }
+            Stmt::IfStmt(Box::new(IfStmt {
+                condition: Box::new(condition),
+                then_branch: Box::new(body(*i.then_branch)),
+                else_branch: i
+                    .else_branch
+                    .and_then(|branch| statement(*branch))
+                    .map(Box::new),
+                span: i.span,
+            }))
+        }
+        Stmt::WhileStmt(w) => {
+            let condition = expression(*w.condition);
+            if literal(&condition).is_some_and(|value| !Interpreter::is_truthy(value.clone())) {
+                return None;
+            }
+            Stmt::WhileStmt(Box::new(WhileStmt {
+                condition: Box::new(condition),
+                body: Box::new(body(*w.body)),
+                increment: w.increment.map(boxed),
+                span: w.span,
+            }))
+        }
+        Stmt::Function(f) => Stmt::Function(Box::new(function(*f))),
+        Stmt::Class(c) => Stmt::Class(Box::new(Class {
+            name: c.name,
+            superclass: c.superclass.map(boxed),
+            methods: optimize(c.methods),
+            span: c.span,
+        })),
+        Stmt::ReturnStmt(r) => Stmt::ReturnStmt(Box::new(ReturnStmt {
+            tok: r.tok,
+            value: r.value.map(boxed),
+            span: r.span,
+            tail_call: r.tail_call,
+        })),
+        stmt @ (Stmt::Break(_) | Stmt::Continue(_)) => stmt,
+    };
+    Some(stmt)
+}
+
+// a statement that has to stay, e.g. a loop body, becomes an empty block if it's removed
+fn body(stmt: Stmt) -> Stmt {
+    let span = stmt.span();
+    statement(stmt).unwrap_or_else(|| empty_block(span))
+}
+
+fn empty_block(span: Span) -> Stmt {
+    Stmt::Block(Box::new(Block {
+        statements: Vec::new(),
+        span,
+    }))
+}
+
+fn function(function: Function) -> Function {
+    Function {
+        name: function.name,
+        parameters: function.parameters,
+        body: Box::new(body(*function.body)),
+        span: function.span,
+    }
+}
+
+// optimizes the expression in place, reusing its allocation
+fn boxed(mut expr: Box<Expr>) -> Box<Expr> {
+    let placeholder = folded(Object::Nil, Span::default());
+    *expr = expression(std::mem::replace(&mut *expr, placeholder));
+    expr
+}
+
+fn literal(expr: &Expr) -> Option<&Object> {
+    match expr {
+        Expr::Literal(literal) => Some(&literal.value),
+        _ => None,
+    }
+}
+
+fn folded(value: Object, span: Span) -> Expr {
+    Expr::Literal(Literal { value, span })
+}
+
+fn expression(expr: Expr) -> Expr {
+    match expr {
+        Expr::Literal(_) | Expr::Variable(_) | Expr::This(_) | Expr::Super(_) => expr,
+        Expr::Grouping(g) => {
+            let inner = expression(*g.expression);
+            match literal(&inner) {
+                Some(value) => folded(value.clone(), g.span),
+                None => Expr::Grouping(Box::new(Grouping {
+                    expression: Box::new(inner),
+                    span: g.span,
+                })),
+            }
+        }
+        Expr::Unary(u) => {
+            let right = expression(*u.right);
+            let value = literal(&right)
+                .and_then(|value| Interpreter::unary_op(&u.operator, value.clone()).ok());
+            match value {
+                Some(value) => folded(value, u.span),
+                None => Expr::Unary(Box::new(Unary {
+                    operator: u.operator,
+                    right: Box::new(right),
+                    span: u.span,
+                })),
+            }
+        }
+        Expr::Binary(b) => {
+            let left = expression(*b.left);
+            let right = expression(*b.right);
+            let value = match (literal(&left), literal(&right)) {
+                (Some(l), Some(r)) => {
+                    Interpreter::binary_op(&b.operator, l.clone(), r.clone()).ok()
+                }
+                _ => None,
+            };
+            match value {
+                Some(value) => folded(value, b.span),
+                None => Expr::Binary(Box::new(Binary {
+                    left: Box::new(left),
+                    operator: b.operator,
+                    right: Box::new(right),
+                    span: b.span,
+                })),
+            }
+        }
+        Expr::Ternary(t) => {
+            let condition = expression(*t.condition);
+            match literal(&condition).map(|value| Interpreter::is_truthy(value.clone())) {
+                Some(true) => expression(*t.true_branch),
+                Some(false) => expression(*t.false_branch),
+                None => Expr::Ternary(Box::new(Ternary {
+                    condition: Box::new(condition),
+                    true_branch: boxed(t.true_branch),
+                    false_branch: boxed(t.false_branch),
+                    span: t.span,
+                })),
+            }
+        }
+        Expr::Assign(a) => Expr::Assign(Box::new(Assign {
+            id: a.id,
+            name: a.name,
+            value: boxed(a.value),
+            span: a.span,
+        })),
+        Expr::Logical(l) => Expr::Logical(Box::new(Logical {
+            left: boxed(l.left),
+            operator: l.operator,
+            right: boxed(l.right),
+            span: l.span,
+        })),
+        Expr::Call(c) => Expr::Call(Box::new(Call {
+            callee: boxed(c.callee),
+            paren: c.paren,
+            args: c.args.into_iter().map(expression).collect(),
+            span: c.span,
+        })),
+        Expr::Get(g) => Expr::Get(Box::new(Get {
+            object: boxed(g.object),
+            name: g.name,
+            span: g.span,
+        })),
+        Expr::Set(s) => Expr::Set(Box::new(Set {
+            object: boxed(s.object),
+            name: s.name,
+            value: boxed(s.value),
+            span: s.span,
+        })),
+        Expr::List(l) => Expr::List(Box::new(List {
+            bracket: l.bracket,
+            elements: l.elements.into_iter().map(expression).collect(),
+            span: l.span,
+        })),
+        Expr::IndexGet(i) => Expr::IndexGet(Box::new(IndexGet {
+            object: boxed(i.object),
+            bracket: i.bracket,
+            index: boxed(i.index),
+            span: i.span,
+        })),
+        Expr::IndexSet(i) => Expr::IndexSet(Box::new(IndexSet {
+            object: boxed(i.object),
+            bracket: i.bracket,
+            index: boxed(i.index),
+            value: boxed(i.value),
+            span: i.span,
+        })),
+        Expr::Map(m) => Expr::Map(Box::new(Map {
+            brace: m.brace,
+            entries: m
+                .entries
+                .into_iter()
+                .map(|(key, value)| (expression(key), expression(value)))
+                .collect(),
+            span: m.span,
+        })),
+        Expr::Lambda(l) => Expr::Lambda(Box::new(Lambda {
+            declaration: function(l.declaration),
+            span: l.span,
+        })),
+    }
+}
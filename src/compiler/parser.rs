@@ -1,20 +1,27 @@
+use crate::compiler::error_codes::ErrorCode;
 use crate::compiler::expr::{
-    Assign, Binary, Call, Expr, Grouping, Literal, Logical, Object, Ternary, Unary, Variable,
+    Assign, Binary, Call, Expr, ExprId, Get, Grouping, IndexGet, IndexSet, Lambda, List, Literal,
+    Logical, Map, Object, Set, Super, Ternary, This, Unary, Variable,
 };
 use crate::compiler::stmt::{
-    Block, Class, Expression, Function, IfStmt, Print, ReturnStmt, Stmt, Var, WhileStmt,
+    Block, Break, Class, Continue, Expression, Function, IfStmt, Print, ReturnStmt, Stmt, Var,
+    WhileStmt,
 };
-use crate::compiler::token::TokenType;
+use crate::compiler::token::{Span, TokenType};
 use crate::compiler::{LoxError, Result, Token};
+use std::cell::Cell;
 
 // The essential grammar for lox is as follows (low to high precedence):
 // program -> declaration* EOF
 // declaration -> varStmt | funStmt | classDev | statement
 // varStmt -> "var" identifier ("=" expression)? ";"
 // statement -> printStmt | exprStmt | whileStmt | forStmt | ifStmt | block | funcStmt | returnStmt
-// classDec -> "class" identifier "{" function* "}" ;
+//              | breakStmt | continueStmt
+// classDec -> "class" identifier ( "<" identifier )? "{" function* "}" ;
 // funcStmt -> "func" function;
 // returnStmt -> "return" expression? ";"
+// breakStmt -> "break" ";"
+// continueStmt -> "continue" ";"
 // function -> Identifier "(" parameters? ")" block;
 // parameters -> Identifier ("," Identifier)*
 // forStmt -> "for" "(" (exprStmt | varStmt | ";") expression? ";" expression? ")" statement
@@ -23,7 +30,8 @@ use crate::compiler::{LoxError, Result, Token};
 // block -> "{" declaration* "}" ;
 // printStmt -> "print" expression ";"
 // exprStmt -> expression ";"
-// expression -> IDENTIFIER "=" expression | logic_or;
+// expression -> ( call "." )? IDENTIFIER "=" expression | call "[" expression "]" "=" expression
+//               | logic_or;
 // logic_or -> logic_and ("or" logic_and)*;
 // logic_and -> equality ("and" equality)*;
 // equality -> ternary ( ( "!=" | "==" ) ternary)*;
@@ -32,13 +40,20 @@ use crate::compiler::{LoxError, Result, Token};
 // term -> factor ( ( "-" | "+" ) factor )*;
 // factor -> unary ( ( "/" | "*" ) unary )*;
 // unary -> ( "!" | "-" ) unary | call ;
-// call -> primary ( "(" arguments ")" ) ;
+// call -> primary ( "(" arguments ")" | "." IDENTIFIER | "[" expression "]" )* ;
 // arguments -> expression ( "," expression )*;
-// primary -> NUMBER | STRING | "true" | "false" | "nil" | "(" expression ")" | identifier ;
+// primary -> NUMBER | STRING | "true" | "false" | "nil" | "this" | "(" expression ")" | identifier
+//            | "super" "." identifier | "[" arguments? "]" | "{" entries? "}" ;
+//            | lambda ;
+// entries -> expression ":" expression ( "," expression ":" expression )* ;
+// lambda -> "fun" "(" parameters? ")" block | "(" parameters? ")" "=>" expression ;
+// NOTE: a "{" only reaches primary in expression position, so it never clashes with blocks
 
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    // errors recovered from so far; parsing carries on after each one
+    errors: Vec<LoxError>,
 }
 
 impl Parser {
@@ -46,6 +61,7 @@ impl Parser {
         Self {
             tokens: tokens.clone(),
             current: 0,
+            errors: Vec::new(),
         }
     }
 
@@ -56,16 +72,40 @@ impl Parser {
         if self.match_token(&[TokenType::EQUAL]) {
             let equals: Token = self.previous().clone();
             let val: Expr = self.expression()?;
+            let span = lval.span().to(val.span());
 
             // check if lval is a variable type expression
             if let Expr::Variable(name) = lval {
                 return Ok(Expr::Assign(Box::new(Assign {
+                    id: ExprId::fresh(),
                     name: name.name,
                     value: Box::new(val),
+                    span,
                 })));
             }
 
-            return Err(LoxError::new_parse(equals, "Invalid assignment target"));
+            // property assignment turns the trailing get into a set
+            if let Expr::Get(get) = lval {
+                return Ok(Expr::Set(Box::new(Set {
+                    object: get.object,
+                    name: get.name,
+                    value: Box::new(val),
+                    span,
+                })));
+            }
+
+            if let Expr::IndexGet(index_get) = lval {
+                return Ok(Expr::IndexSet(Box::new(IndexSet {
+                    object: index_get.object,
+                    bracket: index_get.bracket,
+                    index: index_get.index,
+                    value: Box::new(val),
+                    span,
+                })));
+            }
+
+            return Err(LoxError::new_parse(equals, "Invalid assignment target")
+                .with_code(ErrorCode::InvalidAssignmentTarget));
         }
 
         Ok(lval)
@@ -76,9 +116,25 @@ impl Parser {
     }
 
     pub fn class(&mut self) -> Result<Stmt> {
+        let start = self.previous().span;
         let name = self
             .consume(&TokenType::IDENTIFIER, "Expect class name.")?
             .clone();
+
+        let superclass = if self.match_token(&[TokenType::LESS]) {
+            let superclass_name = self
+                .consume(&TokenType::IDENTIFIER, "Expect superclass name.")?
+                .clone();
+            let span = superclass_name.span;
+            Some(Box::new(Expr::Variable(Box::new(Variable {
+                id: ExprId::fresh(),
+                name: superclass_name,
+                span,
+            }))))
+        } else {
+            None
+        };
+
         self.consume(&TokenType::LBRACE, "Expected left brace.")?;
         let mut methods: Vec<Stmt> = Vec::new();
         while !self.is_at_end() && !self.check(&TokenType::RBRACE) {
@@ -87,11 +143,19 @@ impl Parser {
         self.consume(&TokenType::RBRACE, "Expected right brace after class body.")?;
         Ok(Stmt::Class(Box::new(Class {
             name: Box::new(name),
-            methods: methods,
+            superclass,
+            methods,
+            span: self.span_from(start),
         })))
     }
 
     pub fn function(&mut self) -> Result<Stmt> {
+        // methods have no leading 'fun', so only include it when we just consumed one
+        let start = if self.previous_is(&TokenType::FUN) {
+            self.previous().span
+        } else {
+            self.peek().span
+        };
         let name = self
             .consume(&TokenType::IDENTIFIER, "Expect function name.")?
             .clone();
@@ -102,6 +166,7 @@ impl Parser {
             name: Box::new(name),
             parameters: Box::new(params),
             body: Box::new(body),
+            span: self.span_from(start),
         })))
     }
 
@@ -115,7 +180,8 @@ impl Parser {
                     return Err(LoxError::new_parse(
                         self.peek().clone(),
                         "Cannot have more than 255 parameters.",
-                    ));
+                    )
+                    .with_code(ErrorCode::TooManyParameters));
                 }
                 params.push(
                     self.consume(&TokenType::IDENTIFIER, "Expect parameter name.")?
@@ -133,6 +199,7 @@ impl Parser {
 
     pub fn for_statement(&mut self) -> Result<Stmt> {
         // let's implement this via desugaring
+        let start = self.previous().span;
         if self.match_token(&[TokenType::LPAREN]) {
             let initializer = match self.peek().token_type {
                 TokenType::SEMICOLON => {
@@ -165,37 +232,35 @@ impl Parser {
             self.consume(&TokenType::RPAREN, "Expect ')' after for clauses.")?;
 
             let body = self.statement()?;
-
-            // append body and inc
-            let body_inc: Stmt;
-            if let Some(inc) = inc {
-                body_inc = Stmt::Block(Box::new(Block {
-                    statements: vec![
-                        body,
-                        Stmt::Expression(Box::new(Expression {
-                            expression: Box::new(inc),
-                        })),
-                    ],
-                }));
-            } else {
-                body_inc = Stmt::Block(Box::new(Block {
-                    statements: vec![body],
-                }));
-            }
+            // every desugared node points back at the whole loop
+            let span = self.span_from(start);
+
+            // wrap the body in its own block; the increment is kept on the while node
+            // rather than appended to the body so `continue` can't skip it
+            let body_block = Stmt::Block(Box::new(Block {
+                span: body.span(),
+                statements: vec![body],
+            }));
+            let increment = inc.map(Box::new);
 
             // generate while body
             let while_body: Stmt;
             if let Some(cond) = cond {
                 while_body = Stmt::WhileStmt(Box::new(WhileStmt {
                     condition: Box::new(cond),
-                    body: Box::new(body_inc),
+                    body: Box::new(body_block),
+                    increment,
+                    span,
                 }));
             } else {
                 while_body = Stmt::WhileStmt(Box::new(WhileStmt {
                     condition: Box::new(Expr::Literal(Literal {
                         value: Object::Boolean(true),
+                        span,
                     })),
-                    body: Box::new(body_inc),
+                    body: Box::new(body_block),
+                    increment,
+                    span,
                 }));
             }
 
@@ -203,28 +268,55 @@ impl Parser {
             if let Some(initializer) = initializer {
                 Ok(Stmt::Block(Box::new(Block {
                     statements: vec![initializer?, while_body],
+                    span,
                 })))
             } else {
                 Ok(while_body)
             }
         } else {
-            Err(LoxError::new_parse(
-                self.peek().clone(),
-                "Expect '(' after 'for'.",
-            ))
+            Err(
+                LoxError::new_parse(self.peek().clone(), "Expect '(' after 'for'.")
+                    .with_code(ErrorCode::ExpectedToken),
+            )
         }
     }
 
     pub fn return_statement(&mut self) -> Result<Stmt> {
-        let expr = self.expression()?;
+        let keyword = self.previous().clone();
+        let value = if self.check(&TokenType::SEMICOLON) {
+            None
+        } else {
+            Some(Box::new(self.expression()?))
+        };
         self.consume(&TokenType::SEMICOLON, "Expect ';' after return value.")?;
         Ok(Stmt::ReturnStmt(Box::new(ReturnStmt {
-            tok: Box::new(self.previous().clone()),
-            value: Box::new(expr),
+            span: self.span_from(keyword.span),
+            tok: Box::new(keyword),
+            value,
+            tail_call: Cell::new(false),
+        })))
+    }
+
+    pub fn break_statement(&mut self) -> Result<Stmt> {
+        let keyword = self.previous().clone();
+        self.consume(&TokenType::SEMICOLON, "Expect ';' after 'break'.")?;
+        Ok(Stmt::Break(Box::new(Break {
+            span: self.span_from(keyword.span),
+            keyword: Box::new(keyword),
+        })))
+    }
+
+    pub fn continue_statement(&mut self) -> Result<Stmt> {
+        let keyword = self.previous().clone();
+        self.consume(&TokenType::SEMICOLON, "Expect ';' after 'continue'.")?;
+        Ok(Stmt::Continue(Box::new(Continue {
+            span: self.span_from(keyword.span),
+            keyword: Box::new(keyword),
         })))
     }
 
     pub fn while_statement(&mut self) -> Result<Stmt> {
+        let start = self.previous().span;
         if self.match_token(&[TokenType::LPAREN]) {
             let cond = self.expression()?;
             self.consume(&TokenType::RPAREN, "Expect ')' after condition.")?;
@@ -234,16 +326,19 @@ impl Parser {
             Ok(Stmt::WhileStmt(Box::new(WhileStmt {
                 condition: Box::new(cond),
                 body: Box::new(body),
+                increment: None,
+                span: self.span_from(start),
             })))
         } else {
-            Err(LoxError::new_parse(
-                self.peek().clone(),
-                "Expect '(' after 'while'.",
-            ))
+            Err(
+                LoxError::new_parse(self.peek().clone(), "Expect '(' after 'while'.")
+                    .with_code(ErrorCode::ExpectedToken),
+            )
         }
     }
 
     pub fn if_statement(&mut self) -> Result<Stmt> {
+        let start = self.previous().span;
         if self.match_token(&[TokenType::LPAREN]) {
             // After the LPAREN, parse the condition next
             let cond = self.expression()?;
@@ -265,29 +360,42 @@ impl Parser {
                 condition: Box::new(cond),
                 then_branch: Box::new(body),
                 else_branch: else_branch.map(|stmt| Box::new(stmt)),
+                span: self.span_from(start),
             })))
         } else {
-            return Err(LoxError::new_parse(
-                self.peek().clone(),
-                "Expect '(' after 'if'.",
-            ));
+            return Err(
+                LoxError::new_parse(self.peek().clone(), "Expect '(' after 'if'.")
+                    .with_code(ErrorCode::ExpectedToken),
+            );
         }
     }
 
     pub fn block(&mut self) -> Result<Stmt> {
+        let start = self.previous().span;
         let mut stmts: Vec<Stmt> = Vec::new();
 
         while !self.check(&TokenType::RBRACE) && !self.is_at_end() {
-            let stmt = self.declaration()?;
-            stmts.push(stmt);
+            match self.declaration() {
+                Ok(stmt) => stmts.push(stmt),
+                Err(error) => {
+                    // record and keep going so later errors in the block are reported too
+                    self.errors.push(error);
+                    self.synchronize();
+                }
+            }
         }
 
         self.consume(&TokenType::RBRACE, "Expect '}' after block.")?;
-        Ok(Stmt::Block(Box::new(Block { statements: stmts })))
+        Ok(Stmt::Block(Box::new(Block {
+            statements: stmts,
+            span: self.span_from(start),
+        })))
     }
 
     pub fn declaration(&mut self) -> Result<Stmt> {
-        if self.match_token(&[TokenType::FUN]) {
+        // `fun (` starts an anonymous function expression, not a declaration
+        if self.check(&TokenType::FUN) && !self.check_next(&TokenType::LPAREN) {
+            self.advance();
             return self.function();
         }
         if self.match_token(&[TokenType::CLASS]) {
@@ -301,6 +409,7 @@ impl Parser {
 
     pub fn var_declar(&mut self) -> Result<Stmt> {
         // 'var' already consumed by match_token in declaration()
+        let start = self.previous().span;
 
         self.consume(&TokenType::IDENTIFIER, "Expect variable name.")?;
         let name = self.previous().clone();
@@ -310,7 +419,10 @@ impl Parser {
             let expr = self.expression()?;
             Box::new(expr)
         } else {
-            Box::new(Expr::Literal(Literal { value: Object::Nil }))
+            Box::new(Expr::Literal(Literal {
+                value: Object::Nil,
+                span: name.span,
+            }))
         };
 
         self.consume(
@@ -322,6 +434,7 @@ impl Parser {
         Ok(Stmt::Var(Box::new(Var {
             name: Box::new(name),
             initializer,
+            span: self.span_from(start),
         })))
     }
 
@@ -333,12 +446,17 @@ impl Parser {
             return self.block();
         } else if self.match_token(&[TokenType::WHILE]) {
             return self.while_statement();
-        } else if self.match_token(&[TokenType::FUN]) {
+        } else if self.check(&TokenType::FUN) && !self.check_next(&TokenType::LPAREN) {
+            self.advance();
             return self.fun_statement();
         } else if self.match_token(&[TokenType::FOR]) {
             return self.for_statement();
         } else if self.match_token(&[TokenType::RETURN]) {
             return self.return_statement();
+        } else if self.match_token(&[TokenType::BREAK]) {
+            return self.break_statement();
+        } else if self.match_token(&[TokenType::CONTINUE]) {
+            return self.continue_statement();
         } else if self.match_token(&[TokenType::IF]) {
             return self.if_statement();
         } else {
@@ -347,6 +465,7 @@ impl Parser {
     }
 
     pub fn print_expression(&mut self) -> Result<Stmt> {
+        let start = self.previous().span;
         let expr: Expr = self.expression()?;
 
         self.consume(&TokenType::SEMICOLON, "Expect ';' after value.")?;
@@ -354,6 +473,7 @@ impl Parser {
         // Semicolon already consumed by consume above
         Ok(Stmt::Print(Box::new(Print {
             expression: Box::new(expr),
+            span: self.span_from(start),
         })))
     }
     pub fn expression_statement(&mut self) -> Result<Stmt> {
@@ -363,6 +483,7 @@ impl Parser {
 
         // Semicolon already consumed by consume above
         Ok(Stmt::Expression(Box::new(Expression {
+            span: self.span_from(expr.span()),
             expression: Box::new(expr),
         })))
     }
@@ -374,6 +495,7 @@ impl Parser {
             let operator = self.previous().clone();
             let right = self.logic_and()?;
             expr = Expr::Logical(Box::new(Logical {
+                span: expr.span().to(right.span()),
                 left: Box::new(expr),
                 operator,
                 right: Box::new(right),
@@ -390,6 +512,7 @@ impl Parser {
             let operator = self.previous().clone();
             let right = self.equality()?;
             expr = Expr::Logical(Box::new(Logical {
+                span: expr.span().to(right.span()),
                 left: Box::new(expr),
                 operator,
                 right: Box::new(right),
@@ -407,6 +530,7 @@ impl Parser {
             let right = self.ternary()?;
             // Create a Binary expression node wrapped in Expr enum
             expr = Expr::Binary(Box::new(Binary {
+                span: expr.span().to(right.span()),
                 left: Box::new(expr), // Box the left expression
                 operator,
                 right: Box::new(right), // Box the right expression
@@ -426,6 +550,7 @@ impl Parser {
             // Colon already consumed by consume above
             let right = self.ternary()?; // parse the right expression
             expr = Expr::Ternary(Box::new(Ternary {
+                span: expr.span().to(right.span()),
                 condition: Box::new(expr),
                 true_branch: Box::new(left),
                 false_branch: Box::new(right),
@@ -453,6 +578,7 @@ impl Parser {
 
             // Create a Binary expression node wrapped in Expr enum
             expr = Expr::Binary(Box::new(Binary {
+                span: expr.span().to(right.span()),
                 left: Box::new(expr), // Box the left expression
                 operator,
                 right: Box::new(right), // Box the right expression
@@ -472,6 +598,7 @@ impl Parser {
 
             // Create a Binary expression node wrapped in Expr enum
             expr = Expr::Binary(Box::new(Binary {
+                span: expr.span().to(right.span()),
                 left: Box::new(expr), // Box the left expression
                 operator,
                 right: Box::new(right), // Box the right expression
@@ -491,6 +618,7 @@ impl Parser {
 
             // Create a Binary expression node wrapped in Expr enum
             expr = Expr::Binary(Box::new(Binary {
+                span: expr.span().to(right.span()),
                 left: Box::new(expr), // Box the left expression
                 operator,
                 right: Box::new(right), // Box the right expression
@@ -509,6 +637,7 @@ impl Parser {
 
             // Create a Unary expression node wrapped in Expr enum
             return Ok(Expr::Unary(Box::new(Unary {
+                span: operator.span.to(right.span()),
                 operator,
                 right: Box::new(right), // Box the right expression
             })));
@@ -519,12 +648,31 @@ impl Parser {
     }
 
     pub fn call(&mut self) -> Result<Expr> {
-        // call -> primary ( "(" arguments ")" )?
+        // call -> primary ( "(" arguments ")" | "." IDENTIFIER )*
         let mut callee = self.primary()?;
 
         loop {
             if self.match_token(&[TokenType::LPAREN]) {
                 callee = self.finish_call(callee)?;
+            } else if self.match_token(&[TokenType::DOT]) {
+                let name = self
+                    .consume(&TokenType::IDENTIFIER, "Expect property name after '.'.")?
+                    .clone();
+                callee = Expr::Get(Box::new(Get {
+                    span: callee.span().to(name.span),
+                    object: Box::new(callee),
+                    name,
+                }));
+            } else if self.match_token(&[TokenType::LBRACKET]) {
+                let bracket = self.previous().clone();
+                let index = self.expression()?;
+                self.consume(&TokenType::RBRACKET, "Expect ']' after index.")?;
+                callee = Expr::IndexGet(Box::new(IndexGet {
+                    span: self.span_from(callee.span()),
+                    object: Box::new(callee),
+                    bracket,
+                    index: Box::new(index),
+                }));
             } else {
                 break;
             }
@@ -549,6 +697,7 @@ impl Parser {
 
         // Use the Call variant we defined in expr.rs
         Ok(Expr::Call(Box::new(Call {
+            span: self.span_from(callee.span()),
             callee: Box::new(callee),
             paren: self.previous().clone(),
             args,
@@ -566,30 +715,37 @@ impl Parser {
                     let value: f64 = literal_clone
                         .ok_or_else(|| {
                             LoxError::new_parse(token.clone(), "Missing literal value for number")
+                                .with_code(ErrorCode::InvalidLiteral)
                         })?
                         .parse()
-                        .map_err(|_| LoxError::new_parse(token.clone(), "Invalid number format"))?;
+                        .map_err(|_| {
+                            LoxError::new_parse(token.clone(), "Invalid number format")
+                                .with_code(ErrorCode::InvalidLiteral)
+                        })?;
 
                     return Ok(Expr::Literal(Literal {
                         value: Object::Number(value), // Wrap in Object::Number
+                        span: token.span,
                     }));
                 }
                 TokenType::STRING => {
                     let literal_clone = token.literal.clone();
                     let value: String = literal_clone.ok_or_else(|| {
                         LoxError::new_parse(token.clone(), "Missing literal value for string")
+                            .with_code(ErrorCode::InvalidLiteral)
                     })?;
 
                     return Ok(Expr::Literal(Literal {
-                        value: Object::String(value), // Wrap in Object::String
+                        value: Object::String(value.into()), // Wrap in Object::String
+                        span: token.span,
                     }));
                 }
                 _ => {
                     // This should not happen as we already checked for NUMBER and STRING
-                    return Err(LoxError::new_parse(
-                        token,
-                        "Unexpected token type in primary",
-                    ));
+                    return Err(
+                        LoxError::new_parse(token, "Unexpected token type in primary")
+                            .with_code(ErrorCode::InvalidLiteral),
+                    );
                 }
             }
         }
@@ -598,6 +754,7 @@ impl Parser {
             // Token already consumed by match_token
             return Ok(Expr::Literal(Literal {
                 value: Object::Boolean(true), // Wrap in Object::Bool
+                span: self.previous().span,
             }));
         }
 
@@ -605,6 +762,7 @@ impl Parser {
             // Token already consumed by match_token
             return Ok(Expr::Literal(Literal {
                 value: Object::Boolean(false), // Wrap in Object::Bool
+                span: self.previous().span,
             }));
         }
 
@@ -612,10 +770,97 @@ impl Parser {
             // Token already consumed by match_token
             return Ok(Expr::Literal(Literal {
                 value: Object::Nil, // Wrap in Object::Nil
+                span: self.previous().span,
             }));
         }
 
+        if self.match_token(&[TokenType::SUPER]) {
+            let keyword = self.previous().clone();
+            self.consume(&TokenType::DOT, "Expect '.' after 'super'.")?;
+            let method = self
+                .consume(&TokenType::IDENTIFIER, "Expect superclass method name.")?
+                .clone();
+            let span = keyword.span.to(method.span);
+            return Ok(Expr::Super(Box::new(Super {
+                id: ExprId::fresh(),
+                keyword,
+                method,
+                span,
+            })));
+        }
+
+        if self.match_token(&[TokenType::THIS]) {
+            return Ok(Expr::This(Box::new(This {
+                id: ExprId::fresh(),
+                keyword: self.previous().clone(),
+                span: self.previous().span,
+            })));
+        }
+
+        if self.match_token(&[TokenType::LBRACKET]) {
+            let bracket = self.previous().clone();
+            let mut elements = Vec::new();
+            if !self.check(&TokenType::RBRACKET) {
+                loop {
+                    elements.push(self.expression()?);
+                    if !self.match_token(&[TokenType::COMMA]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(&TokenType::RBRACKET, "Expect ']' after list elements.")?;
+            return Ok(Expr::List(Box::new(List {
+                span: self.span_from(bracket.span),
+                bracket,
+                elements,
+            })));
+        }
+
+        if self.match_token(&[TokenType::LBRACE]) {
+            let brace = self.previous().clone();
+            let mut entries = Vec::new();
+            if !self.check(&TokenType::RBRACE) {
+                loop {
+                    let key = self.expression()?;
+                    self.consume(&TokenType::COLON, "Expect ':' after map key.")?;
+                    let value = self.expression()?;
+                    entries.push((key, value));
+                    if !self.match_token(&[TokenType::COMMA]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(&TokenType::RBRACE, "Expect '}' after map entries.")?;
+            return Ok(Expr::Map(Box::new(Map {
+                span: self.span_from(brace.span),
+                brace,
+                entries,
+            })));
+        }
+
+        if self.match_token(&[TokenType::FUN]) {
+            let keyword = self.previous().clone();
+            let params = self.fun_parameters()?;
+            self.consume(&TokenType::LBRACE, "Expect '{' before lambda body.")?;
+            let body = self.block()?;
+            let span = self.span_from(keyword.span);
+            return Ok(Expr::Lambda(Box::new(Lambda {
+                declaration: Function {
+                    name: Box::new(keyword),
+                    parameters: Box::new(params),
+                    body: Box::new(body),
+                    span,
+                },
+                span,
+            })));
+        }
+
+        if self.check(&TokenType::LPAREN) && self.is_arrow_lambda() {
+            return self.arrow_lambda();
+        }
+
         if self.match_token(&[TokenType::LPAREN]) {
+            let start = self.previous().span;
             let expr = self.expression()?; // Parse the inner expression
 
             self.consume(&TokenType::RPAREN, "Expected closing parenthesis")?;
@@ -623,28 +868,136 @@ impl Parser {
 
             return Ok(Expr::Grouping(Box::new(Grouping {
                 expression: Box::new(expr),
+                span: self.span_from(start),
             }))); // Grouping expression
         }
 
         if self.match_token(&[TokenType::IDENTIFIER]) {
             let expr_token = self.previous().clone();
-            return Ok(Expr::Variable(Box::new(Variable { name: expr_token })));
+            return Ok(Expr::Variable(Box::new(Variable {
+                id: ExprId::fresh(),
+                span: expr_token.span,
+                name: expr_token,
+            })));
         }
 
         // If none of the above, it's an error
-        Err(LoxError::new_parse(
-            self.peek().clone(),
-            "Expect expression",
-        ))
+        Err(
+            LoxError::new_parse(self.peek().clone(), "Expect expression")
+                .with_code(ErrorCode::ExpectedExpression),
+        )
     }
 
-    pub fn parse(&mut self) -> Result<Vec<Stmt>> {
+    /// Looks ahead from the current '(' for `( identifier ( "," identifier )* )? ) =>`,
+    /// which is the only way to tell an arrow lambda apart from a grouping.
+    fn is_arrow_lambda(&self) -> bool {
+        let mut i = self.current + 1;
+        let token_type = |i: usize| self.tokens.get(i).map(|t| &t.token_type);
+
+        if token_type(i) != Some(&TokenType::RPAREN) {
+            loop {
+                if token_type(i) != Some(&TokenType::IDENTIFIER) {
+                    return false;
+                }
+                i += 1;
+                if token_type(i) != Some(&TokenType::COMMA) {
+                    break;
+                }
+                i += 1;
+            }
+            if token_type(i) != Some(&TokenType::RPAREN) {
+                return false;
+            }
+        }
+
+        token_type(i + 1) == Some(&TokenType::ARROW)
+    }
+
+    fn arrow_lambda(&mut self) -> Result<Expr> {
+        let start = self.peek().span;
+        let params = self.fun_parameters()?;
+        let arrow = self
+            .consume(&TokenType::ARROW, "Expect '=>' after lambda parameters.")?
+            .clone();
+        let value = self.expression()?;
+
+        // desugar the expression body into `{ return value; }`
+        let value_span = value.span();
+        let body = Stmt::Block(Box::new(Block {
+            statements: vec![Stmt::ReturnStmt(Box::new(ReturnStmt {
+                tok: Box::new(arrow.clone()),
+                value: Some(Box::new(value)),
+                span: value_span,
+                tail_call: Cell::new(false),
+            }))],
+            span: value_span,
+        }));
+
+        let span = self.span_from(start);
+        Ok(Expr::Lambda(Box::new(Lambda {
+            declaration: Function {
+                name: Box::new(arrow),
+                parameters: Box::new(params),
+                body: Box::new(body),
+                span,
+            },
+            span,
+        })))
+    }
+
+    /// Parses the whole program, recovering from syntax errors instead of stopping at the
+    /// first one. Returns every statement that parsed cleanly along with all errors found,
+    /// so callers can report everything at once or still work with a partial AST.
+    pub fn parse_all(&mut self) -> (Vec<Stmt>, Vec<LoxError>) {
         let mut statements = Vec::new();
         while !self.is_at_end() {
-            statements.push(self.declaration()?);
+            match self.declaration() {
+                Ok(stmt) => statements.push(stmt),
+                Err(error) => {
+                    self.errors.push(error);
+                    self.synchronize();
+                }
+            }
+        }
+
+        (statements, std::mem::take(&mut self.errors))
+    }
+
+    /// Parses the whole program, failing with the first syntax error if there were any.
+    pub fn parse(&mut self) -> Result<Vec<Stmt>> {
+        let (statements, errors) = self.parse_all();
+        match errors.into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(statements),
         }
+    }
+
+    /// Panic-mode recovery: discards tokens until we're probably at the start of the next
+    /// statement, either just past a ';' or at a keyword that begins a declaration.
+    pub fn synchronize(&mut self) {
+        self.advance();
+
+        while !self.is_at_end() {
+            if self.previous().token_type == TokenType::SEMICOLON {
+                return;
+            }
+
+            match self.peek().token_type {
+                TokenType::CLASS
+                | TokenType::FUN
+                | TokenType::VAR
+                | TokenType::FOR
+                | TokenType::IF
+                | TokenType::WHILE
+                | TokenType::PRINT
+                | TokenType::RETURN
+                | TokenType::BREAK
+                | TokenType::CONTINUE => return,
+                _ => {}
+            }
 
-        Ok(statements)
+            self.advance();
+        }
     }
 
     /// Checks if the current token matches any of the given types.
@@ -669,6 +1022,15 @@ impl Parser {
         &self.tokens[self.current - 1]
     }
 
+    /// Span running from `start` to the end of the most recently consumed token.
+    fn span_from(&self, start: Span) -> Span {
+        start.to(self.previous().span)
+    }
+
+    fn previous_is(&self, token_type: &TokenType) -> bool {
+        self.current > 0 && self.previous().token_type == *token_type
+    }
+
     pub fn check(&self, token_type: &TokenType) -> bool {
         if self.is_at_end() {
             return false;
@@ -677,6 +1039,13 @@ impl Parser {
         return self.peek().token_type == *token_type;
     }
 
+    pub fn check_next(&self, token_type: &TokenType) -> bool {
+        match self.tokens.get(self.current + 1) {
+            Some(token) => token.token_type == *token_type,
+            None => false,
+        }
+    }
+
     pub fn advance(&mut self) -> &Token {
         if !self.is_at_end() {
             self.current += 1;
@@ -709,7 +1078,8 @@ impl Parser {
         if self.check(token_type) {
             Ok(self.advance())
         } else {
-            Err(LoxError::new_parse(self.peek().clone(), message))
+            Err(LoxError::new_parse(self.peek().clone(), message)
+                .with_code(ErrorCode::ExpectedToken))
         }
     }
 }
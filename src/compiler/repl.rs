@@ -0,0 +1,51 @@
+use crate::compiler::error::{ErrorReporter, LoxError};
+use crate::compiler::error_codes::ErrorCode;
+use crate::compiler::scanner::Scanner;
+use crate::compiler::token::TokenType;
+
+// Swallows scanner errors while probing partial input; it only remembers whether a
+// string literal ran off the end of the buffer.
+struct ProbeReporter {
+    unterminated_string: bool,
+}
+
+impl ErrorReporter for ProbeReporter {
+    fn error(&mut self, _line: usize, _message: &str) {}
+
+    fn runtime_error(&mut self, _error: &LoxError) {}
+
+    fn error_with_code(&mut self, _line: usize, code: ErrorCode, _message: &str) {
+        if code == ErrorCode::UnterminatedString {
+            self.unterminated_string = true;
+        }
+    }
+}
+
+/// Returns true when the REPL should keep reading lines before running `source`:
+/// parentheses, braces or brackets are still open, or a string literal is unterminated.
+/// Extra closing delimiters count as complete so the parser can report them.
+pub fn needs_more_input(source: &str) -> bool {
+    let mut reporter = ProbeReporter {
+        unterminated_string: false,
+    };
+    let tokens = {
+        let mut scanner = Scanner::new(source.to_string(), &mut reporter);
+        scanner.scan_tokens();
+        scanner.tokens
+    };
+
+    if reporter.unterminated_string {
+        return true;
+    }
+
+    let mut depth: i64 = 0;
+    for token in &tokens {
+        match token.token_type {
+            TokenType::LPAREN | TokenType::LBRACE | TokenType::LBRACKET => depth += 1,
+            TokenType::RPAREN | TokenType::RBRACE | TokenType::RBRACKET => depth -= 1,
+            _ => {}
+        }
+    }
+
+    depth > 0
+}
@@ -1,10 +1,12 @@
 use crate::compiler::Interpreter;
+use crate::compiler::env::Slot;
 use crate::compiler::error::{LoxError, Result};
-use crate::compiler::expr::Expr;
+use crate::compiler::error_codes::ErrorCode;
 use crate::compiler::expr::ExprVisitor;
+use crate::compiler::expr::{Expr, ExprId};
 use crate::compiler::stmt::Stmt;
 use crate::compiler::stmt::StmtVisitor;
-use crate::compiler::token::{Token, TokenType};
+use crate::compiler::token::{Span, Token};
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
@@ -16,6 +18,21 @@ use super::stmt::Function;
 pub enum FunctionType {
     NONE,
     FUNCTION,
+    METHOD,
+    INITIALIZER,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClassType {
+    NONE,
+    CLASS,
+    SUBCLASS,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopType {
+    NONE,
+    LOOP,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -28,8 +45,14 @@ pub enum VarState {
 pub struct Resolver {
     pub interpreter: Rc<RefCell<Interpreter>>,
     pub scopes: RefCell<Vec<HashMap<String, VarState>>>,
+    // declaring token for each entry in `scopes`, kept alongside so errors can point at it
+    pub declarations: RefCell<Vec<HashMap<String, Token>>>,
+    // env slot for each entry in `scopes`, numbered in declaration order like Env::define
+    pub slots: RefCell<Vec<HashMap<String, usize>>>,
     pub errors: RefCell<Vec<LoxError>>,
     pub current_function: RefCell<FunctionType>,
+    pub current_class: RefCell<ClassType>,
+    pub current_loop: RefCell<LoopType>,
 }
 
 // Our primary concerns for this semantic analysis are for the following cases:
@@ -40,8 +63,50 @@ pub struct Resolver {
 
 impl StmtVisitor<()> for Resolver {
     fn visit_class(&self, class: &super::stmt::Class) -> () {
+        let enclosing_class = self.current_class.replace(ClassType::CLASS);
+
         self.declare(&class.name);
         self.define(&class.name);
+
+        if let Some(Expr::Variable(variable)) = class.superclass.as_deref()
+            && variable.name.lexeme == class.name.lexeme
+        {
+            self.error(
+                ErrorCode::InheritFromSelf,
+                &variable.name,
+                "A class can't inherit from itself.",
+            );
+        }
+
+        if let Some(superclass) = &class.superclass {
+            self.current_class.replace(ClassType::SUBCLASS);
+            self.resolve_expression(superclass);
+
+            // "super" gets its own scope outside the one holding "this"
+            self.begin_scope();
+            self.define_implicit("super");
+        }
+
+        // methods see "this" through an extra scope wrapped around them, mirroring bind()
+        self.begin_scope();
+        self.define_implicit("this");
+
+        for method in &class.methods {
+            if let Stmt::Function(function) = method {
+                let function_type = if function.name.lexeme == "init" {
+                    FunctionType::INITIALIZER
+                } else {
+                    FunctionType::METHOD
+                };
+                self.resolve_function(function, function_type);
+            }
+        }
+
+        self.end_scope();
+        if class.superclass.is_some() {
+            self.end_scope();
+        }
+        self.current_class.replace(enclosing_class);
     }
 
     fn visit_block(&self, block: &super::stmt::Block) -> () {
@@ -59,9 +124,13 @@ impl StmtVisitor<()> for Resolver {
             // Check for self reference without creating scopes
             if let Expr::Variable(name) = &var.initializer.as_ref() {
                 if name.name.lexeme == var.name.lexeme {
-                    self.error(
-                        &var.name,
-                        "Can't read local variable in its own initializer.",
+                    self.errors.borrow_mut().push(
+                        LoxError::new_parse(
+                            name.name.clone(),
+                            "Can't read local variable in its own initializer.",
+                        )
+                        .with_code(ErrorCode::SelfReferencingInitializer)
+                        .with_note("variable declared here", Some(var.name.span)),
                     );
                 }
             }
@@ -87,7 +156,7 @@ impl StmtVisitor<()> for Resolver {
             self.define(&function.name);
         }
 
-        self.resolve_function(function);
+        self.resolve_function(function, FunctionType::FUNCTION);
     }
 
     fn visit_expression(&self, expression: &super::stmt::Expression) -> () {
@@ -108,23 +177,72 @@ impl StmtVisitor<()> for Resolver {
 
     fn visit_return_stmt(&self, return_stmt: &super::stmt::ReturnStmt) -> () {
         if *self.current_function.borrow() == FunctionType::NONE {
-            self.errors.borrow_mut().push(LoxError::new_parse(
-                return_stmt.tok.as_ref().clone(),
-                "Cannot return from top level code.",
-            ))
+            self.errors.borrow_mut().push(
+                LoxError::new_parse(
+                    return_stmt.tok.as_ref().clone(),
+                    "Cannot return from top level code.",
+                )
+                .with_code(ErrorCode::TopLevelReturn),
+            )
+        }
+
+        if let Some(value) = &return_stmt.value {
+            if *self.current_function.borrow() == FunctionType::INITIALIZER {
+                self.error(
+                    ErrorCode::ReturnFromInitializer,
+                    &return_stmt.tok,
+                    "Can't return a value from an initializer.",
+                );
+            }
+            self.resolve_expression(value);
+
+            // `return f(...);` is the last thing the function does, so the call can reuse
+            // its stack space; initializers are excluded since they always return `this`
+            let in_function = matches!(
+                *self.current_function.borrow(),
+                FunctionType::FUNCTION | FunctionType::METHOD
+            );
+            if in_function && matches!(value.as_ref(), Expr::Call(_)) {
+                return_stmt.tail_call.set(true);
+            }
         }
-        self.resolve_expression(&return_stmt.value);
     }
 
     fn visit_while_stmt(&self, while_stmt: &super::stmt::WhileStmt) -> () {
         self.resolve_expression(&while_stmt.condition);
+
+        let enclosing_loop = self.current_loop.replace(LoopType::LOOP);
         self.resolve_statement(&while_stmt.body);
+        self.current_loop.replace(enclosing_loop);
+
+        if let Some(increment) = &while_stmt.increment {
+            self.resolve_expression(increment);
+        }
+    }
+
+    fn visit_break(&self, break_stmt: &super::stmt::Break) -> () {
+        if *self.current_loop.borrow() == LoopType::NONE {
+            self.error(
+                ErrorCode::LoopControlOutsideLoop,
+                &break_stmt.keyword,
+                "Can't use 'break' outside of a loop.",
+            );
+        }
+    }
+
+    fn visit_continue(&self, continue_stmt: &super::stmt::Continue) -> () {
+        if *self.current_loop.borrow() == LoopType::NONE {
+            self.error(
+                ErrorCode::LoopControlOutsideLoop,
+                &continue_stmt.keyword,
+                "Can't use 'continue' outside of a loop.",
+            );
+        }
     }
 }
 
 impl ExprVisitor<()> for Resolver {
     fn visit_variable(&self, variable: &Variable) -> () {
-        println!("Visiting variable: {}", variable.name.lexeme);
         // Check for self-referential initialization in current scope only
         let has_self_ref = {
             let scopes = self.scopes.borrow();
@@ -136,9 +254,14 @@ impl ExprVisitor<()> for Resolver {
         };
 
         if has_self_ref {
-            self.error(
-                &variable.name,
-                "Can't read local variable in its own initializer.",
+            let declared_at = self.declared_at(&variable.name.lexeme);
+            self.errors.borrow_mut().push(
+                LoxError::new_parse(
+                    variable.name.clone(),
+                    "Can't read local variable in its own initializer.",
+                )
+                .with_code(ErrorCode::SelfReferencingInitializer)
+                .with_note("variable declared here", declared_at),
             );
         }
 
@@ -146,7 +269,6 @@ impl ExprVisitor<()> for Resolver {
             let mut scopes = self.scopes.borrow_mut();
             // Mark the variable as used in whichever scope it's defined in
             for scope in scopes.iter_mut().rev() {
-                println!("Checking scope: {:?}", scope);
                 if let Some(current_state) = scope.get(&variable.name.lexeme).cloned() {
                     if current_state == VarState::DEF {
                         scope.insert(variable.name.lexeme.clone(), VarState::USE);
@@ -156,7 +278,7 @@ impl ExprVisitor<()> for Resolver {
             }
         }
 
-        self.resolve_local(&variable.name);
+        self.resolve_local(variable.id, &variable.name);
     }
 
     fn visit_call(&self, call: &super::expr::Call) -> () {
@@ -166,6 +288,82 @@ impl ExprVisitor<()> for Resolver {
         }
     }
 
+    fn visit_super(&self, sup: &super::expr::Super) -> () {
+        match *self.current_class.borrow() {
+            ClassType::NONE => {
+                self.error(
+                    ErrorCode::SuperOutsideClass,
+                    &sup.keyword,
+                    "Can't use 'super' outside of a class.",
+                );
+                return;
+            }
+            ClassType::CLASS => {
+                self.error(
+                    ErrorCode::SuperWithoutSuperclass,
+                    &sup.keyword,
+                    "Can't use 'super' in a class with no superclass.",
+                );
+                return;
+            }
+            ClassType::SUBCLASS => {}
+        }
+
+        self.resolve_local(sup.id, &sup.keyword);
+    }
+
+    fn visit_this(&self, this: &super::expr::This) -> () {
+        if *self.current_class.borrow() == ClassType::NONE {
+            self.error(
+                ErrorCode::ThisOutsideClass,
+                &this.keyword,
+                "Can't use 'this' outside of a class.",
+            );
+            return;
+        }
+
+        self.resolve_local(this.id, &this.keyword);
+    }
+
+    fn visit_list(&self, list: &super::expr::List) -> () {
+        for element in &list.elements {
+            self.resolve_expression(element);
+        }
+    }
+
+    fn visit_lambda(&self, lambda: &super::expr::Lambda) -> () {
+        // no name to declare, but parameters and body resolve like any function
+        self.resolve_function(&lambda.declaration, FunctionType::FUNCTION);
+    }
+
+    fn visit_map(&self, map: &super::expr::Map) -> () {
+        for (key, value) in &map.entries {
+            self.resolve_expression(key);
+            self.resolve_expression(value);
+        }
+    }
+
+    fn visit_index_get(&self, index_get: &super::expr::IndexGet) -> () {
+        self.resolve_expression(&index_get.object);
+        self.resolve_expression(&index_get.index);
+    }
+
+    fn visit_index_set(&self, index_set: &super::expr::IndexSet) -> () {
+        self.resolve_expression(&index_set.value);
+        self.resolve_expression(&index_set.object);
+        self.resolve_expression(&index_set.index);
+    }
+
+    fn visit_get(&self, get: &super::expr::Get) -> () {
+        // properties are looked up dynamically so only the object needs resolving
+        self.resolve_expression(&get.object);
+    }
+
+    fn visit_set(&self, set: &super::expr::Set) -> () {
+        self.resolve_expression(&set.value);
+        self.resolve_expression(&set.object);
+    }
+
     fn visit_binary(&self, binary: &super::expr::Binary) -> () {
         self.resolve_expression(&binary.left);
         self.resolve_expression(&binary.right);
@@ -173,7 +371,7 @@ impl ExprVisitor<()> for Resolver {
 
     fn visit_assign(&self, assign: &super::expr::Assign) -> () {
         self.resolve_expression(&assign.value);
-        self.resolve_local(&assign.name);
+        self.resolve_local(assign.id, &assign.name);
     }
 
     fn visit_logical(&self, logical: &super::expr::Logical) -> () {
@@ -201,37 +399,57 @@ impl ExprVisitor<()> for Resolver {
 impl Resolver {
     pub fn begin_scope(&self) {
         self.scopes.borrow_mut().push(HashMap::new());
+        self.declarations.borrow_mut().push(HashMap::new());
+        self.slots.borrow_mut().push(HashMap::new());
     }
 
     pub fn end_scope(&self) {
+        self.slots.borrow_mut().pop();
+        let declarations = self.declarations.borrow_mut().pop().unwrap_or_default();
+        let warning = |name: &str, message: String| {
+            match declarations.get(name) {
+                Some(token) => LoxError::new_warning_at(token.clone(), &message),
+                None => LoxError::new_warning(&message),
+            }
+            .with_code(ErrorCode::UnusedVariable)
+        };
+
         // iterate through current scope and check for variable that are decl or def
         for (name, kind) in self.scopes.borrow_mut().pop().unwrap().iter() {
             match kind {
                 VarState::DECL => {
-                    self.errors
-                        .borrow_mut()
-                        .push(LoxError::new_warning(&format!(
-                            "Variable '{}' is declared but never used",
-                            name
-                        )));
+                    self.errors.borrow_mut().push(warning(
+                        name,
+                        format!("Variable '{}' is declared but never used", name),
+                    ));
                 }
                 VarState::DEF => {
-                    self.errors
-                        .borrow_mut()
-                        .push(LoxError::new_warning(&format!(
-                            "Variable '{}' is defined but never used",
-                            name
-                        )));
+                    self.errors.borrow_mut().push(warning(
+                        name,
+                        format!("Variable '{}' is defined but never used", name),
+                    ));
                 }
                 VarState::USE => {}
             }
         }
     }
 
-    pub fn resolve_function(&self, func: &Function) {
+    // where the innermost variable called `name` was declared, if it was scanned from source
+    fn declared_at(&self, name: &str) -> Option<Span> {
+        self.declarations
+            .borrow()
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .map(|token| token.span)
+    }
+
+    pub fn resolve_function(&self, func: &Function, function_type: FunctionType) {
         // stash our function status - need to traxk when we enter and exit
         let enclosing_function: FunctionType = self.current_function.borrow().clone();
-        self.current_function.replace(FunctionType::FUNCTION);
+        self.current_function.replace(function_type);
+        // a function body starts outside any loop, even if declared inside one
+        let enclosing_loop = self.current_loop.replace(LoopType::NONE);
 
         self.begin_scope();
         for param in func.parameters.iter() {
@@ -251,16 +469,17 @@ impl Resolver {
             }
         }
         self.end_scope();
+        self.current_loop.replace(enclosing_loop);
         self.current_function.replace(enclosing_function);
     }
 
-    pub fn resolve_local(&self, name: &Token) {
+    pub fn resolve_local(&self, id: ExprId, name: &Token) {
         // iterate backwards through scopes to find appropriate variable to resolve
-        for (i, scope) in self.scopes.borrow().iter().enumerate().rev() {
-            if scope.contains_key(&name.lexeme) {
-                self.interpreter
-                    .borrow()
-                    .resolve(name, self.scopes.borrow().len() - i - 1);
+        let slots = self.slots.borrow();
+        for (i, scope) in slots.iter().enumerate().rev() {
+            if let Some(&index) = scope.get(&name.lexeme) {
+                let depth = slots.len() - i - 1;
+                self.interpreter.borrow().resolve(id, Slot { depth, index });
                 return;
             }
         }
@@ -277,11 +496,36 @@ impl Resolver {
         let mut scopes = self.scopes.borrow_mut();
         let current = scopes.last_mut().unwrap();
         if current.contains_key(&var.lexeme) {
-            self.errors.borrow_mut().push(LoxError::new_internal(
-                "Already a variable with this name in this scope",
-            ));
+            let previous = self.declared_at(&var.lexeme);
+            self.errors.borrow_mut().push(
+                LoxError::new_parse(
+                    var.clone(),
+                    "Already a variable with this name in this scope",
+                )
+                .with_code(ErrorCode::DuplicateDeclaration)
+                .with_note("previous declaration here", previous),
+            );
         }
         current.insert(var.lexeme.clone(), VarState::DECL);
+        if let Some(slots) = self.slots.borrow_mut().last_mut() {
+            let next = slots.len();
+            slots.entry(var.lexeme.clone()).or_insert(next);
+        }
+        if let Some(declarations) = self.declarations.borrow_mut().last_mut() {
+            declarations.insert(var.lexeme.clone(), var.clone());
+        }
+    }
+
+    // "this" and "super" are bound by the interpreter as the first slot of their own env
+    fn define_implicit(&self, name: &str) {
+        self.scopes
+            .borrow_mut()
+            .last_mut()
+            .unwrap()
+            .insert(name.to_string(), VarState::USE);
+        if let Some(slots) = self.slots.borrow_mut().last_mut() {
+            slots.insert(name.to_string(), 0);
+        }
     }
 
     pub fn define(&self, var: &Token) {
@@ -315,18 +559,22 @@ impl Resolver {
         expr.accept(self);
     }
 
-    pub fn error(&self, token: &Token, err_msg: &str) {
+    pub fn error(&self, code: ErrorCode, token: &Token, err_msg: &str) {
         self.errors
             .borrow_mut()
-            .push(LoxError::new_parse(token.clone(), err_msg));
+            .push(LoxError::new_parse(token.clone(), err_msg).with_code(code));
     }
 
     pub fn new(interpreter: Rc<RefCell<Interpreter>>) -> Self {
         Self {
             interpreter,
             scopes: RefCell::new(Vec::new()),
+            declarations: RefCell::new(Vec::new()),
+            slots: RefCell::new(Vec::new()),
             errors: RefCell::new(Vec::new()), // aggregate errors as we go
             current_function: RefCell::new(FunctionType::NONE),
+            current_class: RefCell::new(ClassType::NONE),
+            current_loop: RefCell::new(LoopType::NONE),
         }
     }
 }
@@ -1,14 +1,24 @@
 use crate::compiler::error::ErrorReporter;
+use crate::compiler::error_codes::ErrorCode;
 use crate::compiler::expr::Object;
-use crate::compiler::token::Token;
 use crate::compiler::token::TokenType;
+use crate::compiler::token::{Comment, Span, Token};
 
 pub struct Scanner<'a> {
     source: String,
     pub tokens: Vec<Token>,
+    // comments in source order, kept out of the token stream
+    pub comments: Vec<Comment>,
     start: usize,
     current: usize,
     line: usize,
+    // byte offset where the current line begins, used to work out columns
+    line_start: usize,
+    // position of the first character of the token being scanned
+    start_line: usize,
+    start_column: usize,
+    // stamped on every span, for sessions that keep several sources around
+    source_id: usize,
     error_reporter: &'a mut dyn ErrorReporter,
 }
 
@@ -17,27 +27,41 @@ impl<'a> Scanner<'a> {
         Self {
             source,
             tokens: Vec::new(),
+            comments: Vec::new(),
             start: 0,
             current: 0,
             line: 1,
+            line_start: 0,
+            start_line: 1,
+            start_column: 1,
+            source_id: 0,
             error_reporter,
         }
     }
 
+    pub fn with_source_id(mut self, source_id: usize) -> Self {
+        self.source_id = source_id;
+        self
+    }
+
     pub fn scan_tokens(&mut self) {
         // process tokens one by one
         while !self.at_end() {
             self.start = self.current;
+            self.start_line = self.line;
+            self.start_column = self.column_at(self.start);
             self.scan_token();
         }
 
         // append a EOF to stream
-        self.tokens.push(Token::new(
-            TokenType::EOF,
-            String::from(""),
-            self.line,
-            None,
-        ));
+        let end = self.source.len();
+        let span = Span::new(end, end, self.line, self.column_at(end)).in_source(self.source_id);
+        self.tokens
+            .push(Token::new(TokenType::EOF, String::from(""), self.line, None).with_span(span));
+    }
+
+    fn column_at(&self, offset: usize) -> usize {
+        self.source[self.line_start..offset].chars().count() + 1
     }
 
     fn at_end(&self) -> bool {
@@ -50,13 +74,25 @@ impl<'a> Scanner<'a> {
 
     fn add_token_literal(&mut self, token_type: TokenType, literal: Option<String>) {
         let text = self.source[self.start..self.current].to_string();
+        let span = Span::new(self.start, self.current, self.start_line, self.start_column)
+            .in_source(self.source_id);
         self.tokens
-            .push(Token::new(token_type, text, self.line, literal));
+            .push(Token::new(token_type, text, self.line, literal).with_span(span));
+    }
+
+    fn add_comment(&mut self) {
+        let text = self.source[self.start..self.current].to_string();
+        let span = Span::new(self.start, self.current, self.start_line, self.start_column)
+            .in_source(self.source_id);
+        self.comments.push(Comment { text, span });
     }
 
     fn advance(&mut self) -> char {
         let c = self.source.chars().nth(self.current).unwrap();
         self.current += c.len_utf8();
+        if c == '\n' {
+            self.line_start = self.current;
+        }
         c
     }
 
@@ -115,9 +151,11 @@ impl<'a> Scanner<'a> {
                     'n' => s.push('\n'),
                     'r' => s.push('\r'),
                     't' => s.push('\t'),
-                    _ => self
-                        .error_reporter
-                        .error(self.line, "Invalid escape sequence."),
+                    _ => self.error_reporter.error_with_code(
+                        self.line,
+                        ErrorCode::InvalidEscape,
+                        "Invalid escape sequence.",
+                    ),
                 }
                 continue;
             }
@@ -125,7 +163,11 @@ impl<'a> Scanner<'a> {
         }
 
         if self.at_end() {
-            self.error_reporter.error(self.line, "Unterminated string.");
+            self.error_reporter.error_with_code(
+                self.line,
+                ErrorCode::UnterminatedString,
+                "Unterminated string.",
+            );
             return;
         }
 
@@ -171,6 +213,8 @@ impl<'a> Scanner<'a> {
             ')' => self.add_token(TokenType::RPAREN),
             '{' => self.add_token(TokenType::LBRACE),
             '}' => self.add_token(TokenType::RBRACE),
+            '[' => self.add_token(TokenType::LBRACKET),
+            ']' => self.add_token(TokenType::RBRACKET),
             ',' => self.add_token(TokenType::COMMA),
             '.' => self.add_token(TokenType::DOT),
             '-' => self.add_token(TokenType::MINUS),
@@ -191,6 +235,8 @@ impl<'a> Scanner<'a> {
             '=' => {
                 if self.check('=') {
                     self.add_token(TokenType::EQUAL_EQUAL);
+                } else if self.check('>') {
+                    self.add_token(TokenType::ARROW);
                 } else {
                     self.add_token(TokenType::EQUAL);
                 }
@@ -215,6 +261,7 @@ impl<'a> Scanner<'a> {
                     while self.peek() != '\n' && !self.at_end() {
                         self.advance();
                     }
+                    self.add_comment();
                 } else if self.check('*') {
                     // Multiline comment
                     let mut nesting = 1;
@@ -235,9 +282,13 @@ impl<'a> Scanner<'a> {
                         }
                     }
                     if nesting > 0 {
-                        self.error_reporter
-                            .error(self.line, "Unterminated multiline comment");
+                        self.error_reporter.error_with_code(
+                            self.line,
+                            ErrorCode::UnterminatedComment,
+                            "Unterminated multiline comment",
+                        );
                     }
+                    self.add_comment();
                 } else {
                     self.add_token(TokenType::SLASH);
                 }
@@ -257,8 +308,11 @@ impl<'a> Scanner<'a> {
                 } else if c.is_ascii_alphabetic() {
                     self.identifier();
                 } else {
-                    self.error_reporter
-                        .error(self.line, &format!("Unexpected character '{}'", c));
+                    self.error_reporter.error_with_code(
+                        self.line,
+                        ErrorCode::UnexpectedCharacter,
+                        &format!("Unexpected character '{}'", c),
+                    );
                 }
             }
         }
@@ -1,6 +1,7 @@
 use crate::compiler::expr::Expr;
 use crate::compiler::expr::Object;
-use crate::compiler::token::Token;
+use crate::compiler::token::{Span, Token};
+use std::cell::Cell;
 
 #[derive(Debug, Clone)]
 pub enum Stmt {
@@ -13,6 +14,8 @@ pub enum Stmt {
     Function(Box<Function>),
     Class(Box<Class>),
     ReturnStmt(Box<ReturnStmt>),
+    Break(Box<Break>),
+    Continue(Box<Continue>),
 }
 
 pub trait StmtVisitor<T> {
@@ -25,6 +28,8 @@ pub trait StmtVisitor<T> {
     fn visit_function(&self, function: &Function) -> T;
     fn visit_class(&self, class: &Class) -> T;
     fn visit_return_stmt(&self, return_stmt: &ReturnStmt) -> T;
+    fn visit_break(&self, break_stmt: &Break) -> T;
+    fn visit_continue(&self, continue_stmt: &Continue) -> T;
 }
 
 impl Stmt {
@@ -39,6 +44,25 @@ impl Stmt {
             Stmt::Function(b) => visitor.visit_function(b),
             Stmt::Class(b) => visitor.visit_class(b),
             Stmt::ReturnStmt(b) => visitor.visit_return_stmt(b),
+            Stmt::Break(b) => visitor.visit_break(b),
+            Stmt::Continue(b) => visitor.visit_continue(b),
+        }
+    }
+
+    /// The source text this statement was parsed from, including its trailing ';'.
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::Expression(b) => b.span,
+            Stmt::Print(b) => b.span,
+            Stmt::Var(b) => b.span,
+            Stmt::Block(b) => b.span,
+            Stmt::IfStmt(b) => b.span,
+            Stmt::WhileStmt(b) => b.span,
+            Stmt::Function(b) => b.span,
+            Stmt::Class(b) => b.span,
+            Stmt::ReturnStmt(b) => b.span,
+            Stmt::Break(b) => b.span,
+            Stmt::Continue(b) => b.span,
         }
     }
 }
@@ -47,21 +71,25 @@ impl Stmt {
 pub struct Var {
     pub name: Box<Token>,
     pub initializer: Box<Expr>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct Expression {
     pub expression: Box<Expr>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct Print {
     pub expression: Box<Expr>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct Block {
     pub statements: Vec<Stmt>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
@@ -69,12 +97,16 @@ pub struct IfStmt {
     pub condition: Box<Expr>,
     pub then_branch: Box<Stmt>,
     pub else_branch: Option<Box<Stmt>>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct WhileStmt {
     pub condition: Box<Expr>,
     pub body: Box<Stmt>,
+    // only set for desugared for loops so `continue` still runs the increment
+    pub increment: Option<Box<Expr>>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
@@ -82,16 +114,35 @@ pub struct Function {
     pub name: Box<Token>,
     pub parameters: Box<Vec<Token>>,
     pub body: Box<Stmt>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct Class {
     pub name: Box<Token>,
+    pub superclass: Option<Box<Expr>>,
     pub methods: Vec<Stmt>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct ReturnStmt {
     pub tok: Box<Token>,
-    pub value: Box<Expr>,
+    pub value: Option<Box<Expr>>,
+    pub span: Span,
+    // set by the resolver when the value is a call the function can hand back to the
+    // interpreter instead of making it itself (see Interpreter::call_function)
+    pub tail_call: Cell<bool>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Break {
+    pub keyword: Box<Token>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct Continue {
+    pub keyword: Box<Token>,
+    pub span: Span,
 }
@@ -9,6 +9,8 @@ pub enum TokenType {
     RPAREN,
     LBRACE,
     RBRACE,
+    LBRACKET,
+    RBRACKET,
     COMMA,
     DOT,
     MINUS,
@@ -28,6 +30,7 @@ pub enum TokenType {
     GREATER_EQUAL,
     LESS,
     LESS_EQUAL,
+    ARROW,
 
     // literals
     IDENTIFIER,
@@ -36,7 +39,9 @@ pub enum TokenType {
 
     // keywords
     AND,
+    BREAK,
     CLASS,
+    CONTINUE,
     ELSE,
     FALSE,
     FUN,
@@ -66,6 +71,8 @@ impl FromStr for TokenType {
             ")" => Ok(Self::RPAREN),
             "{" => Ok(Self::LBRACE),
             "}" => Ok(Self::RBRACE),
+            "[" => Ok(Self::LBRACKET),
+            "]" => Ok(Self::RBRACKET),
             "," => Ok(Self::COMMA),
             "." => Ok(Self::DOT),
             "-" => Ok(Self::MINUS),
@@ -83,9 +90,12 @@ impl FromStr for TokenType {
             ">=" => Ok(Self::GREATER_EQUAL),
             "<" => Ok(Self::LESS),
             "<=" => Ok(Self::LESS_EQUAL),
+            "=>" => Ok(Self::ARROW),
             // keywords
             "and" => Ok(Self::AND),
+            "break" => Ok(Self::BREAK),
             "class" => Ok(Self::CLASS),
+            "continue" => Ok(Self::CONTINUE),
             "else" => Ok(Self::ELSE),
             "false" => Ok(Self::FALSE),
             "fun" => Ok(Self::FUN),
@@ -106,6 +116,68 @@ impl FromStr for TokenType {
     }
 }
 
+/// A region of the source text. `start` and `end` are byte offsets (end exclusive) while
+/// `line` and `column` are 1-based and point at the first character of the region.
+/// Tokens made up by the interpreter rather than scanned carry the default, empty span.
+/// `source` says which text the offsets point into when there are several, like the inputs
+/// of a REPL session; a single script is source 0.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+    pub source: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Self {
+        Self {
+            start,
+            end,
+            line,
+            column,
+            source: 0,
+        }
+    }
+
+    pub fn in_source(mut self, source: usize) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Returns the smallest span covering both `self` and `other`. Default spans are
+    /// ignored so joining with a synthetic token keeps the real location.
+    pub fn to(self, other: Span) -> Span {
+        if self == Span::default() {
+            return other;
+        }
+        if other == Span::default() {
+            return self;
+        }
+        let (first, last) = if self.start <= other.start {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        Span {
+            start: first.start,
+            end: first.end.max(last.end),
+            line: first.line,
+            column: first.column,
+            source: first.source,
+        }
+    }
+}
+
 // Token Struct
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Token {
@@ -113,6 +185,7 @@ pub struct Token {
     pub lexeme: String,
     pub line: usize,
     pub literal: Option<String>,
+    pub span: Span,
 }
 
 impl Token {
@@ -127,9 +200,15 @@ impl Token {
             lexeme,
             line,
             literal,
+            span: Span::default(),
         }
     }
 
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
     pub fn to_string(&self) -> String {
         format!(
             "{:?} {} {}",
@@ -140,4 +219,12 @@ impl Token {
     }
 }
 
+// A `//` or `/* */` comment. The parser never sees these; the scanner sets them aside so
+// tools that reprint source, like the formatter, can put them back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    pub text: String,
+    pub span: Span,
+}
+
 // RuntimeError has been moved to the error.rs module
@@ -0,0 +1,226 @@
+use crate::compiler::lox_string::LoxString;
+use crate::compiler::token::Span;
+use std::rc::Rc;
+
+// One byte per instruction, followed by its operands. u16 operands are little-endian.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum OpCode {
+    // u16 constant index
+    Constant,
+    Nil,
+    True,
+    False,
+    Pop,
+    // u8 stack slot, relative to the current frame
+    GetLocal,
+    SetLocal,
+    // u16 constant index of the name
+    GetGlobal,
+    DefineGlobal,
+    SetGlobal,
+    // u8 index into the closure's upvalues
+    GetUpvalue,
+    SetUpvalue,
+    // u16 constant index of the name
+    GetProperty,
+    SetProperty,
+    GetSuper,
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Not,
+    Negate,
+    Print,
+    // u16 forward offset from the end of the instruction
+    Jump,
+    JumpIfFalse,
+    // u16 backward offset from the end of the instruction
+    Loop,
+    // u8 argument count. TailCall replaces the current frame when calling a closure and is
+    // always followed by a Return for other callees.
+    Call,
+    TailCall,
+    // u16 constant index of the function, then an (is_local, index) byte pair per upvalue
+    Closure,
+    CloseUpvalue,
+    Return,
+    // u16 name constant of the superclass; checks the value on top of the stack is a class
+    // before it's inherited from
+    Inherit,
+    // u16 name constant, u8 method count, u8 1 if the superclass was pushed after the methods
+    Class,
+    // u16 element count
+    List,
+    // u16 entry count; keys and values alternate on the stack
+    Map,
+    IndexGet,
+    IndexSet,
+}
+
+const OPCODES: [OpCode; 42] = [
+    OpCode::Constant,
+    OpCode::Nil,
+    OpCode::True,
+    OpCode::False,
+    OpCode::Pop,
+    OpCode::GetLocal,
+    OpCode::SetLocal,
+    OpCode::GetGlobal,
+    OpCode::DefineGlobal,
+    OpCode::SetGlobal,
+    OpCode::GetUpvalue,
+    OpCode::SetUpvalue,
+    OpCode::GetProperty,
+    OpCode::SetProperty,
+    OpCode::GetSuper,
+    OpCode::Equal,
+    OpCode::NotEqual,
+    OpCode::Greater,
+    OpCode::GreaterEqual,
+    OpCode::Less,
+    OpCode::LessEqual,
+    OpCode::Add,
+    OpCode::Subtract,
+    OpCode::Multiply,
+    OpCode::Divide,
+    OpCode::Not,
+    OpCode::Negate,
+    OpCode::Print,
+    OpCode::Jump,
+    OpCode::JumpIfFalse,
+    OpCode::Loop,
+    OpCode::Call,
+    OpCode::TailCall,
+    OpCode::Closure,
+    OpCode::CloseUpvalue,
+    OpCode::Return,
+    OpCode::Inherit,
+    OpCode::Class,
+    OpCode::List,
+    OpCode::Map,
+    OpCode::IndexGet,
+    OpCode::IndexSet,
+];
+
+impl OpCode {
+    pub fn from_byte(byte: u8) -> Option<OpCode> {
+        OPCODES.get(byte as usize).copied()
+    }
+
+    // bytes of operands following the opcode; Closure is also followed by its upvalue pairs
+    pub fn operand_len(self) -> usize {
+        match self {
+            OpCode::GetLocal
+            | OpCode::SetLocal
+            | OpCode::GetUpvalue
+            | OpCode::SetUpvalue
+            | OpCode::Call
+            | OpCode::TailCall => 1,
+            OpCode::Constant
+            | OpCode::GetGlobal
+            | OpCode::DefineGlobal
+            | OpCode::SetGlobal
+            | OpCode::GetProperty
+            | OpCode::SetProperty
+            | OpCode::GetSuper
+            | OpCode::Jump
+            | OpCode::JumpIfFalse
+            | OpCode::Loop
+            | OpCode::Closure
+            | OpCode::Inherit
+            | OpCode::List
+            | OpCode::Map => 2,
+            OpCode::Class => 4,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Constant {
+    Number(f64),
+    String(LoxString),
+    Function(Rc<FunctionProto>),
+}
+
+// A compiled function: everything a closure over it shares
+#[derive(Debug)]
+pub struct FunctionProto {
+    pub name: String,
+    pub arity: usize,
+    pub upvalue_count: usize,
+    pub chunk: Chunk,
+}
+
+// Starts a run of bytecode compiled from the same piece of source; it lasts until the
+// next entry's offset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineStart {
+    pub offset: usize,
+    pub span: Span,
+}
+
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<u8>,
+    pub constants: Vec<Constant>,
+    // run-length line table; spans rather than bare lines so runtime errors can underline
+    // the same token the tree-walker would
+    pub lines: Vec<LineStart>,
+}
+
+impl Chunk {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn write(&mut self, byte: u8, span: Span) {
+        if self.lines.last().is_none_or(|last| last.span != span) {
+            self.lines.push(LineStart {
+                offset: self.code.len(),
+                span,
+            });
+        }
+        self.code.push(byte);
+    }
+
+    // numbers and strings are reused when the same value is already in the pool
+    pub fn add_constant(&mut self, constant: Constant) -> usize {
+        let existing = self
+            .constants
+            .iter()
+            .position(|known| match (known, &constant) {
+                (Constant::Number(a), Constant::Number(b)) => a.to_bits() == b.to_bits(),
+                (Constant::String(a), Constant::String(b)) => a == b,
+                _ => false,
+            });
+        existing.unwrap_or_else(|| {
+            self.constants.push(constant);
+            self.constants.len() - 1
+        })
+    }
+
+    pub fn read_u16(&self, offset: usize) -> u16 {
+        u16::from_le_bytes([self.code[offset], self.code[offset + 1]])
+    }
+
+    pub fn span_at(&self, offset: usize) -> Span {
+        let entry = self.lines.partition_point(|line| line.offset <= offset);
+        match entry {
+            0 => Span::default(),
+            n => self.lines[n - 1].span,
+        }
+    }
+
+    pub fn line_at(&self, offset: usize) -> usize {
+        self.span_at(offset).line
+    }
+}
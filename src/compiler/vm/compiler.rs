@@ -0,0 +1,842 @@
+use crate::compiler::error::{LoxError, Result};
+use crate::compiler::error_codes::ErrorCode;
+use crate::compiler::expr::{
+    Assign, Binary, Call, Expr, ExprVisitor, Get, Grouping, IndexGet, IndexSet, Lambda, List,
+    Literal, Logical, Map, Object, Set, Super, Ternary, This, Unary, Variable,
+};
+use crate::compiler::stmt::{
+    Block, Break, Class, Continue, Expression, Function, IfStmt, Print, ReturnStmt, Stmt,
+    StmtVisitor, Var, WhileStmt,
+};
+use crate::compiler::token::{Span, TokenType};
+use crate::compiler::vm::chunk::{Chunk, Constant, FunctionProto, OpCode};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+// slots and upvalues are addressed by a u8 operand
+const MAX_LOCALS: usize = 256;
+const MAX_UPVALUES: usize = 256;
+
+/// Compiles a program into the function the VM runs as its top-level script.
+pub fn compile(statements: &[Stmt]) -> Result<Rc<FunctionProto>> {
+    let compiler = Compiler::new();
+    for statement in statements {
+        statement.accept(&compiler)?;
+    }
+    compiler.emit_op(OpCode::Nil);
+    compiler.emit_op(OpCode::Return);
+    Ok(Rc::new(compiler.finish_function().0))
+}
+
+/// Compiles a lone expression into a script that returns its value, for REPL echoing.
+pub fn compile_expression(expr: &Expr) -> Result<Rc<FunctionProto>> {
+    let compiler = Compiler::new();
+    expr.accept(&compiler)?;
+    compiler.emit_op(OpCode::Return);
+    Ok(Rc::new(compiler.finish_function().0))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FunctionKind {
+    Script,
+    Function,
+    Method,
+    Initializer,
+}
+
+struct Local {
+    name: String,
+    depth: usize,
+    // captured locals get closed over rather than just popped when their scope ends
+    captured: bool,
+}
+
+// Where a closure finds a captured variable when it's created: a local slot of the
+// enclosing function, or one of the enclosing function's own upvalues
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct UpvalueSource {
+    is_local: bool,
+    index: u8,
+}
+
+struct LoopState {
+    // locals deeper than this belong to the loop body and are popped by break/continue
+    scope_depth: usize,
+    breaks: Vec<usize>,
+    continues: Vec<usize>,
+}
+
+struct FunctionState {
+    name: String,
+    arity: usize,
+    kind: FunctionKind,
+    chunk: Chunk,
+    // slot 0 holds the callee, or "this" in methods
+    locals: Vec<Local>,
+    upvalues: Vec<UpvalueSource>,
+    scope_depth: usize,
+    loops: Vec<LoopState>,
+}
+
+impl FunctionState {
+    fn new(name: &str, kind: FunctionKind) -> Self {
+        let receiver = match kind {
+            FunctionKind::Method | FunctionKind::Initializer => "this",
+            FunctionKind::Script | FunctionKind::Function => "",
+        };
+        FunctionState {
+            name: name.to_string(),
+            arity: 0,
+            kind,
+            chunk: Chunk::new(),
+            locals: vec![Local {
+                name: receiver.to_string(),
+                depth: 0,
+                captured: false,
+            }],
+            upvalues: Vec::new(),
+            // everything but the script starts inside the scope holding its parameters
+            scope_depth: if kind == FunctionKind::Script { 0 } else { 1 },
+            loops: Vec::new(),
+        }
+    }
+}
+
+// How a name resolved: a slot of the current function, one of its upvalues, or a global
+// looked up by the name constant
+enum Resolved {
+    Local(u8),
+    Upvalue(u8),
+    Global(u16),
+}
+
+// Turns the AST into bytecode. Scopes are tracked here rather than taken from the
+// Resolver since the VM addresses locals by stack slot, not by env depth.
+pub struct Compiler {
+    // the function being compiled is last, the functions enclosing it come before
+    functions: RefCell<Vec<FunctionState>>,
+    // the source that the code being emitted came from
+    span: Cell<Span>,
+}
+
+impl Compiler {
+    fn new() -> Self {
+        Compiler {
+            functions: RefCell::new(vec![FunctionState::new("script", FunctionKind::Script)]),
+            span: Cell::new(Span::default()),
+        }
+    }
+
+    fn with_function<T>(&self, f: impl FnOnce(&mut FunctionState) -> T) -> T {
+        f(self.functions.borrow_mut().last_mut().unwrap())
+    }
+
+    fn finish_function(&self) -> (FunctionProto, Vec<UpvalueSource>) {
+        let function = self.functions.borrow_mut().pop().unwrap();
+        let proto = FunctionProto {
+            name: function.name,
+            arity: function.arity,
+            upvalue_count: function.upvalues.len(),
+            chunk: function.chunk,
+        };
+        (proto, function.upvalues)
+    }
+
+    // synthetic nodes carry the default span, so they keep the location of their parent
+    fn set_span(&self, span: Span) {
+        if span != Span::default() {
+            self.span.set(span);
+        }
+    }
+
+    fn error(&self, message: &str) -> LoxError {
+        LoxError::new_from_line(self.span.get().line, message)
+    }
+
+    fn emit(&self, byte: u8) {
+        let span = self.span.get();
+        self.with_function(|function| function.chunk.write(byte, span));
+    }
+
+    fn emit_op(&self, op: OpCode) {
+        self.emit(op as u8);
+    }
+
+    fn emit_u16(&self, value: u16) {
+        for byte in value.to_le_bytes() {
+            self.emit(byte);
+        }
+    }
+
+    fn emit_op_u8(&self, op: OpCode, operand: u8) {
+        self.emit_op(op);
+        self.emit(operand);
+    }
+
+    fn emit_op_u16(&self, op: OpCode, operand: u16) {
+        self.emit_op(op);
+        self.emit_u16(operand);
+    }
+
+    fn offset(&self) -> usize {
+        self.with_function(|function| function.chunk.code.len())
+    }
+
+    // emits a jump with a placeholder target, returning where to patch it
+    fn emit_jump(&self, op: OpCode) -> usize {
+        self.emit_op_u16(op, u16::MAX);
+        self.offset() - 2
+    }
+
+    // points a jump emitted earlier at the current end of the code
+    fn patch_jump(&self, at: usize) -> Result<()> {
+        let distance = u16::try_from(self.offset() - at - 2)
+            .map_err(|_| self.error("Too much code to jump over."))?;
+        self.with_function(|function| {
+            function.chunk.code[at..at + 2].copy_from_slice(&distance.to_le_bytes());
+        });
+        Ok(())
+    }
+
+    fn emit_loop(&self, start: usize) -> Result<()> {
+        self.emit_op(OpCode::Loop);
+        let distance = u16::try_from(self.offset() - start + 2)
+            .map_err(|_| self.error("Loop body too large."))?;
+        self.emit_u16(distance);
+        Ok(())
+    }
+
+    fn make_constant(&self, constant: Constant) -> Result<u16> {
+        let index = self.with_function(|function| function.chunk.add_constant(constant));
+        u16::try_from(index).map_err(|_| self.error("Too many constants in one chunk."))
+    }
+
+    fn name_constant(&self, name: &str) -> Result<u16> {
+        self.make_constant(Constant::String(name.into()))
+    }
+
+    fn begin_scope(&self) {
+        self.with_function(|function| function.scope_depth += 1);
+    }
+
+    fn end_scope(&self) {
+        let depth = self.with_function(|function| {
+            function.scope_depth -= 1;
+            function.scope_depth
+        });
+        self.discard_locals(depth);
+        self.with_function(|function| {
+            while function
+                .locals
+                .last()
+                .is_some_and(|local| local.depth > depth)
+            {
+                function.locals.pop();
+            }
+        });
+    }
+
+    // pops the locals declared deeper than `depth` off the stack, without forgetting them
+    // at compile time since break and continue jump out of scopes that are still open
+    fn discard_locals(&self, depth: usize) {
+        let captured: Vec<bool> = self.with_function(|function| {
+            function
+                .locals
+                .iter()
+                .rev()
+                .take_while(|local| local.depth > depth)
+                .map(|local| local.captured)
+                .collect()
+        });
+        for captured in captured {
+            self.emit_op(if captured {
+                OpCode::CloseUpvalue
+            } else {
+                OpCode::Pop
+            });
+        }
+    }
+
+    fn scope_depth(&self) -> usize {
+        self.with_function(|function| function.scope_depth)
+    }
+
+    // the new local lives in whichever stack slot is pushed next
+    fn add_local(&self, name: &str) -> Result<()> {
+        let full = self.with_function(|function| function.locals.len() >= MAX_LOCALS);
+        if full {
+            return Err(self.error("Too many local variables in function."));
+        }
+        self.with_function(|function| {
+            let depth = function.scope_depth;
+            function.locals.push(Local {
+                name: name.to_string(),
+                depth,
+                captured: false,
+            });
+        });
+        Ok(())
+    }
+
+    // binds the value on top of the stack: locals just keep it in their slot
+    fn define_variable(&self, name: &str) -> Result<()> {
+        if self.scope_depth() > 0 {
+            return self.add_local(name);
+        }
+        let constant = self.name_constant(name)?;
+        self.emit_op_u16(OpCode::DefineGlobal, constant);
+        Ok(())
+    }
+
+    fn resolve_local(function: &FunctionState, name: &str) -> Option<u8> {
+        function
+            .locals
+            .iter()
+            .rposition(|local| local.name == name)
+            .map(|slot| slot as u8)
+    }
+
+    fn resolve_upvalue(
+        &self,
+        functions: &mut [FunctionState],
+        current: usize,
+        name: &str,
+    ) -> Result<Option<u8>> {
+        if current == 0 {
+            return Ok(None);
+        }
+
+        let enclosing = current - 1;
+        if let Some(slot) = Self::resolve_local(&functions[enclosing], name) {
+            functions[enclosing].locals[slot as usize].captured = true;
+            let source = UpvalueSource {
+                is_local: true,
+                index: slot,
+            };
+            return self.add_upvalue(&mut functions[current], source).map(Some);
+        }
+
+        match self.resolve_upvalue(functions, enclosing, name)? {
+            Some(index) => {
+                let source = UpvalueSource {
+                    is_local: false,
+                    index,
+                };
+                self.add_upvalue(&mut functions[current], source).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn add_upvalue(&self, function: &mut FunctionState, source: UpvalueSource) -> Result<u8> {
+        if let Some(index) = function.upvalues.iter().position(|known| *known == source) {
+            return Ok(index as u8);
+        }
+        if function.upvalues.len() >= MAX_UPVALUES {
+            return Err(self.error("Too many closure variables in function."));
+        }
+        function.upvalues.push(source);
+        Ok((function.upvalues.len() - 1) as u8)
+    }
+
+    fn resolve(&self, name: &str) -> Result<Resolved> {
+        {
+            let mut functions = self.functions.borrow_mut();
+            let current = functions.len() - 1;
+            if let Some(slot) = Self::resolve_local(&functions[current], name) {
+                return Ok(Resolved::Local(slot));
+            }
+            if let Some(index) = self.resolve_upvalue(&mut functions, current, name)? {
+                return Ok(Resolved::Upvalue(index));
+            }
+        }
+        Ok(Resolved::Global(self.name_constant(name)?))
+    }
+
+    fn emit_get(&self, name: &str) -> Result<()> {
+        match self.resolve(name)? {
+            Resolved::Local(slot) => self.emit_op_u8(OpCode::GetLocal, slot),
+            Resolved::Upvalue(index) => self.emit_op_u8(OpCode::GetUpvalue, index),
+            Resolved::Global(constant) => self.emit_op_u16(OpCode::GetGlobal, constant),
+        }
+        Ok(())
+    }
+
+    fn emit_set(&self, name: &str) -> Result<()> {
+        match self.resolve(name)? {
+            Resolved::Local(slot) => self.emit_op_u8(OpCode::SetLocal, slot),
+            Resolved::Upvalue(index) => self.emit_op_u8(OpCode::SetUpvalue, index),
+            Resolved::Global(constant) => self.emit_op_u16(OpCode::SetGlobal, constant),
+        }
+        Ok(())
+    }
+
+    fn emit_return(&self) {
+        if self.with_function(|function| function.kind) == FunctionKind::Initializer {
+            // initializers always hand back the instance
+            self.emit_op_u8(OpCode::GetLocal, 0);
+        } else {
+            self.emit_op(OpCode::Nil);
+        }
+        self.emit_op(OpCode::Return);
+    }
+
+    // compiles the body into its own FunctionProto and leaves a closure over it on the stack
+    fn function(&self, declaration: &Function, kind: FunctionKind) -> Result<()> {
+        self.set_span(declaration.span);
+        let name = match declaration.name.token_type {
+            TokenType::IDENTIFIER => declaration.name.lexeme.as_str(),
+            // lambdas are named after the keyword that introduced them
+            _ => "lambda",
+        };
+        let mut state = FunctionState::new(name, kind);
+        state.arity = declaration.parameters.len();
+        self.functions.borrow_mut().push(state);
+
+        for parameter in declaration.parameters.iter() {
+            self.add_local(&parameter.lexeme)?;
+        }
+        // the body block shares the parameters' scope, as in the tree-walker
+        let statements = match declaration.body.as_ref() {
+            Stmt::Block(block) => block.statements.as_slice(),
+            body => std::slice::from_ref(body),
+        };
+        for statement in statements {
+            statement.accept(self)?;
+        }
+        self.emit_return();
+
+        let (proto, upvalues) = self.finish_function();
+        self.set_span(declaration.span);
+        let constant = self.make_constant(Constant::Function(Rc::new(proto)))?;
+        self.emit_op_u16(OpCode::Closure, constant);
+        for upvalue in upvalues {
+            self.emit(upvalue.is_local as u8);
+            self.emit(upvalue.index);
+        }
+        Ok(())
+    }
+
+    fn call(&self, call: &Call, op: OpCode) -> Result<()> {
+        call.callee.accept(self)?;
+        for arg in &call.args {
+            arg.accept(self)?;
+        }
+        self.set_span(call.paren.span);
+        let argc = u8::try_from(call.args.len())
+            .map_err(|_| self.error("Can't have more than 255 arguments."))?;
+        self.emit_op_u8(op, argc);
+        Ok(())
+    }
+
+    // jumps out of the innermost loop; the jump is patched once the loop is compiled
+    fn loop_jump(&self, is_break: bool) -> Result<()> {
+        let depth = self.with_function(|function| function.loops.last().map(|l| l.scope_depth));
+        let depth = depth.ok_or_else(|| self.error("Can't jump outside of a loop."))?;
+        self.discard_locals(depth);
+        let jump = self.emit_jump(OpCode::Jump);
+        self.with_function(|function| {
+            let state = function.loops.last_mut().unwrap();
+            if is_break {
+                state.breaks.push(jump);
+            } else {
+                state.continues.push(jump);
+            }
+        });
+        Ok(())
+    }
+}
+
+impl StmtVisitor<Result<()>> for Compiler {
+    fn visit_expression(&self, expression: &Expression) -> Result<()> {
+        expression.expression.accept(self)?;
+        self.set_span(expression.span);
+        self.emit_op(OpCode::Pop);
+        Ok(())
+    }
+
+    fn visit_print(&self, print: &Print) -> Result<()> {
+        print.expression.accept(self)?;
+        self.set_span(print.span);
+        self.emit_op(OpCode::Print);
+        Ok(())
+    }
+
+    fn visit_var(&self, var: &Var) -> Result<()> {
+        var.initializer.accept(self)?;
+        self.set_span(var.name.span);
+        self.define_variable(&var.name.lexeme)
+    }
+
+    fn visit_block(&self, block: &Block) -> Result<()> {
+        self.begin_scope();
+        for statement in &block.statements {
+            statement.accept(self)?;
+        }
+        self.end_scope();
+        Ok(())
+    }
+
+    fn visit_if_stmt(&self, if_stmt: &IfStmt) -> Result<()> {
+        if_stmt.condition.accept(self)?;
+        let then_jump = self.emit_jump(OpCode::JumpIfFalse);
+        self.emit_op(OpCode::Pop);
+        if_stmt.then_branch.accept(self)?;
+
+        let else_jump = self.emit_jump(OpCode::Jump);
+        self.patch_jump(then_jump)?;
+        self.emit_op(OpCode::Pop);
+        if let Some(else_branch) = &if_stmt.else_branch {
+            else_branch.accept(self)?;
+        }
+        self.patch_jump(else_jump)
+    }
+
+    fn visit_while_stmt(&self, while_stmt: &WhileStmt) -> Result<()> {
+        let start = self.offset();
+        while_stmt.condition.accept(self)?;
+        let exit_jump = self.emit_jump(OpCode::JumpIfFalse);
+        self.emit_op(OpCode::Pop);
+
+        let depth = self.scope_depth();
+        self.with_function(|function| {
+            function.loops.push(LoopState {
+                scope_depth: depth,
+                breaks: Vec::new(),
+                continues: Vec::new(),
+            })
+        });
+        while_stmt.body.accept(self)?;
+        let state = self.with_function(|function| function.loops.pop().unwrap());
+
+        // continue still runs the increment of a desugared for loop
+        for jump in state.continues {
+            self.patch_jump(jump)?;
+        }
+        if let Some(increment) = &while_stmt.increment {
+            increment.accept(self)?;
+            self.emit_op(OpCode::Pop);
+        }
+        self.emit_loop(start)?;
+
+        self.patch_jump(exit_jump)?;
+        self.emit_op(OpCode::Pop);
+        // break skips the Pop above, its condition value was already popped
+        for jump in state.breaks {
+            self.patch_jump(jump)?;
+        }
+        Ok(())
+    }
+
+    fn visit_function(&self, function: &Function) -> Result<()> {
+        self.set_span(function.name.span);
+        if self.scope_depth() == 0 {
+            self.function(function, FunctionKind::Function)?;
+            let constant = self.name_constant(&function.name.lexeme)?;
+            self.emit_op_u16(OpCode::DefineGlobal, constant);
+            return Ok(());
+        }
+        // declared before the body is compiled so the function can call itself
+        self.add_local(&function.name.lexeme)?;
+        self.function(function, FunctionKind::Function)
+    }
+
+    fn visit_class(&self, class: &Class) -> Result<()> {
+        let name = &class.name.lexeme;
+        self.set_span(class.name.span);
+        let local = self.scope_depth() > 0;
+        if local {
+            // hold the slot with nil so methods can capture the class by name; the class
+            // is stored into it once built
+            self.add_local(name)?;
+            self.emit_op(OpCode::Nil);
+        }
+
+        // the superclass lives in a scope wrapped around the methods, as "super"
+        if let Some(superclass) = &class.superclass {
+            superclass.accept(self)?;
+            let superclass_name = match superclass.as_ref() {
+                Expr::Variable(variable) => &variable.name.lexeme,
+                _ => name,
+            };
+            let constant = self.name_constant(superclass_name)?;
+            self.emit_op_u16(OpCode::Inherit, constant);
+            self.begin_scope();
+            self.add_local("super")?;
+        }
+
+        for method in &class.methods {
+            if let Stmt::Function(function) = method {
+                let kind = if function.name.lexeme == "init" {
+                    FunctionKind::Initializer
+                } else {
+                    FunctionKind::Method
+                };
+                self.function(function, kind)?;
+            }
+        }
+
+        self.set_span(class.name.span);
+        if class.superclass.is_some() {
+            self.emit_get("super")?;
+        }
+        let methods = u8::try_from(class.methods.len())
+            .map_err(|_| self.error("Too many methods in one class."))?;
+        let constant = self.name_constant(name)?;
+        self.emit_op_u16(OpCode::Class, constant);
+        self.emit(methods);
+        self.emit(class.superclass.is_some() as u8);
+
+        if local {
+            self.emit_set(name)?;
+            self.emit_op(OpCode::Pop);
+        } else {
+            self.emit_op_u16(OpCode::DefineGlobal, constant);
+        }
+
+        if class.superclass.is_some() {
+            self.end_scope();
+        }
+        Ok(())
+    }
+
+    fn visit_return_stmt(&self, return_stmt: &ReturnStmt) -> Result<()> {
+        self.set_span(return_stmt.tok.span);
+        match self.with_function(|function| function.kind) {
+            FunctionKind::Script => Err(LoxError::new_parse(
+                return_stmt.tok.as_ref().clone(),
+                "Cannot return from top-level code.",
+            )
+            .with_code(ErrorCode::TopLevelReturn)),
+            FunctionKind::Initializer => {
+                if let Some(value) = &return_stmt.value {
+                    value.accept(self)?;
+                    self.emit_op(OpCode::Pop);
+                }
+                self.emit_return();
+                Ok(())
+            }
+            FunctionKind::Function | FunctionKind::Method => match &return_stmt.value {
+                // the callee takes over this frame instead of stacking a new one
+                Some(value) => match value.as_ref() {
+                    Expr::Call(call) => {
+                        self.call(call, OpCode::TailCall)?;
+                        self.emit_op(OpCode::Return);
+                        Ok(())
+                    }
+                    value => {
+                        value.accept(self)?;
+                        self.emit_op(OpCode::Return);
+                        Ok(())
+                    }
+                },
+                None => {
+                    self.emit_return();
+                    Ok(())
+                }
+            },
+        }
+    }
+
+    fn visit_break(&self, break_stmt: &Break) -> Result<()> {
+        self.set_span(break_stmt.keyword.span);
+        self.loop_jump(true)
+    }
+
+    fn visit_continue(&self, continue_stmt: &Continue) -> Result<()> {
+        self.set_span(continue_stmt.keyword.span);
+        self.loop_jump(false)
+    }
+}
+
+impl ExprVisitor<Result<()>> for Compiler {
+    fn visit_binary(&self, binary: &Binary) -> Result<()> {
+        binary.left.accept(self)?;
+        binary.right.accept(self)?;
+        self.set_span(binary.operator.span);
+        let op = match binary.operator.token_type {
+            TokenType::PLUS => OpCode::Add,
+            TokenType::MINUS => OpCode::Subtract,
+            TokenType::STAR => OpCode::Multiply,
+            TokenType::SLASH => OpCode::Divide,
+            TokenType::GREATER => OpCode::Greater,
+            TokenType::GREATER_EQUAL => OpCode::GreaterEqual,
+            TokenType::LESS => OpCode::Less,
+            TokenType::LESS_EQUAL => OpCode::LessEqual,
+            TokenType::EQUAL_EQUAL => OpCode::Equal,
+            TokenType::BANG_EQUAL => OpCode::NotEqual,
+            _ => {
+                return Err(LoxError::new_parse(
+                    binary.operator.clone(),
+                    &format!("Unknown binary operator: {:?}", binary.operator.token_type),
+                ));
+            }
+        };
+        self.emit_op(op);
+        Ok(())
+    }
+
+    fn visit_grouping(&self, grouping: &Grouping) -> Result<()> {
+        grouping.expression.accept(self)
+    }
+
+    fn visit_literal(&self, literal: &Literal) -> Result<()> {
+        self.set_span(literal.span);
+        match &literal.value {
+            Object::Nil => self.emit_op(OpCode::Nil),
+            Object::Boolean(true) => self.emit_op(OpCode::True),
+            Object::Boolean(false) => self.emit_op(OpCode::False),
+            Object::Number(n) => {
+                let constant = self.make_constant(Constant::Number(*n))?;
+                self.emit_op_u16(OpCode::Constant, constant);
+            }
+            Object::String(s) => {
+                let constant = self.make_constant(Constant::String(s.clone()))?;
+                self.emit_op_u16(OpCode::Constant, constant);
+            }
+            other => {
+                return Err(LoxError::new_internal(&format!(
+                    "can't compile the literal {:?}",
+                    other
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_unary(&self, unary: &Unary) -> Result<()> {
+        unary.right.accept(self)?;
+        self.set_span(unary.operator.span);
+        match unary.operator.token_type {
+            TokenType::MINUS => self.emit_op(OpCode::Negate),
+            TokenType::BANG => self.emit_op(OpCode::Not),
+            _ => {
+                return Err(LoxError::new_parse(
+                    unary.operator.clone(),
+                    &format!("Unknown unary operator: {:?}", unary.operator.token_type),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_ternary(&self, ternary: &Ternary) -> Result<()> {
+        ternary.condition.accept(self)?;
+        let else_jump = self.emit_jump(OpCode::JumpIfFalse);
+        self.emit_op(OpCode::Pop);
+        ternary.true_branch.accept(self)?;
+        let end_jump = self.emit_jump(OpCode::Jump);
+        self.patch_jump(else_jump)?;
+        self.emit_op(OpCode::Pop);
+        ternary.false_branch.accept(self)?;
+        self.patch_jump(end_jump)
+    }
+
+    fn visit_variable(&self, variable: &Variable) -> Result<()> {
+        self.set_span(variable.name.span);
+        self.emit_get(&variable.name.lexeme)
+    }
+
+    fn visit_assign(&self, assign: &Assign) -> Result<()> {
+        assign.value.accept(self)?;
+        self.set_span(assign.name.span);
+        self.emit_set(&assign.name.lexeme)
+    }
+
+    fn visit_logical(&self, logical: &Logical) -> Result<()> {
+        logical.left.accept(self)?;
+        self.set_span(logical.operator.span);
+        // the left value is the result when it decides the outcome
+        let end_jump = if logical.operator.token_type == TokenType::OR {
+            let else_jump = self.emit_jump(OpCode::JumpIfFalse);
+            let end_jump = self.emit_jump(OpCode::Jump);
+            self.patch_jump(else_jump)?;
+            end_jump
+        } else {
+            self.emit_jump(OpCode::JumpIfFalse)
+        };
+        self.emit_op(OpCode::Pop);
+        logical.right.accept(self)?;
+        self.patch_jump(end_jump)
+    }
+
+    fn visit_call(&self, call: &Call) -> Result<()> {
+        self.call(call, OpCode::Call)
+    }
+
+    fn visit_get(&self, get: &Get) -> Result<()> {
+        get.object.accept(self)?;
+        self.set_span(get.name.span);
+        let constant = self.name_constant(&get.name.lexeme)?;
+        self.emit_op_u16(OpCode::GetProperty, constant);
+        Ok(())
+    }
+
+    fn visit_set(&self, set: &Set) -> Result<()> {
+        set.object.accept(self)?;
+        set.value.accept(self)?;
+        self.set_span(set.name.span);
+        let constant = self.name_constant(&set.name.lexeme)?;
+        self.emit_op_u16(OpCode::SetProperty, constant);
+        Ok(())
+    }
+
+    fn visit_this(&self, this: &This) -> Result<()> {
+        self.set_span(this.keyword.span);
+        self.emit_get("this")
+    }
+
+    fn visit_super(&self, sup: &Super) -> Result<()> {
+        self.set_span(sup.keyword.span);
+        self.emit_get("this")?;
+        self.emit_get("super")?;
+        self.set_span(sup.method.span);
+        let constant = self.name_constant(&sup.method.lexeme)?;
+        self.emit_op_u16(OpCode::GetSuper, constant);
+        Ok(())
+    }
+
+    fn visit_list(&self, list: &List) -> Result<()> {
+        for element in &list.elements {
+            element.accept(self)?;
+        }
+        self.set_span(list.bracket.span);
+        let count = u16::try_from(list.elements.len())
+            .map_err(|_| self.error("Too many elements in a list literal."))?;
+        self.emit_op_u16(OpCode::List, count);
+        Ok(())
+    }
+
+    fn visit_index_get(&self, index_get: &IndexGet) -> Result<()> {
+        index_get.object.accept(self)?;
+        index_get.index.accept(self)?;
+        self.set_span(index_get.bracket.span);
+        self.emit_op(OpCode::IndexGet);
+        Ok(())
+    }
+
+    fn visit_index_set(&self, index_set: &IndexSet) -> Result<()> {
+        index_set.object.accept(self)?;
+        index_set.index.accept(self)?;
+        index_set.value.accept(self)?;
+        self.set_span(index_set.bracket.span);
+        self.emit_op(OpCode::IndexSet);
+        Ok(())
+    }
+
+    fn visit_map(&self, map: &Map) -> Result<()> {
+        for (key, value) in &map.entries {
+            key.accept(self)?;
+            value.accept(self)?;
+        }
+        self.set_span(map.brace.span);
+        let count = u16::try_from(map.entries.len())
+            .map_err(|_| self.error("Too many entries in a map literal."))?;
+        self.emit_op_u16(OpCode::Map, count);
+        Ok(())
+    }
+
+    fn visit_lambda(&self, lambda: &Lambda) -> Result<()> {
+        self.function(&lambda.declaration, FunctionKind::Function)
+    }
+}
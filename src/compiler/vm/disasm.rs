@@ -0,0 +1,156 @@
+use crate::compiler::vm::chunk::{Chunk, Constant, FunctionProto, OpCode};
+use std::fmt::Write;
+
+// Human readable listings of compiled bytecode, printed by `rlox disasm`. Each function is
+// listed under a "== name ==" heading, followed by the functions it defines:
+//
+// == script ==
+// 0000    1 CONSTANT            0 '1'
+// 0003    | CONSTANT            1 '2'
+// 0006    | ADD
+// 0007    | PRINT
+//
+// The columns are the byte offset, the source line ('|' when it's the same as the line
+// before) and the instruction with its operands.
+
+/// Lists a function and every function nested inside it.
+pub fn disassemble(function: &FunctionProto) -> String {
+    let mut out = String::new();
+    disassemble_function(function, &mut out);
+    out
+}
+
+fn disassemble_function(function: &FunctionProto, out: &mut String) {
+    let _ = writeln!(out, "== {} ==", function.name);
+    let chunk = &function.chunk;
+    let mut offset = 0;
+    while offset < chunk.code.len() {
+        offset = disassemble_instruction(chunk, offset, out);
+    }
+
+    for constant in &chunk.constants {
+        if let Constant::Function(nested) = constant {
+            let _ = writeln!(out);
+            disassemble_function(nested, out);
+        }
+    }
+}
+
+/// Writes the instruction at `offset` as one line (plus one per captured variable for
+/// closures) and returns the offset of the next instruction.
+pub fn disassemble_instruction(chunk: &Chunk, offset: usize, out: &mut String) -> usize {
+    let _ = write!(out, "{:04} ", offset);
+    let line = chunk.line_at(offset);
+    if offset > 0 && line == chunk.line_at(offset - 1) {
+        let _ = write!(out, "   | ");
+    } else {
+        let _ = write!(out, "{:4} ", line);
+    }
+
+    let op = match OpCode::from_byte(chunk.code[offset]) {
+        Some(op) => op,
+        None => {
+            let _ = writeln!(out, "UNKNOWN {}", chunk.code[offset]);
+            return offset + 1;
+        }
+    };
+    let name = mnemonic(op);
+    let next = offset + 1 + op.operand_len();
+    let byte = |at: usize| chunk.code[offset + at];
+    let constant = |index: u16| describe(&chunk.constants[index as usize]);
+
+    match op {
+        OpCode::Constant
+        | OpCode::GetGlobal
+        | OpCode::DefineGlobal
+        | OpCode::SetGlobal
+        | OpCode::GetProperty
+        | OpCode::SetProperty
+        | OpCode::GetSuper
+        | OpCode::Inherit => {
+            let index = chunk.read_u16(offset + 1);
+            let _ = writeln!(out, "{:<16} {:4} {}", name, index, constant(index));
+        }
+        OpCode::GetLocal
+        | OpCode::SetLocal
+        | OpCode::GetUpvalue
+        | OpCode::SetUpvalue
+        | OpCode::Call
+        | OpCode::TailCall => {
+            let _ = writeln!(out, "{:<16} {:4}", name, byte(1));
+        }
+        OpCode::List | OpCode::Map => {
+            let _ = writeln!(out, "{:<16} {:4}", name, chunk.read_u16(offset + 1));
+        }
+        OpCode::Jump | OpCode::JumpIfFalse => {
+            let target = next + chunk.read_u16(offset + 1) as usize;
+            let _ = writeln!(out, "{:<16} {:4} -> {}", name, offset, target);
+        }
+        OpCode::Loop => {
+            let target = next - chunk.read_u16(offset + 1) as usize;
+            let _ = writeln!(out, "{:<16} {:4} -> {}", name, offset, target);
+        }
+        OpCode::Class => {
+            let index = chunk.read_u16(offset + 1);
+            let superclass = if byte(4) == 1 { ", inherits" } else { "" };
+            let _ = writeln!(
+                out,
+                "{:<16} {:4} {} ({} methods{})",
+                name,
+                index,
+                constant(index),
+                byte(3),
+                superclass
+            );
+        }
+        OpCode::Closure => {
+            let index = chunk.read_u16(offset + 1);
+            let _ = writeln!(out, "{:<16} {:4} {}", name, index, constant(index));
+            let upvalue_count = match &chunk.constants[index as usize] {
+                Constant::Function(function) => function.upvalue_count,
+                _ => 0,
+            };
+            let mut at = next;
+            for _ in 0..upvalue_count {
+                let kind = if chunk.code[at] == 1 {
+                    "local"
+                } else {
+                    "upvalue"
+                };
+                let _ = writeln!(
+                    out,
+                    "{:04}    |                     {} {}",
+                    at,
+                    kind,
+                    chunk.code[at + 1]
+                );
+                at += 2;
+            }
+            return at;
+        }
+        _ => {
+            let _ = writeln!(out, "{}", name);
+        }
+    }
+    next
+}
+
+// GetLocal -> GET_LOCAL
+fn mnemonic(op: OpCode) -> String {
+    let mut name = String::new();
+    for (i, c) in format!("{:?}", op).chars().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            name.push('_');
+        }
+        name.push(c.to_ascii_uppercase());
+    }
+    name
+}
+
+fn describe(constant: &Constant) -> String {
+    match constant {
+        Constant::Number(n) => format!("'{}'", n),
+        Constant::String(s) => format!("{:?}", s),
+        Constant::Function(function) => format!("<fn {}>", function.name),
+    }
+}
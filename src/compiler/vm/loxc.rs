@@ -0,0 +1,212 @@
+use crate::compiler::error::{LoxError, Result};
+use crate::compiler::error_codes::ErrorCode;
+use crate::compiler::token::Span;
+use crate::compiler::vm::chunk::{Chunk, Constant, FunctionProto, LineStart};
+use crate::compiler::vm::verify::verify;
+use std::rc::Rc;
+
+// The `.loxc` format: a compiled script saved to disk so it can be run without the source.
+// All integers are little-endian.
+//
+//   header    "LOXC" magic, u16 format version
+//   function  the top-level script:
+//     name        string
+//     arity       u8
+//     upvalues    u16 count
+//     code        u32 length, then the bytecode
+//     constants   u32 count, then per constant a u8 tag and its value:
+//                   0 number (f64 bits), 1 string, 2 function (nested, same layout)
+//     lines       u32 count, then per entry u32 offset, start, end, line and column
+//
+// Strings are a u32 byte length followed by UTF-8. Bump FORMAT_VERSION whenever the layout
+// or the meaning of an opcode changes; older files are then rejected rather than misread.
+pub const MAGIC: &[u8; 4] = b"LOXC";
+pub const FORMAT_VERSION: u16 = 1;
+
+const TAG_NUMBER: u8 = 0;
+const TAG_STRING: u8 = 1;
+const TAG_FUNCTION: u8 = 2;
+
+// far deeper than any real program, but keeps a crafted file from overflowing the stack
+const MAX_NESTING: usize = 256;
+
+/// Serializes a compiled script into the `.loxc` format.
+pub fn write(script: &FunctionProto) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    write_function(&mut out, script);
+    out
+}
+
+fn write_function(out: &mut Vec<u8>, function: &FunctionProto) {
+    write_string(out, &function.name);
+    out.push(function.arity as u8);
+    out.extend_from_slice(&(function.upvalue_count as u16).to_le_bytes());
+
+    let chunk = &function.chunk;
+    write_u32(out, chunk.code.len());
+    out.extend_from_slice(&chunk.code);
+
+    write_u32(out, chunk.constants.len());
+    for constant in &chunk.constants {
+        match constant {
+            Constant::Number(n) => {
+                out.push(TAG_NUMBER);
+                out.extend_from_slice(&n.to_bits().to_le_bytes());
+            }
+            Constant::String(s) => {
+                out.push(TAG_STRING);
+                write_string(out, s);
+            }
+            Constant::Function(function) => {
+                out.push(TAG_FUNCTION);
+                write_function(out, function);
+            }
+        }
+    }
+
+    write_u32(out, chunk.lines.len());
+    for line in &chunk.lines {
+        let span = line.span;
+        for value in [line.offset, span.start, span.end, span.line, span.column] {
+            write_u32(out, value);
+        }
+    }
+}
+
+fn write_u32(out: &mut Vec<u8>, value: usize) {
+    out.extend_from_slice(&(value as u32).to_le_bytes());
+}
+
+fn write_string(out: &mut Vec<u8>, s: &str) {
+    write_u32(out, s.len());
+    out.extend_from_slice(s.as_bytes());
+}
+
+/// Loads a `.loxc` file, checking its header and verifying every function's bytecode so a
+/// truncated, corrupt or hand-edited file is rejected before anything runs.
+pub fn read(bytes: &[u8]) -> Result<Rc<FunctionProto>> {
+    if !bytes.starts_with(MAGIC) {
+        return Err(invalid("not a compiled Lox file (missing LOXC header)"));
+    }
+    let mut reader = Reader {
+        bytes,
+        pos: MAGIC.len(),
+    };
+    let version = reader.u16()?;
+    if version != FORMAT_VERSION {
+        return Err(invalid(&format!(
+            "compiled with format version {}, but this interpreter reads version {}; \
+             recompile the script",
+            version, FORMAT_VERSION
+        ))
+        .with_code(ErrorCode::BytecodeVersion));
+    }
+
+    let script = reader.function(0)?;
+    if reader.pos != bytes.len() {
+        return Err(invalid("unexpected data after the script"));
+    }
+    if script.arity != 0 || script.upvalue_count != 0 {
+        return Err(invalid(
+            "the top-level script can't take arguments or capture variables",
+        ));
+    }
+    verify(&script)?;
+    Ok(Rc::new(script))
+}
+
+pub(super) fn invalid(message: &str) -> LoxError {
+    let mut error = LoxError::new_from_line(0, &format!("Invalid bytecode file: {}.", message))
+        .with_code(ErrorCode::InvalidBytecode);
+    // nothing in the file maps back to a source line
+    error.line = None;
+    error
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| invalid("file is truncated"))?;
+        let taken = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<usize> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.u32()?;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| invalid("string is not valid UTF-8"))
+    }
+
+    fn function(&mut self, nesting: usize) -> Result<FunctionProto> {
+        if nesting > MAX_NESTING {
+            return Err(invalid("functions are nested too deeply"));
+        }
+        let name = self.string()?;
+        let arity = self.u8()? as usize;
+        let upvalue_count = self.u16()? as usize;
+
+        let mut chunk = Chunk::new();
+        let code_len = self.u32()?;
+        chunk.code = self.take(code_len)?.to_vec();
+
+        // counts aren't trusted for preallocation; a bogus one just runs out of bytes
+        for _ in 0..self.u32()? {
+            let constant = match self.u8()? {
+                TAG_NUMBER => {
+                    let bytes = self.take(8)?;
+                    Constant::Number(f64::from_bits(u64::from_le_bytes(
+                        bytes.try_into().unwrap(),
+                    )))
+                }
+                TAG_STRING => Constant::String(self.string()?.into()),
+                TAG_FUNCTION => Constant::Function(Rc::new(self.function(nesting + 1)?)),
+                tag => return Err(invalid(&format!("unknown constant tag {}", tag))),
+            };
+            chunk.constants.push(constant);
+        }
+
+        for _ in 0..self.u32()? {
+            let offset = self.u32()?;
+            let start = self.u32()?;
+            let end = self.u32()?;
+            let line = self.u32()?;
+            let column = self.u32()?;
+            chunk.lines.push(LineStart {
+                offset,
+                span: Span::new(start, end, line, column),
+            });
+        }
+
+        Ok(FunctionProto {
+            name,
+            arity,
+            upvalue_count,
+            chunk,
+        })
+    }
+}
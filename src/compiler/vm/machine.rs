@@ -0,0 +1,793 @@
+use crate::compiler::error::{LoxError, LoxErrorKind, Result, StackFrame};
+use crate::compiler::error_codes::ErrorCode;
+use crate::compiler::expr::{LoxCallable, LoxMethod, Object};
+use crate::compiler::gc;
+use crate::compiler::interpreter::{ActiveCall, Interpreter};
+use crate::compiler::lox_class::LoxClass;
+use crate::compiler::lox_instance::LoxInstance;
+use crate::compiler::lox_map::LoxMap;
+use crate::compiler::natives::{list_index, map_key};
+use crate::compiler::token::{Span, Token, TokenType};
+use crate::compiler::vm::chunk::{Constant, FunctionProto, OpCode};
+use crate::compiler::vm::object::{BoundMethod, Closure, Upvalue, UpvalueRef};
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+struct CallFrame {
+    closure: Rc<Closure>,
+    ip: usize,
+    // stack index of slot 0: the callee, or the receiver for methods
+    base: usize,
+    // the call that made this frame, for stack traces; None for the top-level script
+    call_site: Option<Span>,
+}
+
+// What the callee of a call instruction turned out to be
+enum Callee {
+    Closure(Rc<Closure>),
+    Method(Object, Rc<Closure>),
+    Class(Rc<LoxClass>),
+    Native(Rc<dyn LoxCallable>),
+}
+
+// A stack machine running compiled functions. Globals, natives and the call depth limit
+// come from the Interpreter it runs for, so both backends see the same session state.
+pub struct Vm<'a> {
+    interpreter: &'a Interpreter,
+    stack: Vec<Object>,
+    frames: Vec<CallFrame>,
+    // captured variables whose slots are still on the stack
+    open_upvalues: Vec<UpvalueRef>,
+}
+
+impl<'a> Vm<'a> {
+    pub fn new(interpreter: &'a Interpreter) -> Self {
+        Vm {
+            interpreter,
+            stack: Vec::new(),
+            frames: Vec::new(),
+            open_upvalues: Vec::new(),
+        }
+    }
+
+    /// Runs a compiled script, returning the value it returns (nil for whole programs).
+    pub fn run(&mut self, script: Rc<FunctionProto>) -> Result<Object> {
+        let closure = Rc::new(Closure {
+            function: script,
+            upvalues: Vec::new(),
+        });
+        self.stack.push(Object::Function(closure.clone()));
+        self.frames.push(CallFrame {
+            closure,
+            ip: 0,
+            base: 0,
+            call_site: None,
+        });
+        self.execute()
+    }
+
+    /// Calls a function or class from outside any running bytecode.
+    pub fn call_value(&mut self, callee: Object, args: &[Object]) -> Result<Object> {
+        self.stack.push(callee);
+        self.stack.extend_from_slice(args);
+        let result = self.call(args.len(), Span::default());
+        result.map_err(|error| self.attach_trace(error, None))?;
+        if self.frames.is_empty() {
+            return Ok(self.pop());
+        }
+        self.execute()
+    }
+
+    fn execute(&mut self) -> Result<Object> {
+        self.run_frames()
+            .map_err(|error| self.attach_trace(error, None))
+    }
+
+    fn run_frames(&mut self) -> Result<Object> {
+        loop {
+            let byte = self.read_byte();
+            let op = OpCode::from_byte(byte)
+                .ok_or_else(|| LoxError::new_internal(&format!("unknown opcode {}", byte)))?;
+
+            match op {
+                OpCode::Constant => {
+                    let index = self.read_u16();
+                    let value = match &self.closure().function.chunk.constants[index as usize] {
+                        Constant::Number(n) => Object::Number(*n),
+                        Constant::String(s) => Object::String(s.clone()),
+                        Constant::Function(_) => {
+                            return Err(LoxError::new_internal("function used as a value"));
+                        }
+                    };
+                    self.stack.push(value);
+                }
+                OpCode::Nil => self.stack.push(Object::Nil),
+                OpCode::True => self.stack.push(Object::Boolean(true)),
+                OpCode::False => self.stack.push(Object::Boolean(false)),
+                OpCode::Pop => {
+                    self.pop();
+                }
+
+                OpCode::GetLocal => {
+                    let slot = self.read_byte() as usize;
+                    let value = self.stack[self.base() + slot].clone();
+                    self.stack.push(value);
+                }
+                OpCode::SetLocal => {
+                    let slot = self.read_byte() as usize;
+                    let base = self.base();
+                    self.stack[base + slot] = self.peek(0).clone();
+                }
+                OpCode::GetGlobal => {
+                    let closure = self.closure();
+                    let name = constant_name(&closure, self.read_u16())?;
+                    let value = self.interpreter._globals.borrow().lookup(name);
+                    match value {
+                        Some(value) => self.stack.push(value),
+                        None => {
+                            return Err(self.error(
+                                TokenType::IDENTIFIER,
+                                name,
+                                &format!("Undefined variable '{}' during get.", name),
+                                ErrorCode::UndefinedVariable,
+                            ));
+                        }
+                    }
+                }
+                OpCode::DefineGlobal => {
+                    let closure = self.closure();
+                    let name = constant_name(&closure, self.read_u16())?;
+                    let value = self.pop();
+                    self.interpreter._globals.borrow_mut().define(name, value);
+                }
+                OpCode::SetGlobal => {
+                    let closure = self.closure();
+                    let name = constant_name(&closure, self.read_u16())?;
+                    let value = self.peek(0).clone();
+                    let assigned = self
+                        .interpreter
+                        ._globals
+                        .borrow_mut()
+                        .assign_global(name, value);
+                    if !assigned {
+                        return Err(self.error(
+                            TokenType::IDENTIFIER,
+                            name,
+                            &format!("Undefined variable '{}' during assign.", name),
+                            ErrorCode::UndefinedVariable,
+                        ));
+                    }
+                }
+                OpCode::GetUpvalue => {
+                    let index = self.read_byte() as usize;
+                    let upvalue = self.closure().upvalues[index].clone();
+                    let value = match &*upvalue.borrow() {
+                        Upvalue::Open(slot) => self.stack[*slot].clone(),
+                        Upvalue::Closed(value) => value.clone(),
+                    };
+                    self.stack.push(value);
+                }
+                OpCode::SetUpvalue => {
+                    let index = self.read_byte() as usize;
+                    let upvalue = self.closure().upvalues[index].clone();
+                    let value = self.peek(0).clone();
+                    match &mut *upvalue.borrow_mut() {
+                        Upvalue::Open(slot) => self.stack[*slot] = value,
+                        Upvalue::Closed(closed) => *closed = value,
+                    }
+                }
+
+                OpCode::GetProperty => {
+                    let closure = self.closure();
+                    let name = constant_name(&closure, self.read_u16())?;
+                    let instance = match self.pop() {
+                        Object::Instance(instance) => instance,
+                        _ => {
+                            return Err(self.error(
+                                TokenType::IDENTIFIER,
+                                name,
+                                "Only instances have properties.",
+                                ErrorCode::PropertyOnNonInstance,
+                            ));
+                        }
+                    };
+                    let field = instance.fields.borrow().get(name).cloned();
+                    // fields shadow methods, so only fall back to the class afterwards
+                    let value = match field {
+                        Some(value) => value,
+                        None => match instance.klass.find_method(name) {
+                            Some(method) => Object::Function(method.bind(instance.clone())),
+                            None => {
+                                return Err(self.undefined_property(name));
+                            }
+                        },
+                    };
+                    self.stack.push(value);
+                }
+                OpCode::SetProperty => {
+                    let closure = self.closure();
+                    let name = constant_name(&closure, self.read_u16())?;
+                    let value = self.pop();
+                    match self.pop() {
+                        Object::Instance(instance) => {
+                            instance
+                                .fields
+                                .borrow_mut()
+                                .insert(name.to_string(), value.clone());
+                        }
+                        _ => {
+                            return Err(self.error(
+                                TokenType::IDENTIFIER,
+                                name,
+                                "Only instances have fields.",
+                                ErrorCode::PropertyOnNonInstance,
+                            ));
+                        }
+                    }
+                    self.stack.push(value);
+                }
+                OpCode::GetSuper => {
+                    let closure = self.closure();
+                    let name = constant_name(&closure, self.read_u16())?;
+                    let superclass = match self.pop() {
+                        Object::Class(superclass) => superclass,
+                        _ => {
+                            return Err(self.error(
+                                TokenType::SUPER,
+                                "super",
+                                "Superclass must be a class.",
+                                ErrorCode::SuperclassNotClass,
+                            ));
+                        }
+                    };
+                    let instance = match self.pop() {
+                        Object::Instance(instance) => instance,
+                        _ => {
+                            return Err(self.error(
+                                TokenType::SUPER,
+                                "super",
+                                "Can't use 'super' without an instance.",
+                                ErrorCode::RuntimeError,
+                            ));
+                        }
+                    };
+                    match superclass.find_method(name) {
+                        Some(method) => self.stack.push(Object::Function(method.bind(instance))),
+                        None => return Err(self.undefined_property(name)),
+                    }
+                }
+
+                OpCode::Equal => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    self.stack.push(Object::Boolean(left == right));
+                }
+                OpCode::NotEqual => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    self.stack.push(Object::Boolean(left != right));
+                }
+                OpCode::Greater
+                | OpCode::GreaterEqual
+                | OpCode::Less
+                | OpCode::LessEqual
+                | OpCode::Add
+                | OpCode::Subtract
+                | OpCode::Multiply
+                | OpCode::Divide => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    let value = self.binary(op, left, right)?;
+                    self.stack.push(value);
+                }
+                OpCode::Not => {
+                    let value = self.pop();
+                    self.stack
+                        .push(Object::Boolean(!Interpreter::is_truthy(value)));
+                }
+                OpCode::Negate => match self.pop() {
+                    Object::Number(n) => self.stack.push(Object::Number(-n)),
+                    value => {
+                        let operator = self.token(TokenType::MINUS, "-");
+                        let value = Interpreter::unary_op(&operator, value)?;
+                        self.stack.push(value);
+                    }
+                },
+                OpCode::Print => {
+                    let value = self.pop();
+                    println!("{:?}", value);
+                }
+
+                OpCode::Jump => {
+                    let distance = self.read_u16() as usize;
+                    self.frame_mut().ip += distance;
+                }
+                OpCode::JumpIfFalse => {
+                    let distance = self.read_u16() as usize;
+                    if !Interpreter::is_truthy(self.peek(0).clone()) {
+                        self.frame_mut().ip += distance;
+                    }
+                }
+                OpCode::Loop => {
+                    let distance = self.read_u16() as usize;
+                    self.frame_mut().ip -= distance;
+                }
+
+                OpCode::Call => {
+                    let argc = self.read_byte() as usize;
+                    let call_site = self.span();
+                    self.call(argc, call_site)?;
+                }
+                OpCode::TailCall => {
+                    let argc = self.read_byte() as usize;
+                    self.tail_call(argc)?;
+                }
+                OpCode::Closure => {
+                    let closure = self.closure();
+                    let index = self.read_u16() as usize;
+                    let function = match &closure.function.chunk.constants[index] {
+                        Constant::Function(function) => function.clone(),
+                        _ => return Err(LoxError::new_internal("closure over a non-function")),
+                    };
+                    let base = self.base();
+                    let mut upvalues = Vec::with_capacity(function.upvalue_count);
+                    for _ in 0..function.upvalue_count {
+                        let is_local = self.read_byte() == 1;
+                        let index = self.read_byte() as usize;
+                        upvalues.push(if is_local {
+                            self.capture_upvalue(base + index)
+                        } else {
+                            closure.upvalues[index].clone()
+                        });
+                    }
+                    self.stack
+                        .push(Object::Function(gc::track_callable(Closure {
+                            function,
+                            upvalues,
+                        })));
+                }
+                OpCode::CloseUpvalue => {
+                    self.close_upvalues(self.stack.len() - 1);
+                    self.pop();
+                }
+                OpCode::Return => {
+                    let result = self.pop();
+                    if let Some(result) = self.return_from_frame(result) {
+                        return Ok(result);
+                    }
+                }
+
+                OpCode::Inherit => {
+                    let closure = self.closure();
+                    let name = constant_name(&closure, self.read_u16())?;
+                    if !matches!(self.peek(0), Object::Class(_)) {
+                        return Err(self.error(
+                            TokenType::IDENTIFIER,
+                            name,
+                            "Superclass must be a class.",
+                            ErrorCode::SuperclassNotClass,
+                        ));
+                    }
+                }
+                OpCode::Class => {
+                    let closure = self.closure();
+                    let name = constant_name(&closure, self.read_u16())?;
+                    let method_count = self.read_byte() as usize;
+                    let has_superclass = self.read_byte() == 1;
+
+                    let superclass = match has_superclass {
+                        true => match self.pop() {
+                            Object::Class(superclass) => Some(superclass),
+                            _ => return Err(LoxError::new_internal("superclass isn't a class")),
+                        },
+                        false => None,
+                    };
+                    let compiled = self.stack.split_off(self.stack.len() - method_count);
+                    let mut methods: HashMap<String, Rc<dyn LoxMethod>> = HashMap::new();
+                    for method in compiled {
+                        let method = match method {
+                            Object::Function(function) => as_closure(&function),
+                            _ => None,
+                        };
+                        let method = method
+                            .ok_or_else(|| LoxError::new_internal("method isn't a closure"))?;
+                        methods.insert(method.function.name.clone(), method);
+                    }
+                    let class = LoxClass::new(name.to_string(), superclass, methods);
+                    self.stack.push(Object::Class(gc::track_callable(class)));
+                }
+
+                OpCode::List => {
+                    let count = self.read_u16() as usize;
+                    let elements = self.stack.split_off(self.stack.len() - count);
+                    self.stack
+                        .push(Object::List(gc::track_list(Rc::new(RefCell::new(
+                            elements,
+                        )))));
+                }
+                OpCode::Map => {
+                    let count = self.read_u16() as usize;
+                    let entries = self.stack.split_off(self.stack.len() - 2 * count);
+                    let brace = self.token(TokenType::LBRACE, "{");
+                    let mut map = LoxMap::new();
+                    for entry in entries.chunks(2) {
+                        map.insert(map_key(&entry[0], &brace)?, entry[1].clone());
+                    }
+                    self.stack
+                        .push(Object::Map(gc::track_map(Rc::new(RefCell::new(map)))));
+                }
+                OpCode::IndexGet => {
+                    let index = self.pop();
+                    let object = self.pop();
+                    let value = self.index_get(object, index)?;
+                    self.stack.push(value);
+                }
+                OpCode::IndexSet => {
+                    let value = self.pop();
+                    let index = self.pop();
+                    let object = self.pop();
+                    self.index_set(object, index, value.clone())?;
+                    self.stack.push(value);
+                }
+            }
+        }
+    }
+
+    fn frame_mut(&mut self) -> &mut CallFrame {
+        self.frames.last_mut().unwrap()
+    }
+
+    // handing out a clone lets instructions borrow constants while still using the VM
+    fn closure(&self) -> Rc<Closure> {
+        self.frames.last().unwrap().closure.clone()
+    }
+
+    fn base(&self) -> usize {
+        self.frames.last().unwrap().base
+    }
+
+    fn read_byte(&mut self) -> u8 {
+        let frame = self.frame_mut();
+        let byte = frame.closure.function.chunk.code[frame.ip];
+        frame.ip += 1;
+        byte
+    }
+
+    fn read_u16(&mut self) -> u16 {
+        let frame = self.frame_mut();
+        let value = frame.closure.function.chunk.read_u16(frame.ip);
+        frame.ip += 2;
+        value
+    }
+
+    fn pop(&mut self) -> Object {
+        self.stack.pop().unwrap_or(Object::Nil)
+    }
+
+    fn peek(&self, distance: usize) -> &Object {
+        &self.stack[self.stack.len() - 1 - distance]
+    }
+
+    // where the instruction being executed came from
+    fn span(&self) -> Span {
+        let frame = self.frames.last().unwrap();
+        frame
+            .closure
+            .function
+            .chunk
+            .span_at(frame.ip.saturating_sub(1))
+    }
+
+    // stands in for the token the tree-walker would report the error at
+    fn token(&self, token_type: TokenType, lexeme: &str) -> Token {
+        let span = self.span();
+        Token::new(token_type, lexeme.to_string(), span.line, None).with_span(span)
+    }
+
+    fn error(
+        &self,
+        token_type: TokenType,
+        lexeme: &str,
+        message: &str,
+        code: ErrorCode,
+    ) -> LoxError {
+        LoxError::new_runtime(self.token(token_type, lexeme), message).with_code(code)
+    }
+
+    fn undefined_property(&self, name: &str) -> LoxError {
+        self.error(
+            TokenType::IDENTIFIER,
+            name,
+            &format!("Undefined property '{}'.", name),
+            ErrorCode::UndefinedProperty,
+        )
+    }
+
+    fn binary(&self, op: OpCode, left: Object, right: Object) -> Result<Object> {
+        // numbers are by far the common case, so skip building an operator token for them
+        if let (Object::Number(l), Object::Number(r)) = (&left, &right) {
+            let (l, r) = (*l, *r);
+            match op {
+                OpCode::Add => return Ok(Object::Number(l + r)),
+                OpCode::Subtract => return Ok(Object::Number(l - r)),
+                OpCode::Multiply => return Ok(Object::Number(l * r)),
+                OpCode::Divide if r != 0.0 => return Ok(Object::Number(l / r)),
+                OpCode::Greater => return Ok(Object::Boolean(l > r)),
+                OpCode::GreaterEqual => return Ok(Object::Boolean(l >= r)),
+                OpCode::Less => return Ok(Object::Boolean(l < r)),
+                OpCode::LessEqual => return Ok(Object::Boolean(l <= r)),
+                _ => {}
+            }
+        }
+
+        let (token_type, lexeme) = match op {
+            OpCode::Add => (TokenType::PLUS, "+"),
+            OpCode::Subtract => (TokenType::MINUS, "-"),
+            OpCode::Multiply => (TokenType::STAR, "*"),
+            OpCode::Divide => (TokenType::SLASH, "/"),
+            OpCode::Greater => (TokenType::GREATER, ">"),
+            OpCode::GreaterEqual => (TokenType::GREATER_EQUAL, ">="),
+            OpCode::Less => (TokenType::LESS, "<"),
+            _ => (TokenType::LESS_EQUAL, "<="),
+        };
+        Interpreter::binary_op(&self.token(token_type, lexeme), left, right)
+    }
+
+    fn index_get(&self, object: Object, index: Object) -> Result<Object> {
+        let bracket = self.token(TokenType::LBRACKET, "[");
+        match object {
+            Object::List(list) => {
+                let list = list.borrow();
+                let i = list_index(&index, list.len(), &bracket)?;
+                Ok(list[i].clone())
+            }
+            Object::Map(map) => {
+                let key = map_key(&index, &bracket)?;
+                match map.borrow().get(&key) {
+                    Some(value) => Ok(value.clone()),
+                    None => Err(LoxError::new_runtime(
+                        bracket,
+                        &format!("Undefined key '{}'.", index),
+                    )
+                    .with_code(ErrorCode::UndefinedKey)),
+                }
+            }
+            _ => Err(
+                LoxError::new_runtime(bracket, "Only lists and maps can be indexed.")
+                    .with_code(ErrorCode::NotIndexable),
+            ),
+        }
+    }
+
+    fn index_set(&self, object: Object, index: Object, value: Object) -> Result<()> {
+        let bracket = self.token(TokenType::LBRACKET, "[");
+        match object {
+            Object::List(list) => {
+                let mut list = list.borrow_mut();
+                let i = list_index(&index, list.len(), &bracket)?;
+                list[i] = value;
+                Ok(())
+            }
+            Object::Map(map) => {
+                let key = map_key(&index, &bracket)?;
+                map.borrow_mut().insert(key, value);
+                Ok(())
+            }
+            _ => Err(
+                LoxError::new_runtime(bracket, "Only lists and maps can be indexed.")
+                    .with_code(ErrorCode::NotIndexable),
+            ),
+        }
+    }
+
+    // checks the callee and argument count of a call whose callee and arguments are on
+    // top of the stack, with the same errors as the tree-walker
+    fn callee(&self, argc: usize) -> Result<Callee> {
+        let paren = || self.token(TokenType::RPAREN, ")");
+        let (callee, arity) = match self.peek(argc) {
+            Object::Function(function) => {
+                let callee = match as_closure(function) {
+                    Some(closure) => Callee::Closure(closure),
+                    None => match (function.clone() as Rc<dyn Any>).downcast::<BoundMethod>() {
+                        Ok(bound) => Callee::Method(bound.receiver.clone(), bound.method.clone()),
+                        Err(_) => Callee::Native(function.clone()),
+                    },
+                };
+                (callee, function.arity())
+            }
+            Object::Class(class) => (Callee::Class(class.clone()), class.arity()),
+            _ => {
+                return Err(
+                    LoxError::new_runtime(paren(), "Can only call functions and classes.")
+                        .with_code(ErrorCode::NotCallable),
+                );
+            }
+        };
+
+        if argc != arity {
+            return Err(LoxError::new_runtime(
+                paren(),
+                &format!("Expected {} arguments but got {}.", arity, argc),
+            )
+            .with_code(ErrorCode::ArityMismatch));
+        }
+        Ok(callee)
+    }
+
+    fn check_depth(&self) -> Result<()> {
+        let scripts = self
+            .frames
+            .first()
+            .is_some_and(|frame| frame.call_site.is_none()) as usize;
+        if self.frames.len() - scripts >= self.interpreter.max_call_depth() {
+            return Err(LoxError::new_runtime(
+                self.token(TokenType::RPAREN, ")"),
+                "Stack overflow.",
+            )
+            .with_code(ErrorCode::StackOverflow));
+        }
+        Ok(())
+    }
+
+    fn call(&mut self, argc: usize, call_site: Span) -> Result<()> {
+        let callee = self.callee(argc)?;
+        self.check_depth()?;
+
+        let base = self.stack.len() - argc - 1;
+        match callee {
+            Callee::Closure(closure) => self.push_frame(closure, base, call_site),
+            Callee::Method(receiver, method) => {
+                self.stack[base] = receiver;
+                self.push_frame(method, base, call_site);
+            }
+            Callee::Class(class) => self.instantiate(class, base, call_site)?,
+            Callee::Native(native) => {
+                let args = self.stack.split_off(base + 1);
+                let paren = Token::new(TokenType::RPAREN, ")".to_string(), call_site.line, None)
+                    .with_span(call_site);
+                // record the call on the interpreter, as the tree-walker does, so natives
+                // can see where they were called from
+                self.interpreter.frames.borrow_mut().push(ActiveCall {
+                    callable: native.clone(),
+                    call_site: paren.clone(),
+                });
+                let result = native.call(self.interpreter, &args);
+                self.interpreter.frames.borrow_mut().pop();
+
+                let value = result.map_err(|error| {
+                    let frame = StackFrame {
+                        function: native.to_string(),
+                        call_site: paren.clone(),
+                        line: call_site.line,
+                    };
+                    self.attach_trace(error, Some(frame))
+                })?;
+                self.stack[base] = value;
+            }
+        }
+        Ok(())
+    }
+
+    // `return f(...);` in a function: closures take over the returning frame, anything
+    // else is called normally and the Return that follows hands back its result
+    fn tail_call(&mut self, argc: usize) -> Result<()> {
+        let call_site = self.span();
+        let (receiver, closure) = match self.callee(argc)? {
+            Callee::Closure(closure) => (None, closure),
+            Callee::Method(receiver, method) => (Some(receiver), method),
+            _ => return self.call(argc, call_site),
+        };
+
+        let base = self.base();
+        self.close_upvalues(base);
+        let callee_slot = self.stack.len() - argc - 1;
+        self.stack.drain(base..callee_slot);
+        if let Some(receiver) = receiver {
+            self.stack[base] = receiver;
+        }
+        let frame = self.frame_mut();
+        frame.closure = closure;
+        frame.ip = 0;
+        frame.call_site = Some(call_site);
+        Ok(())
+    }
+
+    fn push_frame(&mut self, closure: Rc<Closure>, base: usize, call_site: Span) {
+        self.frames.push(CallFrame {
+            closure,
+            ip: 0,
+            base,
+            call_site: Some(call_site),
+        });
+    }
+
+    // the instance takes the class's slot and the initializer, if any, runs on it
+    fn instantiate(&mut self, class: Rc<LoxClass>, base: usize, call_site: Span) -> Result<()> {
+        let instance =
+            gc::track_instance(Rc::new(LoxInstance::new(Box::new(class.as_ref().clone()))));
+        self.stack[base] = Object::Instance(instance);
+        if let Some(initializer) = class.find_method("init") {
+            let initializer: Rc<dyn LoxCallable> = initializer;
+            let closure = as_closure(&initializer)
+                .ok_or_else(|| LoxError::new_internal("initializer isn't a closure"))?;
+            self.push_frame(closure, base, call_site);
+        }
+        Ok(())
+    }
+
+    // pops the current frame and hands its result to the caller; returns the result
+    // instead once the outermost frame is done
+    fn return_from_frame(&mut self, result: Object) -> Option<Object> {
+        let frame = self.frames.pop().unwrap();
+        self.close_upvalues(frame.base);
+        self.stack.truncate(frame.base);
+        if self.frames.is_empty() {
+            return Some(result);
+        }
+        self.stack.push(result);
+        None
+    }
+
+    fn capture_upvalue(&mut self, slot: usize) -> UpvalueRef {
+        let existing = self
+            .open_upvalues
+            .iter()
+            .find(|upvalue| matches!(*upvalue.borrow(), Upvalue::Open(open) if open == slot));
+        if let Some(upvalue) = existing {
+            return upvalue.clone();
+        }
+        let upvalue = gc::track_upvalue(Rc::new(RefCell::new(Upvalue::Open(slot))));
+        self.open_upvalues.push(upvalue.clone());
+        upvalue
+    }
+
+    // moves the values of captured slots at or above `from` into their upvalues
+    fn close_upvalues(&mut self, from: usize) {
+        let stack = &self.stack;
+        self.open_upvalues.retain(|upvalue| {
+            let slot = match *upvalue.borrow() {
+                Upvalue::Open(slot) => slot,
+                Upvalue::Closed(_) => return false,
+            };
+            if slot < from {
+                return true;
+            }
+            *upvalue.borrow_mut() = Upvalue::Closed(stack[slot].clone());
+            false
+        });
+    }
+
+    // like the tree-walker, the innermost point an error escapes from records every active
+    // call; `innermost` is a native that isn't on the frame stack
+    fn attach_trace(&self, mut error: LoxError, innermost: Option<StackFrame>) -> LoxError {
+        if error.kind != LoxErrorKind::Runtime || !error.trace.is_empty() {
+            return error;
+        }
+        error.trace.extend(innermost);
+        for frame in self.frames.iter().rev() {
+            if let Some(span) = frame.call_site {
+                let call_site =
+                    Token::new(TokenType::RPAREN, ")".to_string(), span.line, None).with_span(span);
+                error.trace.push(StackFrame {
+                    function: frame.closure.to_string(),
+                    call_site,
+                    line: span.line,
+                });
+            }
+        }
+        error
+    }
+}
+
+fn as_closure(function: &Rc<dyn LoxCallable>) -> Option<Rc<Closure>> {
+    (function.clone() as Rc<dyn Any>).downcast::<Closure>().ok()
+}
+
+fn constant_name(closure: &Closure, index: u16) -> Result<&str> {
+    match &closure.function.chunk.constants[index as usize] {
+        Constant::String(name) => Ok(name),
+        _ => Err(LoxError::new_internal("name constant isn't a string")),
+    }
+}
@@ -0,0 +1,16 @@
+// The bytecode backend: `compiler` turns the AST into chunks of bytecode and `machine`
+// runs them on a stack VM. Selected with `--backend=vm` or `Interpreter::set_backend`.
+// Compiled scripts can be saved as `.loxc` files (`loxc`, checked by `verify` on load) and
+// listed with `disasm`.
+pub mod chunk;
+pub mod compiler;
+pub mod disasm;
+pub mod loxc;
+pub mod machine;
+pub mod object;
+pub mod verify;
+
+pub use chunk::{Chunk, Constant, FunctionProto, OpCode};
+pub use compiler::{compile, compile_expression};
+pub use disasm::disassemble;
+pub use machine::Vm;
@@ -0,0 +1,101 @@
+use crate::compiler::error::Result;
+use crate::compiler::expr::{LoxCallable, LoxMethod, Object};
+use crate::compiler::gc::{self, Tracer};
+use crate::compiler::interpreter::Interpreter;
+use crate::compiler::lox_instance::LoxInstance;
+use crate::compiler::vm::chunk::FunctionProto;
+use crate::compiler::vm::machine::Vm;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+// A variable captured by a closure. It points into the VM stack while the variable's
+// scope is live, and takes the value over once the scope ends.
+#[derive(Debug)]
+pub enum Upvalue {
+    Open(usize),
+    Closed(Object),
+}
+
+pub type UpvalueRef = Rc<RefCell<Upvalue>>;
+
+// A compiled function together with the variables it captured
+#[derive(Clone)]
+pub struct Closure {
+    pub function: Rc<FunctionProto>,
+    pub upvalues: Vec<UpvalueRef>,
+}
+
+// upvalues can hold the closure itself, so a derived Debug would recurse forever
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", LoxCallable::to_string(self))
+    }
+}
+
+impl LoxCallable for Closure {
+    // the VM makes its own calls; this is for anything else holding a compiled function
+    fn call(&self, interpreter: &Interpreter, args: &[Object]) -> Result<Object> {
+        let callee = Object::Function(Rc::new(self.clone()));
+        Vm::new(interpreter).call_value(callee, args)
+    }
+
+    fn arity(&self) -> usize {
+        self.function.arity
+    }
+
+    fn to_string(&self) -> String {
+        format!("<fn {}>", self.function.name)
+    }
+
+    fn trace(&self, tracer: &mut Tracer) {
+        for upvalue in &self.upvalues {
+            tracer.edge(upvalue);
+        }
+    }
+}
+
+impl LoxMethod for Closure {
+    fn bind(&self, instance: Rc<LoxInstance>) -> Rc<dyn LoxCallable> {
+        gc::track_callable(BoundMethod {
+            receiver: Object::Instance(instance),
+            // tracked too, so the collector sees the upvalues through it
+            method: gc::track_callable(self.clone()),
+        })
+    }
+}
+
+// A method looked up on an instance; calling it puts the receiver in slot 0 as "this"
+pub struct BoundMethod {
+    pub receiver: Object,
+    pub method: Rc<Closure>,
+}
+
+impl fmt::Debug for BoundMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", LoxCallable::to_string(self))
+    }
+}
+
+impl LoxCallable for BoundMethod {
+    fn call(&self, interpreter: &Interpreter, args: &[Object]) -> Result<Object> {
+        let callee = Object::Function(Rc::new(BoundMethod {
+            receiver: self.receiver.clone(),
+            method: self.method.clone(),
+        }));
+        Vm::new(interpreter).call_value(callee, args)
+    }
+
+    fn arity(&self) -> usize {
+        self.method.arity()
+    }
+
+    fn to_string(&self) -> String {
+        LoxCallable::to_string(self.method.as_ref())
+    }
+
+    fn trace(&self, tracer: &mut Tracer) {
+        tracer.object(&self.receiver);
+        tracer.edge(&self.method);
+    }
+}
@@ -0,0 +1,248 @@
+use crate::compiler::error::{LoxError, Result};
+use crate::compiler::vm::chunk::{Constant, FunctionProto, OpCode};
+use crate::compiler::vm::loxc::invalid;
+
+// Checks that bytecode the compiler didn't just produce, i.e. loaded from a `.loxc` file,
+// can't send the VM out of bounds: every instruction decodes, operands refer to constants
+// of the right kind, jumps land on instructions, and the stack never underflows or gets
+// indexed past its top. Type errors in well-formed code are still left to the VM.
+pub fn verify(function: &FunctionProto) -> Result<()> {
+    let starts = instruction_starts(function)?;
+    check_lines(function)?;
+    check_stack(function, &starts)?;
+    for constant in &function.chunk.constants {
+        if let Constant::Function(nested) = constant {
+            verify(nested)?;
+        }
+    }
+    Ok(())
+}
+
+fn fail(function: &FunctionProto, offset: usize, message: &str) -> LoxError {
+    invalid(&format!(
+        "in '{}' at offset {}: {}",
+        function.name, offset, message
+    ))
+}
+
+fn constant_at(function: &FunctionProto, offset: usize) -> Option<&Constant> {
+    let index = function.chunk.read_u16(offset + 1);
+    function.chunk.constants.get(index as usize)
+}
+
+// decodes every instruction in order, checking its operands, and marks where each starts
+fn instruction_starts(function: &FunctionProto) -> Result<Vec<bool>> {
+    let code = &function.chunk.code;
+    let mut starts = vec![false; code.len()];
+    let mut offset = 0;
+    while offset < code.len() {
+        starts[offset] = true;
+        let op = OpCode::from_byte(code[offset]).ok_or_else(|| {
+            fail(
+                function,
+                offset,
+                &format!("unknown opcode {}", code[offset]),
+            )
+        })?;
+        let mut len = 1 + op.operand_len();
+        if offset + len > code.len() {
+            return Err(fail(function, offset, "instruction is cut off"));
+        }
+
+        match op {
+            OpCode::Constant
+                if !matches!(
+                    constant_at(function, offset),
+                    Some(Constant::Number(_) | Constant::String(_))
+                ) =>
+            {
+                return Err(fail(function, offset, "constant is not a number or string"));
+            }
+            OpCode::GetGlobal
+            | OpCode::DefineGlobal
+            | OpCode::SetGlobal
+            | OpCode::GetProperty
+            | OpCode::SetProperty
+            | OpCode::GetSuper
+            | OpCode::Inherit
+            | OpCode::Class
+                if !matches!(constant_at(function, offset), Some(Constant::String(_))) =>
+            {
+                return Err(fail(function, offset, "name is not a string constant"));
+            }
+            OpCode::GetUpvalue | OpCode::SetUpvalue
+                if code[offset + 1] as usize >= function.upvalue_count =>
+            {
+                return Err(fail(function, offset, "upvalue index out of range"));
+            }
+            OpCode::Closure => match constant_at(function, offset) {
+                Some(Constant::Function(closed)) => {
+                    len += 2 * closed.upvalue_count;
+                    if offset + len > code.len() {
+                        return Err(fail(function, offset, "instruction is cut off"));
+                    }
+                }
+                _ => return Err(fail(function, offset, "closure over a non-function")),
+            },
+            _ => {}
+        }
+        offset += len;
+    }
+    Ok(starts)
+}
+
+fn check_lines(function: &FunctionProto) -> Result<()> {
+    let lines = &function.chunk.lines;
+    for (i, entry) in lines.iter().enumerate() {
+        let ordered = i == 0 || lines[i - 1].offset < entry.offset;
+        if !ordered || entry.offset >= function.chunk.code.len() {
+            return Err(fail(function, entry.offset, "line table is out of order"));
+        }
+    }
+    Ok(())
+}
+
+// How many values an instruction pops and pushes
+fn stack_effect(function: &FunctionProto, offset: usize, op: OpCode) -> (usize, usize) {
+    let code = &function.chunk.code;
+    let byte = || code[offset + 1] as usize;
+    let count = || function.chunk.read_u16(offset + 1) as usize;
+    match op {
+        OpCode::Constant
+        | OpCode::Nil
+        | OpCode::True
+        | OpCode::False
+        | OpCode::GetLocal
+        | OpCode::GetGlobal
+        | OpCode::GetUpvalue
+        | OpCode::Closure => (0, 1),
+        OpCode::Pop | OpCode::DefineGlobal | OpCode::Print | OpCode::CloseUpvalue => (1, 0),
+        OpCode::Return => (1, 0),
+        // these read the top of the stack without removing it
+        OpCode::SetLocal
+        | OpCode::SetGlobal
+        | OpCode::SetUpvalue
+        | OpCode::JumpIfFalse
+        | OpCode::Inherit => (1, 1),
+        OpCode::Jump | OpCode::Loop => (0, 0),
+        OpCode::GetProperty | OpCode::Not | OpCode::Negate => (1, 1),
+        OpCode::SetProperty
+        | OpCode::GetSuper
+        | OpCode::Equal
+        | OpCode::NotEqual
+        | OpCode::Greater
+        | OpCode::GreaterEqual
+        | OpCode::Less
+        | OpCode::LessEqual
+        | OpCode::Add
+        | OpCode::Subtract
+        | OpCode::Multiply
+        | OpCode::Divide
+        | OpCode::IndexGet => (2, 1),
+        OpCode::IndexSet => (3, 1),
+        OpCode::Call | OpCode::TailCall => (byte() + 1, 1),
+        OpCode::Class => {
+            let methods = code[offset + 3] as usize;
+            let superclass = code[offset + 4] as usize;
+            (methods + superclass, 1)
+        }
+        OpCode::List => (count(), 1),
+        OpCode::Map => (2 * count(), 1),
+    }
+}
+
+// Follows every path through the function, tracking how deep the frame's stack is. Paths
+// meeting at an instruction must agree on the depth, as the compiler always arranges.
+fn check_stack(function: &FunctionProto, starts: &[bool]) -> Result<()> {
+    let code = &function.chunk.code;
+    if code.is_empty() {
+        return Err(fail(function, 0, "function has no code"));
+    }
+    let mut depths: Vec<Option<usize>> = vec![None; code.len()];
+    // slot 0 holds the callee or receiver, followed by the arguments
+    let mut pending = vec![(0, 1 + function.arity)];
+
+    while let Some((offset, depth)) = pending.pop() {
+        if offset >= code.len() {
+            return Err(fail(
+                function,
+                offset,
+                "execution runs past the end of the code",
+            ));
+        }
+        if !starts[offset] {
+            return Err(fail(function, offset, "jump lands inside an instruction"));
+        }
+        match depths[offset] {
+            Some(known) if known == depth => continue,
+            Some(_) => {
+                return Err(fail(
+                    function,
+                    offset,
+                    "paths reach it with different stack depths",
+                ));
+            }
+            None => depths[offset] = Some(depth),
+        }
+
+        let op = OpCode::from_byte(code[offset]).unwrap();
+        match op {
+            OpCode::GetLocal | OpCode::SetLocal if code[offset + 1] as usize >= depth => {
+                return Err(fail(function, offset, "local slot out of range"));
+            }
+            OpCode::Class if code[offset + 4] > 1 => {
+                return Err(fail(function, offset, "invalid superclass flag"));
+            }
+            _ => {}
+        }
+
+        let mut next = offset + 1 + op.operand_len();
+        if op == OpCode::Closure
+            && let Some(Constant::Function(closed)) = constant_at(function, offset)
+        {
+            for pair in 0..closed.upvalue_count {
+                let is_local = code[next + 2 * pair];
+                let index = code[next + 2 * pair + 1] as usize;
+                // a local function captures itself in the slot the closure is about to fill
+                let in_range = match is_local {
+                    0 => index < function.upvalue_count,
+                    1 => index <= depth,
+                    _ => false,
+                };
+                if !in_range {
+                    return Err(fail(function, offset, "captured variable out of range"));
+                }
+            }
+            next += 2 * closed.upvalue_count;
+        }
+
+        let (pops, pushes) = stack_effect(function, offset, op);
+        if pops > depth {
+            return Err(fail(function, offset, "stack underflow"));
+        }
+        let after = depth - pops + pushes;
+
+        match op {
+            OpCode::Return => {}
+            OpCode::Jump => {
+                pending.push((next + function.chunk.read_u16(offset + 1) as usize, after))
+            }
+            OpCode::JumpIfFalse => {
+                pending.push((next + function.chunk.read_u16(offset + 1) as usize, after));
+                pending.push((next, after));
+            }
+            OpCode::Loop => match next.checked_sub(function.chunk.read_u16(offset + 1) as usize) {
+                Some(target) => pending.push((target, after)),
+                None => {
+                    return Err(fail(
+                        function,
+                        offset,
+                        "loop jumps before the start of the code",
+                    ));
+                }
+            },
+            _ => pending.push((next, after)),
+        }
+    }
+    Ok(())
+}
@@ -1,90 +1,193 @@
 use std::{
     cell::RefCell,
     env, fs,
-    io::{self, BufRead, Write},
+    io::{self, BufRead, IsTerminal, Write},
+    path::Path,
     rc::Rc,
+    thread,
 };
 
 mod compiler;
 
-use compiler::{ErrorReporter, Interpreter, LoxError, LoxErrorKind, Parser, Resolver, Scanner};
+use compiler::diagnostic::to_json;
+use compiler::formatter::{self, FormatOptions};
+use compiler::gc;
+use compiler::interpreter::{Backend, DEFAULT_MAX_CALL_DEPTH, MAX_CALL_DEPTH_LIMIT};
+use compiler::optimizer::optimize;
+use compiler::repl::needs_more_input;
+use compiler::vm::{self, FunctionProto, disassemble, loxc};
+use compiler::{
+    AstPrinter, DiagnosticRenderer, ErrorCode, ErrorFormat, ErrorReporter, Interpreter, LoxError,
+    LoxErrorKind, Parser, Resolver, Scanner, Stmt,
+};
 
 pub struct Lox {
     had_error: bool,
     had_runtime_error: bool,
+    // kept for the whole session so REPL lines see earlier definitions
+    interpreter: Rc<RefCell<Interpreter>>,
+    resolver: Resolver,
+    // every source run so far, numbered by the ids their spans carry, and the name they
+    // go by, so diagnostics can quote them. The REPL adds one per input: a function
+    // defined in an earlier input can still fail in a later one.
+    sources: Vec<String>,
+    source_name: String,
+    color: bool,
+    error_format: ErrorFormat,
+    // print collector statistics to stderr when done
+    gc_stats: bool,
+    // fold constants and drop dead branches before running
+    optimize: bool,
+    // print the program as S-expressions instead of running it
+    dump_ast: bool,
 }
 
 impl ErrorReporter for Lox {
     fn error(&mut self, line: usize, message: &str) {
-        self.report(line, "", message);
+        self.report(&LoxError::new_from_line(line, message));
+        self.had_error = true;
+    }
+
+    fn error_with_code(&mut self, line: usize, code: ErrorCode, message: &str) {
+        self.report(&LoxError::new_from_line(line, message).with_code(code));
+        self.had_error = true;
     }
 
     fn runtime_error(&mut self, error: &LoxError) {
-        eprintln!("{}", error);
+        self.report(error);
         self.had_runtime_error = true;
     }
 }
 
 impl Lox {
-    fn new() -> Self {
+    fn new(
+        color: bool,
+        error_format: ErrorFormat,
+        max_call_depth: usize,
+        backend: Option<Backend>,
+    ) -> Self {
+        let interpreter = Rc::new(RefCell::new(Interpreter::new()));
+        interpreter.borrow().set_max_call_depth(max_call_depth);
+        // main runs the interpreter on a thread of exactly this size
+        interpreter
+            .borrow()
+            .set_stack_size(Interpreter::stack_size_for(max_call_depth));
+        if let Some(backend) = backend {
+            interpreter.borrow().set_backend(backend);
+        }
+        let resolver = Resolver::new(interpreter.clone());
         Self {
             had_error: false,
             had_runtime_error: false,
+            interpreter,
+            resolver,
+            sources: Vec::new(),
+            source_name: String::from("<stdin>"),
+            color,
+            error_format,
+            gc_stats: false,
+            optimize: false,
+            dump_ast: false,
         }
     }
 
     fn run(&mut self, source: String) {
+        self.run_source(source, false);
+    }
+
+    // Runs one chunk of source against the session interpreter. When `echo` is set and the
+    // chunk is a single expression statement, its value is printed (REPL behaviour).
+    fn run_source(&mut self, source: String, echo: bool) {
+        let Some(ast) = self.front_end(source) else {
+            return;
+        };
+        if self.dump_ast {
+            print!("{}", AstPrinter.print_program(&ast));
+            return;
+        }
+
+        let result = match ast.as_slice() {
+            // echoed the same way `print` shows values
+            [Stmt::Expression(expression)] if echo => self
+                .interpreter
+                .borrow()
+                .evaluate(&expression.expression)
+                .map(|value| println!("{:?}", value)),
+            _ => self.interpreter.borrow_mut().interpret(ast),
+        };
+
+        match result {
+            // the VM compiles the program before running it, and can hit its limits there
+            Err(e) if e.kind == LoxErrorKind::Parse => {
+                self.report(&e);
+                self.had_error = true;
+            }
+            Err(e) => self.runtime_error(&e),
+            Ok(()) => {}
+        }
+    }
+
+    // Scans, parses and resolves the source, reporting any errors along the way. Returns
+    // the program, optimized if asked to, only when it's fit to run (warnings alone don't
+    // stop it).
+    fn front_end(&mut self, source: String) -> Option<Vec<Stmt>> {
+        let source_id = self.sources.len();
+        self.sources.push(source.clone());
+
         // first phase: tokenize the input
-        let mut scanner = Scanner::new(source, self);
-        scanner.scan_tokens();
+        let tokens = {
+            let mut scanner = Scanner::new(source, self).with_source_id(source_id);
+            scanner.scan_tokens();
+            scanner.tokens
+        };
         // dummy tokens for testing (need to use type annotations)
         // let tokens = vec!["(", ")", "{", "}", ",", ".", "-", "+", ";", "*", "!"];
 
         // Debug: print tokens (disabled for now)
-        // for token in &scanner.tokens {
+        // for token in &tokens {
         //     println!("{:?}", token);
         // }
 
         // second phase: parse the tokens
-        let mut parser = Parser::new(&scanner.tokens);
-        match parser.parse() {
-            Ok(ast) => {
-                let interpreter = Rc::new(RefCell::new(Interpreter::new()));
-                let mut resolver = Resolver::new(interpreter.clone());
-
-                // check for resolver errors first
-                resolver.resolve_statements(&ast);
-
-                // Process resolver errors and warnings
-                let mut has_real_errors = false;
-                for error in resolver.errors.borrow().iter() {
-                    if error.kind != LoxErrorKind::Warning {
-                        eprintln!("Resolver error: {}", error);
-                        self.had_error = true;
-                        has_real_errors = true;
-                    } else {
-                        eprintln!("Warning: {}", error);
-                    }
-                }
-
-                // Only skip interpretation if there are actual errors (not just warnings)
-                if !has_real_errors {
-                    match interpreter.borrow_mut().interpret(ast) {
-                        Ok(_) => (),
-                        Err(e) => {
-                            eprintln!("Runtime error: {}", e);
-                            self.had_runtime_error = true;
-                        }
-                    }
-                }
+        let mut parser = Parser::new(&tokens);
+        let (ast, parse_errors) = parser.parse_all();
+        if !parse_errors.is_empty() {
+            // Display every parsing error recovered from, not just the first
+            for error in &parse_errors {
+                self.report(error);
             }
-            Err(error) => {
-                // Display the parsing error
-                eprintln!("Parse error: {}", error);
+            self.had_error = true;
+            return None;
+        }
+
+        // errors from earlier REPL lines have already been reported
+        self.resolver.errors.borrow_mut().clear();
+
+        // check for resolver errors first
+        self.resolver.resolve_statements(&ast);
+
+        // Process resolver errors and warnings
+        let mut has_real_errors = false;
+        let mut resolver_errors = self.resolver.errors.borrow().clone();
+        // unused-variable warnings come out of the scope maps in no set order; report
+        // everything by position so the output is the same from run to run
+        resolver_errors.sort_by_key(|error| error.span().map(|span| (span.line, span.column)));
+        for error in &resolver_errors {
+            if error.kind != LoxErrorKind::Warning {
                 self.had_error = true;
-                return;
+                has_real_errors = true;
             }
+            self.report(error);
         }
+
+        // Only skip interpretation if there are actual errors (not just warnings)
+        if has_real_errors {
+            return None;
+        }
+        if self.optimize {
+            return Some(optimize(ast));
+        }
+        Some(ast)
     }
 
     fn run_prompt(&mut self) {
@@ -95,28 +198,165 @@ impl Lox {
             print!("> ");
             stdout.flush().unwrap();
 
-            let mut line = String::new();
-            let bytes = stdin.lock().read_line(&mut line).unwrap();
+            let mut source = String::new();
+            let bytes = stdin.lock().read_line(&mut source).unwrap();
 
             if bytes == 0 {
                 break;
             }
 
-            self.run(line);
+            // keep reading until every bracket is closed, e.g. for multi-line functions
+            while needs_more_input(&source) {
+                print!("... ");
+                stdout.flush().unwrap();
+
+                let bytes = stdin.lock().read_line(&mut source).unwrap();
+                if bytes == 0 {
+                    break;
+                }
+            }
+
+            if source.trim().is_empty() {
+                continue;
+            }
+
+            self.run_source(source, true);
             // prevent error from stopping the REPL
             self.had_error = false;
             self.had_runtime_error = false;
         }
+        self.finish();
     }
 
     fn run_file(&mut self, path: &str) {
+        if is_compiled(path) {
+            if let Some(script) = self.load_compiled(path) {
+                let result = self.interpreter.borrow().run_compiled(script);
+                if let Err(e) = result {
+                    self.runtime_error(&e);
+                }
+            }
+        } else {
+            let content = self.read_source(path);
+            self.run(content);
+        }
+        self.finish();
+    }
+
+    // `rlox disasm`: lists the bytecode of a script, compiling it first unless it's a .loxc
+    fn disasm_file(&mut self, path: &str) {
+        let script = match is_compiled(path) {
+            true => self.load_compiled(path),
+            false => self.compile_source(path),
+        };
+        if let Some(script) = script {
+            print!("{}", disassemble(&script));
+        }
+        self.finish();
+    }
+
+    // `rlox compile`: saves the compiled script, next to the source unless told otherwise
+    fn compile_file(&mut self, path: &str, output: Option<&str>) {
+        let Some(script) = self.compile_source(path) else {
+            self.exit_on_error();
+            return;
+        };
+        let output = match output {
+            Some(output) => output.to_string(),
+            None => Path::new(path)
+                .with_extension("loxc")
+                .to_string_lossy()
+                .into_owned(),
+        };
+        fs::write(&output, loxc::write(&script)).unwrap_or_else(|err| {
+            eprintln!("Could not write file '{}': {}", output, err);
+            std::process::exit(74);
+        });
+    }
+
+    // `rlox fmt`: rewrites each file in canonical layout, or with `check` only lists the
+    // files that would change and exits with 1 if there are any
+    fn fmt_files(&mut self, paths: &[String], options: &FormatOptions, check: bool) {
+        let mut unformatted = false;
+        for path in paths {
+            let content = self.read_source(path);
+            let formatted = match formatter::format(&content, options) {
+                Ok(formatted) => formatted,
+                Err(errors) => {
+                    self.sources = vec![content];
+                    for error in &errors {
+                        self.report(error);
+                    }
+                    self.had_error = true;
+                    continue;
+                }
+            };
+            if formatted == content {
+                continue;
+            }
+            if check {
+                println!("{}", path);
+                unformatted = true;
+            } else {
+                fs::write(path, formatted).unwrap_or_else(|err| {
+                    eprintln!("Could not write file '{}': {}", path, err);
+                    std::process::exit(74);
+                });
+            }
+        }
+        self.exit_on_error();
+        if unformatted {
+            std::process::exit(1);
+        }
+    }
+
+    fn compile_source(&mut self, path: &str) -> Option<Rc<FunctionProto>> {
+        let content = self.read_source(path);
+        let ast = self.front_end(content)?;
+        match vm::compile(&ast) {
+            Ok(script) => Some(script),
+            Err(e) => {
+                self.report(&e);
+                self.had_error = true;
+                None
+            }
+        }
+    }
+
+    fn load_compiled(&mut self, path: &str) -> Option<Rc<FunctionProto>> {
+        let bytes = fs::read(path).unwrap_or_else(|err| {
+            eprintln!("Could not read file '{}': {}", path, err);
+            std::process::exit(65);
+        });
+        // there's no source to quote, so diagnostics only give locations
+        self.source_name = path.to_string();
+        match loxc::read(&bytes) {
+            Ok(script) => Some(script),
+            Err(e) => {
+                self.report(&e);
+                self.had_error = true;
+                None
+            }
+        }
+    }
+
+    fn read_source(&mut self, path: &str) -> String {
         let content = fs::read_to_string(path).unwrap_or_else(|err| {
             eprintln!("Could not read file '{}': {}", path, err);
             std::process::exit(65);
         });
+        self.source_name = path.to_string();
+        content
+    }
 
-        self.run(content);
+    fn finish(&self) {
+        if self.gc_stats {
+            eprintln!("{}", gc::stats());
+        }
+        self.exit_on_error();
+    }
 
+    fn exit_on_error(&self) {
         // Exit with different error codes for different error types
         if self.had_error {
             std::process::exit(65);
@@ -126,22 +366,169 @@ impl Lox {
         }
     }
 
-    fn report(&mut self, line: usize, loc: &str, message: &str) {
-        eprintln!("[line {}] Error {}: {}", line, loc, message);
-        self.had_error = true;
+    fn report(&self, error: &LoxError) {
+        match self.error_format {
+            ErrorFormat::Human => {
+                let renderer =
+                    DiagnosticRenderer::with_sources(&self.sources, &self.source_name, self.color);
+                eprintln!("{}", renderer.render(error));
+            }
+            // one object per line so tools can stream them
+            ErrorFormat::Json => eprintln!("{}", to_json(error, &self.source_name)),
+        }
     }
 }
 
-fn main() {
-    let mut lox = Lox::new();
-    let args: Vec<String> = env::args().collect();
-
-    match args.len() {
-        1 => lox.run_prompt(),
-        2 => lox.run_file(&args[1]),
-        _ => {
-            eprintln!("Usage: rlox [script]");
+// compiled scripts are recognised by their extension
+fn is_compiled(path: &str) -> bool {
+    Path::new(path).extension().is_some_and(|ext| ext == "loxc")
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: rlox [--color=auto|always|never] [--error-format=human|json] [--max-call-depth=N]\n             [--backend=tree|vm] [--gc-stress] [--gc-stats]\n             [--optimize] [--dump-ast] [--print-optimized] [script]"
+    );
+    eprintln!("       rlox [options] compile script.lox [output.loxc]");
+    eprintln!("       rlox [options] disasm script.lox|script.loxc");
+    eprintln!("       rlox [options] fmt [--check] [--indent=N] [--line-width=N] script.lox...");
+    eprintln!("       rlox --explain CODE");
+    std::process::exit(64);
+}
+
+// `--explain E0301` prints the catalog entry for a diagnostic code
+fn explain(code: &str) -> ! {
+    match code.parse::<ErrorCode>() {
+        Ok(code) => {
+            println!("{}: {}\n\n{}", code, code.title(), code.explanation());
+            std::process::exit(0);
+        }
+        Err(_) => {
+            eprintln!("'{}' is not a known error code.", code);
             std::process::exit(64);
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Run,
+    Compile,
+    Disasm,
+    Fmt,
+}
+
+fn main() {
+    let mut color = io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none();
+    let mut error_format = ErrorFormat::Human;
+    let mut max_call_depth = DEFAULT_MAX_CALL_DEPTH;
+    // unset means LOX_BACKEND, or the tree-walker
+    let mut backend = None;
+    let mut gc_stress = false;
+    let mut gc_stats = false;
+    let mut optimize = false;
+    let mut dump_ast = false;
+    let mut format_options = FormatOptions::default();
+    let mut check = false;
+    let mut scripts = Vec::new();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--explain" => explain(&args.next().unwrap_or_else(|| usage())),
+            "--color=auto" => {}
+            "--color=always" => color = true,
+            "--color=never" => color = false,
+            "--error-format=human" => error_format = ErrorFormat::Human,
+            "--error-format=json" => error_format = ErrorFormat::Json,
+            "--gc-stress" => gc_stress = true,
+            "--gc-stats" => gc_stats = true,
+            "--optimize" => optimize = true,
+            "--dump-ast" => dump_ast = true,
+            // shorthand for --optimize --dump-ast
+            "--print-optimized" => {
+                optimize = true;
+                dump_ast = true;
+            }
+            "--check" => check = true,
+            flag if flag.starts_with("--indent=") => {
+                format_options.indent = flag["--indent=".len()..]
+                    .parse()
+                    .unwrap_or_else(|_| usage());
+            }
+            flag if flag.starts_with("--line-width=") => {
+                format_options.line_width = flag["--line-width=".len()..]
+                    .parse()
+                    .unwrap_or_else(|_| usage());
+            }
+            flag if flag.starts_with("--max-call-depth=") => {
+                max_call_depth = flag["--max-call-depth=".len()..]
+                    .parse()
+                    .unwrap_or_else(|_| usage());
+                if max_call_depth > MAX_CALL_DEPTH_LIMIT {
+                    eprintln!(
+                        "--max-call-depth can be at most {}, got {}.",
+                        MAX_CALL_DEPTH_LIMIT, max_call_depth
+                    );
+                    std::process::exit(64);
+                }
+            }
+            flag if flag.starts_with("--backend=") => {
+                backend = Some(
+                    Backend::from_name(&flag["--backend=".len()..]).unwrap_or_else(|| usage()),
+                );
+            }
+            flag if flag.starts_with("--") => usage(),
+            _ => scripts.push(arg),
+        }
+    }
+
+    let command = match scripts.first().map(String::as_str) {
+        Some("compile") if matches!(scripts.len(), 2 | 3) => Command::Compile,
+        Some("disasm") if scripts.len() == 2 => Command::Disasm,
+        Some("fmt") if scripts.len() >= 2 => Command::Fmt,
+        _ if scripts.len() <= 1 => Command::Run,
+        _ => usage(),
+    };
+    if command != Command::Run {
+        scripts.remove(0);
+    }
+
+    // LOX_GC_STRESS=1 does the same as --gc-stress
+    let gc_stress = gc_stress || env::var_os("LOX_GC_STRESS").is_some_and(|value| value != "0");
+    let backend = backend.or_else(|| {
+        env::var("LOX_BACKEND")
+            .ok()
+            .and_then(|name| Backend::from_name(&name))
+    });
+
+    // Lox calls recurse on the native stack, so run on a thread big enough to reach the
+    // call depth limit and report "Stack overflow." rather than crash
+    let runner = thread::Builder::new()
+        .stack_size(Interpreter::stack_size_for(max_call_depth))
+        .spawn(move || {
+            // the heap is per thread, so it's set up here rather than in main
+            if gc_stress {
+                gc::set_stress(true);
+            }
+            let mut lox = Lox::new(color, error_format, max_call_depth, backend);
+            lox.gc_stats = gc_stats;
+            lox.optimize = optimize;
+            lox.dump_ast = dump_ast;
+            match (command, scripts.first()) {
+                (Command::Compile, Some(script)) => {
+                    lox.compile_file(script, scripts.get(1).map(String::as_str))
+                }
+                (Command::Disasm, Some(script)) => lox.disasm_file(script),
+                (Command::Fmt, _) => lox.fmt_files(&scripts, &format_options, check),
+                (_, None) => lox.run_prompt(),
+                (_, Some(script)) => lox.run_file(script),
+            }
+        })
+        .unwrap_or_else(|err| {
+            eprintln!("Could not start the interpreter thread: {}", err);
+            std::process::exit(70);
+        });
+    if runner.join().is_err() {
+        std::process::exit(70);
+    }
+}
@@ -0,0 +1,212 @@
+use super::{execute, global};
+use lox::compiler::expr::Object;
+
+#[test]
+fn test_inherited_method() {
+    let source = r#"
+        class Doughnut {
+            cook() {
+                return "Fry until golden brown.";
+            }
+        }
+        class BostonCream < Doughnut {}
+        var result = BostonCream().cook();
+    "#;
+
+    let (result, interpreter) = execute(source);
+    assert!(
+        result.is_ok(),
+        "Inherited method failed: {:?}",
+        result.err()
+    );
+    assert_eq!(
+        global(&interpreter, "result"),
+        Object::String("Fry until golden brown.".into())
+    );
+}
+
+#[test]
+fn test_override_and_super_call() {
+    let source = r#"
+        class A {
+            name() {
+                return "A";
+            }
+        }
+        class B < A {
+            name() {
+                return "B" + super.name();
+            }
+        }
+        var result = B().name();
+    "#;
+
+    let (result, interpreter) = execute(source);
+    assert!(result.is_ok(), "Super call failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "result"), Object::String("BA".into()));
+}
+
+#[test]
+fn test_super_resolves_statically() {
+    let source = r#"
+        class A {
+            method() {
+                return "A method";
+            }
+        }
+        class B < A {
+            method() {
+                return "B method";
+            }
+            test() {
+                return super.method();
+            }
+        }
+        class C < B {}
+        var result = C().test();
+    "#;
+
+    let (result, interpreter) = execute(source);
+    assert!(result.is_ok(), "Super chain failed: {:?}", result.err());
+    assert_eq!(
+        global(&interpreter, "result"),
+        Object::String("A method".into())
+    );
+}
+
+#[test]
+fn test_inherited_init_and_super_init() {
+    let source = r#"
+        class Base {
+            init(x) {
+                this.x = x;
+            }
+        }
+        class Derived < Base {
+            init(x, y) {
+                super.init(x);
+                this.y = y;
+            }
+        }
+        class Plain < Base {}
+        var d = Derived(1, 2);
+        var sum = d.x + d.y;
+        var p = Plain(7);
+        var px = p.x;
+    "#;
+
+    let (result, interpreter) = execute(source);
+    assert!(result.is_ok(), "Inherited init failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "sum"), Object::Number(3.0));
+    assert_eq!(global(&interpreter, "px"), Object::Number(7.0));
+}
+
+#[test]
+fn test_super_method_uses_this() {
+    let source = r#"
+        class Animal {
+            describe() {
+                return this.name + " makes a sound";
+            }
+        }
+        class Dog < Animal {
+            describe() {
+                return super.describe() + ": woof";
+            }
+        }
+        var dog = Dog();
+        dog.name = "Rex";
+        var result = dog.describe();
+    "#;
+
+    let (result, interpreter) = execute(source);
+    assert!(result.is_ok(), "Super with this failed: {:?}", result.err());
+    assert_eq!(
+        global(&interpreter, "result"),
+        Object::String("Rex makes a sound: woof".into())
+    );
+}
+
+#[test]
+fn test_inherit_from_self_is_error() {
+    let (result, _) = execute("class Oops < Oops {}");
+    let error = result.expect_err("Expected resolver error for self inheritance");
+    assert!(
+        error.message.contains("can't inherit from itself"),
+        "Unexpected error message: {}",
+        error
+    );
+}
+
+#[test]
+fn test_inherit_from_non_class_is_error() {
+    let (result, _) = execute("var NotAClass = \"nope\"; class Sub < NotAClass {}");
+    let error = result.expect_err("Expected runtime error for non-class superclass");
+    assert!(
+        error.message.contains("Superclass must be a class"),
+        "Unexpected error message: {}",
+        error
+    );
+}
+
+#[test]
+fn test_super_without_superclass_is_error() {
+    let source = r#"
+        class Lonely {
+            method() {
+                return super.method();
+            }
+        }
+    "#;
+
+    let (result, _) = execute(source);
+    let error = result.expect_err("Expected resolver error for super without superclass");
+    assert!(
+        error.message.contains("no superclass"),
+        "Unexpected error message: {}",
+        error
+    );
+}
+
+#[test]
+fn test_super_outside_class_is_error() {
+    let (result, _) = execute("super.method();");
+    let error = result.expect_err("Expected resolver error for super outside class");
+    assert!(
+        error.message.contains("outside of a class"),
+        "Unexpected error message: {}",
+        error
+    );
+}
+
+#[test]
+fn test_undefined_super_method_is_error() {
+    let source = r#"
+        class A {}
+        class B < A {
+            method() {
+                return super.missing();
+            }
+        }
+        B().method();
+    "#;
+
+    let (result, _) = execute(source);
+    let error = result.expect_err("Expected runtime error for missing super method");
+    assert!(
+        error.message.contains("Undefined property 'missing'"),
+        "Unexpected error message: {}",
+        error
+    );
+}
+
+#[test]
+fn test_super_requires_method_name() {
+    let (result, _) = execute("class A {} class B < A { m() { return super; } }");
+    let error = result.expect_err("Expected parse error for bare super");
+    assert!(
+        error.message.contains("Expect '.' after 'super'"),
+        "Unexpected error message: {}",
+        error
+    );
+}
@@ -0,0 +1,155 @@
+use super::{execute, global};
+use lox::compiler::expr::Object;
+
+#[test]
+fn test_set_and_get_field() {
+    let source = r#"
+        class Point {}
+        var p = Point();
+        p.x = 1;
+        p.y = 2;
+        var sum = p.x + p.y;
+    "#;
+
+    let (result, interpreter) = execute(source);
+    assert!(result.is_ok(), "Field access failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "sum"), Object::Number(3.0));
+}
+
+#[test]
+fn test_set_expression_returns_value() {
+    let source = r#"
+        class Box {}
+        var b = Box();
+        var v = b.value = "stored";
+    "#;
+
+    let (result, interpreter) = execute(source);
+    assert!(result.is_ok(), "Set expression failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "v"), Object::String("stored".into()));
+}
+
+#[test]
+fn test_fields_are_per_instance() {
+    let source = r#"
+        class Counter {}
+        var a = Counter();
+        var b = Counter();
+        a.count = 1;
+        b.count = 2;
+        a.count = a.count + 10;
+        var first = a.count;
+        var second = b.count;
+    "#;
+
+    let (result, interpreter) = execute(source);
+    assert!(
+        result.is_ok(),
+        "Per-instance fields failed: {:?}",
+        result.err()
+    );
+    assert_eq!(global(&interpreter, "first"), Object::Number(11.0));
+    assert_eq!(global(&interpreter, "second"), Object::Number(2.0));
+}
+
+#[test]
+fn test_instances_share_fields_by_reference() {
+    let source = r#"
+        class Node {}
+        var a = Node();
+        var alias = a;
+        alias.name = "shared";
+        var name = a.name;
+    "#;
+
+    let (result, interpreter) = execute(source);
+    assert!(
+        result.is_ok(),
+        "Aliased field write failed: {:?}",
+        result.err()
+    );
+    assert_eq!(
+        global(&interpreter, "name"),
+        Object::String("shared".into())
+    );
+}
+
+#[test]
+fn test_chained_property_access() {
+    let source = r#"
+        class Node {}
+        var outer = Node();
+        outer.inner = Node();
+        outer.inner.value = 42;
+        var v = outer.inner.value;
+    "#;
+
+    let (result, interpreter) = execute(source);
+    assert!(result.is_ok(), "Chained access failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "v"), Object::Number(42.0));
+}
+
+#[test]
+fn test_fields_in_local_scope() {
+    let source = r#"
+        class Point {}
+        var result;
+        {
+            var p = Point();
+            p.x = 5;
+            result = p.x;
+        }
+    "#;
+
+    let (result, interpreter) = execute(source);
+    assert!(
+        result.is_ok(),
+        "Local field access failed: {:?}",
+        result.err()
+    );
+    assert_eq!(global(&interpreter, "result"), Object::Number(5.0));
+}
+
+#[test]
+fn test_undefined_property_error() {
+    let (result, _) = execute("class Point {} var p = Point(); print p.missing;");
+    let error = result.expect_err("Expected runtime error for undefined property");
+    assert!(
+        error.message.contains("Undefined property 'missing'"),
+        "Unexpected error message: {}",
+        error
+    );
+}
+
+#[test]
+fn test_get_on_non_instance_error() {
+    let (result, _) = execute("var s = \"text\"; print s.length;");
+    let error = result.expect_err("Expected runtime error for property on string");
+    assert!(
+        error.message.contains("Only instances have properties"),
+        "Unexpected error message: {}",
+        error
+    );
+}
+
+#[test]
+fn test_set_on_non_instance_error() {
+    let (result, _) = execute("var n = 3; n.field = 1;");
+    let error = result.expect_err("Expected runtime error for field on number");
+    assert!(
+        error.message.contains("Only instances have fields"),
+        "Unexpected error message: {}",
+        error
+    );
+}
+
+#[test]
+fn test_invalid_property_name_is_parse_error() {
+    let (result, _) = execute("class A {} var a = A(); a.1 = 2;");
+    let error = result.expect_err("Expected parse error for numeric property name");
+    assert!(
+        error.message.contains("Expect property name after '.'"),
+        "Unexpected error message: {}",
+        error
+    );
+}
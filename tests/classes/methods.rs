@@ -0,0 +1,256 @@
+use super::{execute, global};
+use lox::compiler::expr::Object;
+
+#[test]
+fn test_method_call() {
+    let source = r#"
+        class Greeter {
+            greet(name) {
+                return "Hello, " + name;
+            }
+        }
+        var message = Greeter().greet("Lox");
+    "#;
+
+    let (result, interpreter) = execute(source);
+    assert!(result.is_ok(), "Method call failed: {:?}", result.err());
+    assert_eq!(
+        global(&interpreter, "message"),
+        Object::String("Hello, Lox".into())
+    );
+}
+
+#[test]
+fn test_this_reads_fields() {
+    let source = r#"
+        class Point {
+            sum() {
+                return this.x + this.y;
+            }
+        }
+        var p = Point();
+        p.x = 3;
+        p.y = 4;
+        var total = p.sum();
+    "#;
+
+    let (result, interpreter) = execute(source);
+    assert!(
+        result.is_ok(),
+        "Method using this failed: {:?}",
+        result.err()
+    );
+    assert_eq!(global(&interpreter, "total"), Object::Number(7.0));
+}
+
+#[test]
+fn test_bound_method_keeps_instance() {
+    let source = r#"
+        class Person {
+            name() {
+                return this.first;
+            }
+        }
+        var jane = Person();
+        jane.first = "Jane";
+        var bill = Person();
+        bill.first = "Bill";
+        bill.name = jane.name;
+        var who = bill.name();
+    "#;
+
+    let (result, interpreter) = execute(source);
+    assert!(result.is_ok(), "Bound method failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "who"), Object::String("Jane".into()));
+}
+
+#[test]
+fn test_this_captured_in_closure() {
+    let source = r#"
+        class Thing {
+            getCallback() {
+                fun localFunction() {
+                    return this.value;
+                }
+                return localFunction;
+            }
+        }
+        var thing = Thing();
+        thing.value = "captured";
+        var callback = thing.getCallback();
+        var result = callback();
+    "#;
+
+    let (result, interpreter) = execute(source);
+    assert!(
+        result.is_ok(),
+        "Closure over this failed: {:?}",
+        result.err()
+    );
+    assert_eq!(
+        global(&interpreter, "result"),
+        Object::String("captured".into())
+    );
+}
+
+#[test]
+fn test_init_sets_fields() {
+    let source = r#"
+        class Point {
+            init(x, y) {
+                this.x = x;
+                this.y = y;
+            }
+        }
+        var p = Point(1, 2);
+        var x = p.x;
+        var y = p.y;
+    "#;
+
+    let (result, interpreter) = execute(source);
+    assert!(result.is_ok(), "Initializer failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "x"), Object::Number(1.0));
+    assert_eq!(global(&interpreter, "y"), Object::Number(2.0));
+}
+
+#[test]
+fn test_init_arity_checked() {
+    let source = r#"
+        class Point {
+            init(x, y) {
+                this.x = x;
+                this.y = y;
+            }
+        }
+        Point(1);
+    "#;
+
+    let (result, _) = execute(source);
+    let error = result.expect_err("Expected arity error for initializer");
+    assert!(
+        error.message.contains("Expected 2 arguments but got 1"),
+        "Unexpected error message: {}",
+        error
+    );
+}
+
+#[test]
+fn test_class_without_init_takes_no_args() {
+    let (result, _) = execute("class Empty {} Empty(1);");
+    assert!(
+        result.is_err(),
+        "Expected arity error for class without init"
+    );
+}
+
+#[test]
+fn test_init_returns_instance_when_called_directly() {
+    let source = r#"
+        class Counter {
+            init() {
+                this.count = 0;
+            }
+        }
+        var c = Counter();
+        c.count = 5;
+        var same = c.init() == c;
+        var reset = c.count;
+    "#;
+
+    let (result, interpreter) = execute(source);
+    assert!(
+        result.is_ok(),
+        "Direct init call failed: {:?}",
+        result.err()
+    );
+    assert_eq!(global(&interpreter, "same"), Object::Boolean(true));
+    assert_eq!(global(&interpreter, "reset"), Object::Number(0.0));
+}
+
+#[test]
+fn test_early_return_in_init_returns_instance() {
+    let source = r#"
+        class Maybe {
+            init(flag) {
+                this.flag = flag;
+                if (flag) return;
+                this.flag = "unreachable";
+            }
+        }
+        var m = Maybe(true);
+        var flag = m.flag;
+    "#;
+
+    let (result, interpreter) = execute(source);
+    assert!(
+        result.is_ok(),
+        "Early return in init failed: {:?}",
+        result.err()
+    );
+    assert_eq!(global(&interpreter, "flag"), Object::Boolean(true));
+}
+
+#[test]
+fn test_return_value_from_init_is_error() {
+    let source = r#"
+        class Bad {
+            init() {
+                return 1;
+            }
+        }
+    "#;
+
+    let (result, _) = execute(source);
+    let error = result.expect_err("Expected resolver error for returning from init");
+    assert!(
+        error.message.contains("initializer"),
+        "Unexpected error message: {}",
+        error
+    );
+}
+
+#[test]
+fn test_this_outside_class_is_error() {
+    let (result, _) = execute("print this;");
+    let error = result.expect_err("Expected resolver error for top-level this");
+    assert!(
+        error
+            .message
+            .contains("Can't use 'this' outside of a class"),
+        "Unexpected error message: {}",
+        error
+    );
+}
+
+#[test]
+fn test_this_in_plain_function_is_error() {
+    let source = r#"
+        fun notAMethod() {
+            return this;
+        }
+    "#;
+
+    let (result, _) = execute(source);
+    assert!(
+        result.is_err(),
+        "Expected resolver error for this in a function"
+    );
+}
+
+#[test]
+fn test_fields_shadow_methods() {
+    let source = r#"
+        class Shadow {
+            value() {
+                return "method";
+            }
+        }
+        var s = Shadow();
+        s.value = "field";
+        var v = s.value;
+    "#;
+
+    let (result, interpreter) = execute(source);
+    assert!(result.is_ok(), "Field shadowing failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "v"), Object::String("field".into()));
+}
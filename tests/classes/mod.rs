@@ -0,0 +1,58 @@
+use crate::common::TestErrorReporter;
+use lox::compiler::error::{LoxErrorKind, Result};
+use lox::compiler::expr::Object;
+use lox::compiler::interpreter::Interpreter;
+use lox::compiler::parser::Parser;
+use lox::compiler::resolver::Resolver;
+use lox::compiler::scanner::Scanner;
+use lox::compiler::token::{Token, TokenType};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+mod inheritance;
+mod instance_fields;
+mod methods;
+
+// Helper function to scan, parse, resolve and run a source string, handing back the
+// interpreter so tests can inspect globals afterwards
+fn execute(source: &str) -> (Result<()>, Rc<RefCell<Interpreter>>) {
+    let mut reporter = TestErrorReporter::new();
+    let mut scanner = Scanner::new(source.to_string(), &mut reporter);
+    scanner.scan_tokens();
+
+    let interpreter = Rc::new(RefCell::new(Interpreter::new()));
+
+    let mut parser = Parser::new(&scanner.tokens);
+    let statements = match parser.parse() {
+        Ok(stmts) => stmts,
+        Err(e) => return (Err(e), interpreter),
+    };
+
+    let resolver = Resolver::new(interpreter.clone());
+    resolver.resolve_statements(&statements);
+
+    // Bail out on the first real resolver error, ignoring warnings
+    let resolver_error = resolver
+        .errors
+        .borrow()
+        .iter()
+        .find(|e| e.kind != LoxErrorKind::Warning)
+        .cloned();
+    if let Some(error) = resolver_error {
+        return (Err(error), interpreter);
+    }
+
+    let result = interpreter.borrow_mut().interpret(statements);
+    (result, interpreter)
+}
+
+// Helper function to read a global variable after a script has run
+fn global(interpreter: &Rc<RefCell<Interpreter>>, name: &str) -> Object {
+    let token = Token::new(TokenType::IDENTIFIER, name.to_string(), 0, None);
+    interpreter
+        .borrow()
+        ._globals
+        .borrow()
+        .get(name, &token)
+        .expect("global should be defined")
+}
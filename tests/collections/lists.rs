@@ -0,0 +1,253 @@
+use crate::common::{global, run_program};
+use lox::compiler::expr::Object;
+
+#[test]
+fn test_list_literal_and_indexing() {
+    let source = r#"
+        var xs = [10, 20, 30];
+        var first = xs[0];
+        var last = xs[2];
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "List indexing failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "first"), Object::Number(10.0));
+    assert_eq!(global(&interpreter, "last"), Object::Number(30.0));
+}
+
+#[test]
+fn test_empty_list_and_mixed_elements() {
+    let source = r#"
+        var empty = [];
+        var mixed = ["a", 1, true, nil, [2, 3]];
+        var emptyLen = len(empty);
+        var nested = mixed[4][1];
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "Mixed list failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "emptyLen"), Object::Number(0.0));
+    assert_eq!(global(&interpreter, "nested"), Object::Number(3.0));
+}
+
+#[test]
+fn test_index_assignment() {
+    let source = r#"
+        var xs = [1, 2, 3];
+        var assigned = xs[1] = 20;
+        var middle = xs[1];
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(
+        result.is_ok(),
+        "Index assignment failed: {:?}",
+        result.err()
+    );
+    assert_eq!(global(&interpreter, "assigned"), Object::Number(20.0));
+    assert_eq!(global(&interpreter, "middle"), Object::Number(20.0));
+}
+
+#[test]
+fn test_lists_are_shared_by_reference() {
+    let source = r#"
+        var a = [1];
+        var b = a;
+        push(b, 2);
+        var size = len(a);
+        var same = a == b;
+        var different = a == [1, 2];
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "Shared list failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "size"), Object::Number(2.0));
+    assert_eq!(global(&interpreter, "same"), Object::Boolean(true));
+    assert_eq!(global(&interpreter, "different"), Object::Boolean(false));
+}
+
+#[test]
+fn test_list_natives() {
+    let source = r#"
+        var xs = [];
+        push(xs, "b");
+        push(xs, "d");
+        insert(xs, 0, "a");
+        insert(xs, 2, "c");
+        insert(xs, 4, "e");
+        var popped = pop(xs);
+        var removed = remove(xs, 0);
+        var size = len(xs);
+        var joined = xs[0] + xs[1] + xs[2];
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "List natives failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "popped"), Object::String("e".into()));
+    assert_eq!(global(&interpreter, "removed"), Object::String("a".into()));
+    assert_eq!(global(&interpreter, "size"), Object::Number(3.0));
+    assert_eq!(global(&interpreter, "joined"), Object::String("bcd".into()));
+}
+
+#[test]
+fn test_list_built_in_loop() {
+    let source = r#"
+        var squares = [];
+        for (var i = 0; i < 5; i = i + 1) {
+            push(squares, i * i);
+        }
+        var sum = 0;
+        for (var i = 0; i < len(squares); i = i + 1) {
+            sum = sum + squares[i];
+        }
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "List loop failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "sum"), Object::Number(30.0));
+}
+
+#[test]
+fn test_list_field_on_instance() {
+    let source = r#"
+        class Stack {
+            init() {
+                this.items = [];
+            }
+            push(value) {
+                push(this.items, value);
+            }
+        }
+        var s = Stack();
+        s.push(1);
+        s.push(2);
+        s.items[0] = 5;
+        var top = s.items[1] + s.items[0];
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(
+        result.is_ok(),
+        "List on instance failed: {:?}",
+        result.err()
+    );
+    assert_eq!(global(&interpreter, "top"), Object::Number(7.0));
+}
+
+#[test]
+fn test_list_display() {
+    let xs = Object::List(std::rc::Rc::new(std::cell::RefCell::new(vec![
+        Object::Number(1.0),
+        Object::String("two".into()),
+        Object::Nil,
+    ])));
+    assert_eq!(xs.to_string(), "[1, two, nil]");
+}
+
+#[test]
+fn test_list_debug_shows_elements() {
+    let xs = Object::List(std::rc::Rc::new(std::cell::RefCell::new(vec![
+        Object::Number(1.0),
+        Object::String("two".into()),
+    ])));
+    assert_eq!(format!("{:?}", xs), r#"List([Number(1.0), String("two")])"#);
+}
+
+#[test]
+fn test_printing_a_list_that_contains_itself() {
+    let source = r#"
+        var a = [1];
+        push(a, a);
+        var b = [a, a];
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "Cyclic list failed: {:?}", result.err());
+    let a = global(&interpreter, "a");
+    assert_eq!(a.to_string(), "[1, [...]]");
+    assert_eq!(format!("{:?}", a), "List([Number(1.0), List([...])])");
+    // only a list inside itself is a cycle, not the same list twice side by side
+    assert_eq!(
+        global(&interpreter, "b").to_string(),
+        "[[1, [...]], [1, [...]]]"
+    );
+}
+
+#[test]
+fn test_index_out_of_bounds() {
+    let (result, _) = run_program("var xs = [1, 2];\nprint xs[2];");
+    let error = result.expect_err("Expected runtime error for out-of-bounds index");
+    assert!(
+        error.message.contains("out of bounds"),
+        "Unexpected error message: {}",
+        error
+    );
+    let token = error.token.expect("Error should carry the bracket token");
+    assert_eq!(token.lexeme, "[");
+    assert_eq!(token.line, 2);
+}
+
+#[test]
+fn test_negative_and_fractional_indices() {
+    let (result, _) = run_program("var xs = [1, 2]; print xs[-1];");
+    assert!(result.is_err(), "Negative index should be a runtime error");
+
+    let (result, _) = run_program("var xs = [1, 2]; print xs[0.5];");
+    let error = result.expect_err("Fractional index should be a runtime error");
+    assert!(
+        error.message.contains("must be an integer"),
+        "Unexpected error message: {}",
+        error
+    );
+}
+
+#[test]
+fn test_index_assignment_out_of_bounds() {
+    let (result, _) = run_program("var xs = []; xs[0] = 1;");
+    let error = result.expect_err("Expected runtime error for assignment past the end");
+    assert_eq!(error.token.expect("Error should carry a token").lexeme, "[");
+}
+
+#[test]
+fn test_index_non_list() {
+    let (result, _) = run_program("var n = 5; print n[0];");
+    let error = result.expect_err("Expected runtime error for indexing a number");
+    assert!(
+        error.message.contains("can be indexed"),
+        "Unexpected error message: {}",
+        error
+    );
+}
+
+#[test]
+fn test_pop_empty_list() {
+    let (result, _) = run_program("pop([]);");
+    let error = result.expect_err("Expected runtime error for popping an empty list");
+    assert!(
+        error.message.contains("empty list"),
+        "Unexpected error message: {}",
+        error
+    );
+}
+
+#[test]
+fn test_native_errors_point_at_the_call() {
+    let source = "var xs = [];\ninsert(xs, 5, 1);";
+    let (result, _) = run_program(source);
+    let error = result.expect_err("Expected runtime error for inserting past the end");
+    let token = error.token.expect("Error should carry the call's paren");
+    assert_eq!(token.lexeme, ")");
+    assert_eq!(token.line, 2);
+    assert_eq!(token.span.start, source.rfind(')').unwrap());
+}
+
+#[test]
+fn test_unterminated_list_literal() {
+    let (result, _) = run_program("var xs = [1, 2;");
+    let error = result.expect_err("Expected parse error for missing ']'");
+    assert!(
+        error.message.contains("Expect ']'"),
+        "Unexpected error message: {}",
+        error
+    );
+}
@@ -0,0 +1,187 @@
+use crate::common::{global, run_program};
+use lox::compiler::expr::Object;
+use lox::compiler::lox_map::{HashKey, LoxMap};
+
+#[test]
+fn test_map_literal_and_lookup() {
+    let source = r#"
+        var m = {"a": 1, "b": 2};
+        var sum = m["a"] + m["b"];
+        var empty = {};
+        var size = len(m) + len(empty);
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "Map lookup failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "sum"), Object::Number(3.0));
+    assert_eq!(global(&interpreter, "size"), Object::Number(2.0));
+}
+
+#[test]
+fn test_map_assignment_inserts_and_updates() {
+    let source = r#"
+        var m = {};
+        m["x"] = 1;
+        m["x"] = m["x"] + 1;
+        m[10] = "ten";
+        var x = m["x"];
+        var ten = m[10];
+        var size = len(m);
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "Map assignment failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "x"), Object::Number(2.0));
+    assert_eq!(global(&interpreter, "ten"), Object::String("ten".into()));
+    assert_eq!(global(&interpreter, "size"), Object::Number(2.0));
+}
+
+#[test]
+fn test_map_key_kinds() {
+    let source = r#"
+        var m = {true: "yes", 1: "one", "1": "string one"};
+        var a = m[true];
+        var b = m[1];
+        var c = m["1"];
+        m[-0] = "zero";
+        var d = m[0];
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "Map key kinds failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "a"), Object::String("yes".into()));
+    assert_eq!(global(&interpreter, "b"), Object::String("one".into()));
+    assert_eq!(
+        global(&interpreter, "c"),
+        Object::String("string one".into())
+    );
+    assert_eq!(global(&interpreter, "d"), Object::String("zero".into()));
+}
+
+#[test]
+fn test_map_natives() {
+    let source = r#"
+        var m = {"first": 1, "second": 2, "third": 3};
+        var hadSecond = has(m, "second");
+        var deleted = delete(m, "second");
+        var missing = delete(m, "second");
+        var hasSecond = has(m, "second");
+        var ks = keys(m);
+        var vs = values(m);
+        var joined = ks[0] + ks[1];
+        var total = vs[0] + vs[1];
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "Map natives failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "hadSecond"), Object::Boolean(true));
+    assert_eq!(global(&interpreter, "deleted"), Object::Number(2.0));
+    assert_eq!(global(&interpreter, "missing"), Object::Nil);
+    assert_eq!(global(&interpreter, "hasSecond"), Object::Boolean(false));
+    assert_eq!(
+        global(&interpreter, "joined"),
+        Object::String("firstthird".into())
+    );
+    assert_eq!(global(&interpreter, "total"), Object::Number(4.0));
+}
+
+#[test]
+fn test_map_block_is_still_a_block() {
+    let source = r#"
+        var inside;
+        { inside = {"k": "v"}; }
+        var v = inside["k"];
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(
+        result.is_ok(),
+        "Block/map disambiguation failed: {:?}",
+        result.err()
+    );
+    assert_eq!(global(&interpreter, "v"), Object::String("v".into()));
+}
+
+#[test]
+fn test_map_shared_by_reference() {
+    let source = r#"
+        var a = {};
+        var b = a;
+        b["k"] = 1;
+        var seen = has(a, "k");
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "Shared map failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "seen"), Object::Boolean(true));
+}
+
+#[test]
+fn test_map_display_keeps_insertion_order() {
+    let mut map = LoxMap::new();
+    map.insert(HashKey::String("b".into()), Object::Number(2.0));
+    map.insert(HashKey::String("a".into()), Object::Number(1.0));
+    map.insert(HashKey::String("c".into()), Object::Nil);
+    map.remove(&HashKey::String("a".into()));
+    let value = Object::Map(std::rc::Rc::new(std::cell::RefCell::new(map)));
+    assert_eq!(value.to_string(), "{b: 2, c: nil}");
+}
+
+#[test]
+fn test_printing_a_map_that_contains_itself() {
+    let source = r#"
+        var m = {"a": 1};
+        m["self"] = m;
+        var l = [m];
+        m["list"] = l;
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "Cyclic map failed: {:?}", result.err());
+    let m = global(&interpreter, "m");
+    assert_eq!(m.to_string(), "{a: 1, self: {...}, list: [{...}]}");
+    assert_eq!(
+        format!("{:?}", m),
+        r#"Map({String("a"): Number(1.0), String("self"): Map({...}), String("list"): List([Map({...})])})"#
+    );
+}
+
+#[test]
+fn test_unhashable_keys_are_rejected() {
+    assert!(HashKey::from_object(&Object::Nil).is_none());
+    assert!(HashKey::from_object(&Object::Number(f64::NAN)).is_none());
+
+    let (result, _) = run_program("var m = {}; m[[1]] = 2;");
+    let error = result.expect_err("Expected runtime error for list key");
+    assert!(
+        error.message.contains("Map keys must be"),
+        "Unexpected error message: {}",
+        error
+    );
+
+    let (result, _) = run_program("var m = {nil: 1};");
+    assert!(result.is_err(), "nil should not be accepted as a map key");
+}
+
+#[test]
+fn test_missing_key_is_error() {
+    let (result, _) = run_program("var m = {\"a\": 1}; print m[\"b\"];");
+    let error = result.expect_err("Expected runtime error for missing key");
+    assert!(
+        error.message.contains("Undefined key 'b'"),
+        "Unexpected error message: {}",
+        error
+    );
+    assert_eq!(error.token.expect("Error should carry a token").lexeme, "[");
+}
+
+#[test]
+fn test_map_entry_requires_colon() {
+    let (result, _) = run_program("var m = {\"a\" 1};");
+    let error = result.expect_err("Expected parse error for missing ':'");
+    assert!(
+        error.message.contains("Expect ':' after map key"),
+        "Unexpected error message: {}",
+        error
+    );
+}
@@ -1,4 +1,15 @@
-use lox::compiler::error::{ErrorReporter, LoxError};
+use lox::compiler::error::{ErrorReporter, LoxError, LoxErrorKind, Result};
+use lox::compiler::expr::Object;
+use lox::compiler::gc;
+use lox::compiler::interpreter::{Backend, Interpreter};
+use lox::compiler::parser::Parser;
+use lox::compiler::resolver::Resolver;
+use lox::compiler::scanner::Scanner;
+use lox::compiler::token::{Token, TokenType};
+use lox::compiler::vm::{FunctionProto, compile};
+use std::cell::RefCell;
+use std::env;
+use std::rc::Rc;
 
 pub struct TestErrorReporter {
     pub errors: Vec<(usize, String)>,
@@ -48,4 +59,95 @@ impl ErrorReporter for TestErrorReporter {
     fn runtime_error(&mut self, error: &LoxError) {
         self.runtime_errors.push(error.to_string());
     }
-} 
\ No newline at end of file
+}
+
+// Helper function to scan, parse, resolve and run a source string, handing back the
+// interpreter so tests can inspect globals afterwards
+pub fn run_program(source: &str) -> (Result<()>, Rc<RefCell<Interpreter>>) {
+    let interpreter = Interpreter::new();
+    interpreter.set_backend(crate::vm::parity::suite_backend());
+    // lets the whole suite be run with LOX_GC_STRESS=1; the heap is per thread, so this
+    // only affects the calling test
+    if env::var_os("LOX_GC_STRESS").is_some_and(|value| value != "0") {
+        gc::set_stress(true);
+    }
+    run_program_with(interpreter, source)
+}
+
+// Same as run_program, but fails the test if the program errors
+pub fn run_ok(source: &str) -> Rc<RefCell<Interpreter>> {
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "{:?}", result.err());
+    interpreter
+}
+
+// Same as run_program, but on the given backend
+pub fn run_program_on(backend: Backend, source: &str) -> (Result<()>, Rc<RefCell<Interpreter>>) {
+    let interpreter = Interpreter::new();
+    interpreter.set_backend(backend);
+    run_program_with(interpreter, source)
+}
+
+// Same as run_ok, but on the given backend
+pub fn run_ok_on(backend: Backend, source: &str) -> Rc<RefCell<Interpreter>> {
+    let (result, interpreter) = run_program_on(backend, source);
+    assert!(result.is_ok(), "{:?}", result.err());
+    interpreter
+}
+
+// Same as run_program, but on an interpreter the test has already configured
+pub fn run_program_with(
+    interpreter: Interpreter,
+    source: &str,
+) -> (Result<()>, Rc<RefCell<Interpreter>>) {
+    let mut reporter = TestErrorReporter::new();
+    let mut scanner = Scanner::new(source.to_string(), &mut reporter);
+    scanner.scan_tokens();
+
+    let interpreter = Rc::new(RefCell::new(interpreter));
+
+    let mut parser = Parser::new(&scanner.tokens);
+    let statements = match parser.parse() {
+        Ok(stmts) => stmts,
+        Err(e) => return (Err(e), interpreter),
+    };
+
+    let resolver = Resolver::new(interpreter.clone());
+    resolver.resolve_statements(&statements);
+
+    // Bail out on the first real resolver error, ignoring warnings
+    let resolver_error = resolver
+        .errors
+        .borrow()
+        .iter()
+        .find(|e| e.kind != LoxErrorKind::Warning)
+        .cloned();
+    if let Some(error) = resolver_error {
+        return (Err(error), interpreter);
+    }
+
+    let result = interpreter.borrow_mut().interpret(statements);
+    (result, interpreter)
+}
+
+// Helper function to read a global variable after a script has run
+pub fn global(interpreter: &Rc<RefCell<Interpreter>>, name: &str) -> Object {
+    let token = Token::new(TokenType::IDENTIFIER, name.to_string(), 0, None);
+    interpreter
+        .borrow()
+        ._globals
+        .borrow()
+        .get(name, &token)
+        .expect("global should be defined")
+}
+
+// Helper function to scan, parse and compile a source string to bytecode
+pub fn compile_source(source: &str) -> Rc<FunctionProto> {
+    let mut reporter = TestErrorReporter::new();
+    let mut scanner = Scanner::new(source.to_string(), &mut reporter);
+    scanner.scan_tokens();
+    let statements = Parser::new(&scanner.tokens)
+        .parse()
+        .expect("source should parse");
+    compile(&statements).expect("source should compile")
+}
@@ -0,0 +1,121 @@
+use super::*;
+use lox::compiler::error::ErrorReporter;
+use std::collections::HashSet;
+
+// Records the codes scanner errors are reported with
+struct CodeReporter {
+    codes: Vec<ErrorCode>,
+}
+
+impl ErrorReporter for CodeReporter {
+    fn error(&mut self, _line: usize, _message: &str) {}
+
+    fn runtime_error(&mut self, _error: &LoxError) {}
+
+    fn error_with_code(&mut self, _line: usize, code: ErrorCode, _message: &str) {
+        self.codes.push(code);
+    }
+}
+
+fn code_of(source: &str, message: &str) -> ErrorCode {
+    diagnostics(source)
+        .into_iter()
+        .find(|e| e.message.contains(message))
+        .unwrap_or_else(|| panic!("no diagnostic containing '{}'", message))
+        .code
+}
+
+fn runtime_code(source: &str) -> ErrorCode {
+    run_program(source).0.expect_err("should fail").code
+}
+
+#[test]
+fn test_codes_are_unique_and_documented() {
+    let mut seen = HashSet::new();
+    for code in ErrorCode::ALL {
+        assert!(seen.insert(code.as_str()), "duplicate code {}", code);
+        assert_eq!(code.as_str().len(), 5);
+        assert!(code.as_str().starts_with('E') || code.as_str().starts_with('W'));
+        assert!(!code.title().is_empty());
+        assert!(!code.explanation().is_empty());
+        assert_eq!(code.as_str().parse::<ErrorCode>(), Ok(*code));
+    }
+}
+
+#[test]
+fn test_parse_codes_case_insensitively() {
+    assert_eq!(
+        "e0301".parse::<ErrorCode>(),
+        Ok(ErrorCode::UndefinedVariable)
+    );
+    assert_eq!("W0201".parse::<ErrorCode>(), Ok(ErrorCode::UnusedVariable));
+    assert!("E9999".parse::<ErrorCode>().is_err());
+}
+
+#[test]
+fn test_scanner_codes() {
+    let mut reporter = CodeReporter { codes: Vec::new() };
+    let mut scanner = Scanner::new("@ \"a\\q\" /* open".to_string(), &mut reporter);
+    scanner.scan_tokens();
+    assert_eq!(
+        reporter.codes,
+        vec![
+            ErrorCode::UnexpectedCharacter,
+            ErrorCode::InvalidEscape,
+            ErrorCode::UnterminatedComment,
+        ]
+    );
+}
+
+#[test]
+fn test_parser_codes() {
+    assert_eq!(code_of("print 1", "Expect ';'"), ErrorCode::ExpectedToken);
+    assert_eq!(
+        code_of("var x = ;", "Expect expression"),
+        ErrorCode::ExpectedExpression
+    );
+    assert_eq!(
+        code_of("1 = 2;", "Invalid assignment"),
+        ErrorCode::InvalidAssignmentTarget
+    );
+}
+
+#[test]
+fn test_resolver_codes() {
+    assert_eq!(
+        code_of("{ var a = a; }", "own initializer"),
+        ErrorCode::SelfReferencingInitializer
+    );
+    assert_eq!(
+        code_of("{ var a = 1; var a = 2; print a; }", "Already a variable"),
+        ErrorCode::DuplicateDeclaration
+    );
+    assert_eq!(code_of("return 1;", "top level"), ErrorCode::TopLevelReturn);
+    assert_eq!(
+        code_of("print this;", "'this'"),
+        ErrorCode::ThisOutsideClass
+    );
+    assert_eq!(
+        code_of("break;", "'break'"),
+        ErrorCode::LoopControlOutsideLoop
+    );
+    assert_eq!(
+        code_of("fun f() { var x = 1; }", "never used"),
+        ErrorCode::UnusedVariable
+    );
+}
+
+#[test]
+fn test_runtime_codes() {
+    assert_eq!(runtime_code("print missing;"), ErrorCode::UndefinedVariable);
+    assert_eq!(runtime_code("fun f(a) {} f();"), ErrorCode::ArityMismatch);
+    assert_eq!(runtime_code("var x = 1; x();"), ErrorCode::NotCallable);
+    assert_eq!(runtime_code("print \"a\" - 1;"), ErrorCode::OperandType);
+    assert_eq!(runtime_code("print [1][3];"), ErrorCode::InvalidIndex);
+    assert_eq!(runtime_code("print {}[\"k\"];"), ErrorCode::UndefinedKey);
+    assert_eq!(runtime_code("push(1, 2);"), ErrorCode::NativeArgumentType);
+    assert_eq!(
+        runtime_code("class A {} print A().nope;"),
+        ErrorCode::UndefinedProperty
+    );
+}
@@ -0,0 +1,93 @@
+use super::*;
+use lox::compiler::diagnostic::to_json;
+use std::process::Command;
+
+#[test]
+fn test_parse_error_as_json() {
+    let source = "var x = 1;\nprint x 2;";
+    let errors = diagnostics(source);
+    assert_eq!(
+        to_json(&errors[0], "test.lox"),
+        r#"{"severity":"error","kind":"parse","code":"E0105","message":"Expect ';' after value.","file":"test.lox","line":2,"column":9,"span":{"start":19,"end":20},"notes":[],"trace":[]}"#
+    );
+}
+
+#[test]
+fn test_notes_are_included() {
+    let source = "{\n  var a = 1;\n  var a = 2;\n  print a;\n}";
+    let errors = diagnostics(source);
+    let error = errors
+        .iter()
+        .find(|e| e.message.contains("Already a variable"))
+        .expect("should report the duplicate");
+    let json = to_json(error, "test.lox");
+    assert!(
+        json.ends_with(r#""notes":[{"message":"previous declaration here","line":2,"column":7,"span":{"start":8,"end":9}}],"trace":[]}"#),
+        "{}",
+        json
+    );
+}
+
+#[test]
+fn test_warning_severity() {
+    let source = "fun f() {\n  var unused = 1;\n}";
+    let errors = diagnostics(source);
+    let warning = errors
+        .iter()
+        .find(|e| e.message.contains("never used"))
+        .expect("should warn about the unused variable");
+    let json = to_json(warning, "test.lox");
+    assert!(
+        json.starts_with(r#"{"severity":"warning","kind":"warning","#),
+        "{}",
+        json
+    );
+}
+
+#[test]
+fn test_line_only_error_has_null_column() {
+    let error = LoxError::new_from_line(4, "Unterminated string.");
+    assert_eq!(
+        to_json(&error, "test.lox"),
+        r#"{"severity":"error","kind":"parse","code":"E0100","message":"Unterminated string.","file":"test.lox","line":4,"column":null,"span":null,"notes":[],"trace":[]}"#
+    );
+}
+
+#[test]
+fn test_strings_are_escaped() {
+    let error = LoxError::new_internal("bad \"quote\"\\\n\tand \u{1}");
+    let json = to_json(&error, "dir\\a.lox");
+    assert!(
+        json.contains(r#""message":"bad \"quote\"\\\n\tand \u0001","file":"dir\\a.lox""#),
+        "{}",
+        json
+    );
+}
+
+#[test]
+fn test_cli_reports_warnings_in_source_order() {
+    let path = std::env::temp_dir().join(format!("lox_json_{}.lox", std::process::id()));
+    std::fs::write(&path, "{\n  var a = 1;\n  var b = 2;\n  var c = 3;\n}\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_lox"))
+        .arg("--error-format=json")
+        .arg(&path)
+        .output()
+        .expect("lox should run");
+    std::fs::remove_file(&path).unwrap();
+
+    let warning = |name: &str, line: usize, start: usize| {
+        format!(
+            r#"{{"severity":"warning","kind":"warning","code":"W0201","message":"Variable '{}' is defined but never used","file":{:?},"line":{},"column":7,"span":{{"start":{},"end":{}}},"notes":[],"trace":[]}}"#,
+            name,
+            path.display().to_string(),
+            line,
+            start,
+            start + 1
+        )
+    };
+    let expected = [warning("a", 2, 8), warning("b", 3, 21), warning("c", 4, 34)];
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        format!("{}\n", expected.join("\n"))
+    );
+}
@@ -0,0 +1,131 @@
+use crate::common::*;
+use lox::compiler::error::LoxError;
+use lox::compiler::{DiagnosticRenderer, ErrorCode, Interpreter, Parser, Resolver, Scanner};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+mod codes;
+mod json;
+mod traces;
+
+// Helper function to collect every error a source string produces before it would run
+fn diagnostics(source: &str) -> Vec<LoxError> {
+    let mut reporter = TestErrorReporter::new();
+    let mut scanner = Scanner::new(source.to_string(), &mut reporter);
+    scanner.scan_tokens();
+
+    let mut parser = Parser::new(&scanner.tokens);
+    let (statements, mut errors) = parser.parse_all();
+    if !errors.is_empty() {
+        return errors;
+    }
+
+    let resolver = Resolver::new(Rc::new(RefCell::new(Interpreter::new())));
+    resolver.resolve_statements(&statements);
+    errors.extend(resolver.errors.borrow().iter().cloned());
+    errors
+}
+
+fn render_plain(source: &str, error: &LoxError) -> String {
+    DiagnosticRenderer::new(source, "test.lox", false).render(error)
+}
+
+#[test]
+fn test_parse_error_underlines_token() {
+    let source = "var x = 1;\nprint x 2;";
+    let errors = diagnostics(source);
+    assert_eq!(
+        render_plain(source, &errors[0]),
+        "error[E0105]: Expect ';' after value.\n \
+         --> test.lox:2:9\n  \
+         |\n\
+         2 | print x 2;\n  \
+         |         ^\n"
+    );
+}
+
+#[test]
+fn test_runtime_error_underlines_whole_token() {
+    let source = "print missing;";
+    let (result, _) = run_program(source);
+    let error = result.expect_err("should fail at runtime");
+    let rendered = render_plain(source, &error);
+    assert!(rendered.starts_with("runtime error[E0301]: Undefined variable 'missing'"));
+    assert!(
+        rendered.ends_with("1 | print missing;\n  |       ^^^^^^^\n"),
+        "{}",
+        rendered
+    );
+}
+
+#[test]
+fn test_duplicate_declaration_notes_previous_one() {
+    let source = "{\n  var a = 1;\n  var a = 2;\n  print a;\n}";
+    let errors = diagnostics(source);
+    let error = errors
+        .iter()
+        .find(|e| e.message.contains("Already a variable"))
+        .expect("should report the duplicate");
+
+    assert_eq!(error.notes.len(), 1);
+    assert_eq!(error.notes[0].message, "previous declaration here");
+    assert_eq!(
+        error.notes[0].span.map(|s| (s.line, s.column)),
+        Some((2, 7))
+    );
+
+    let rendered = render_plain(source, error);
+    assert!(rendered.contains(" --> test.lox:3:7\n"), "{}", rendered);
+    assert!(
+        rendered.contains("note: previous declaration here\n --> test.lox:2:7\n  |\n2 |   var a = 1;\n  |       -\n"),
+        "{}",
+        rendered
+    );
+}
+
+#[test]
+fn test_self_reference_notes_declaration() {
+    let source = "{\n  var b = b;\n}";
+    let errors = diagnostics(source);
+    let error = errors
+        .iter()
+        .find(|e| e.message.contains("own initializer"))
+        .expect("should report the self reference");
+    let span = error.span().expect("error should point at the use");
+    assert_eq!((span.line, span.column), (2, 11));
+    assert_eq!(error.notes[0].message, "variable declared here");
+    assert_eq!(error.notes[0].span.map(|s| s.column), Some(7));
+}
+
+#[test]
+fn test_unused_variable_warning_points_at_declaration() {
+    let source = "fun f() {\n  var unused = 1;\n}";
+    let errors = diagnostics(source);
+    let warning = errors
+        .iter()
+        .find(|e| e.message.contains("never used"))
+        .expect("should warn about the unused variable");
+    let rendered = render_plain(source, warning);
+    assert!(rendered.starts_with("warning[W0201]: Variable 'unused'"));
+    assert!(rendered.contains("  |       ^^^^^^\n"), "{}", rendered);
+}
+
+#[test]
+fn test_line_only_errors_show_the_line() {
+    let source = "print 1;\nprint @;";
+    let error = LoxError::new_from_line(2, "Unexpected character '@'")
+        .with_code(ErrorCode::UnexpectedCharacter);
+    assert_eq!(
+        render_plain(source, &error),
+        "error[E0101]: Unexpected character '@'\n --> test.lox:2\n  |\n2 | print @;\n"
+    );
+}
+
+#[test]
+fn test_color_output_uses_ansi_codes() {
+    let source = "print 1 2;";
+    let errors = diagnostics(source);
+    let colored = DiagnosticRenderer::new(source, "test.lox", true).render(&errors[0]);
+    assert!(colored.contains("\x1b[1;31merror[E0105]:\x1b[0m"));
+    assert!(!render_plain(source, &errors[0]).contains('\x1b'));
+}
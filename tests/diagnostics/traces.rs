@@ -0,0 +1,81 @@
+use super::*;
+use lox::compiler::diagnostic::to_json;
+
+fn runtime_error(source: &str) -> LoxError {
+    let (result, _) = run_program(source);
+    result.expect_err("should fail at runtime")
+}
+
+#[test]
+fn test_trace_lists_frames_innermost_first() {
+    let source = "fun inner() {\n  return nope;\n}\nfun outer() {\n  print inner();\n}\nouter();";
+    let error = runtime_error(source);
+    let frames: Vec<(&str, usize)> = error
+        .trace
+        .iter()
+        .map(|frame| (frame.function.as_str(), frame.line))
+        .collect();
+    assert_eq!(frames, vec![("<fn inner>", 5), ("<fn outer>", 7)]);
+}
+
+#[test]
+fn test_tail_calls_replace_their_caller_frame() {
+    let source = "fun inner() {\n  return nope;\n}\nfun outer() {\n  return inner();\n}\nouter();";
+    let error = runtime_error(source);
+    let frames: Vec<(&str, usize)> = error
+        .trace
+        .iter()
+        .map(|frame| (frame.function.as_str(), frame.line))
+        .collect();
+    assert_eq!(frames, vec![("<fn inner>", 5)]);
+}
+
+#[test]
+fn test_trace_includes_natives() {
+    let source = "fun measure(x) {\n  return len(x) + 1;\n}\nmeasure(1);";
+    let error = runtime_error(source);
+    let names: Vec<&str> = error.trace.iter().map(|f| f.function.as_str()).collect();
+    assert_eq!(names, vec!["<native fn len>", "<fn measure>"]);
+}
+
+#[test]
+fn test_top_level_error_has_no_trace() {
+    let error = runtime_error("print -\"a\";");
+    assert!(error.trace.is_empty());
+}
+
+#[test]
+fn test_frames_are_popped_after_an_error() {
+    let (result, interpreter) = run_program("fun f() { return nil + 1; }\nf();");
+    assert!(result.is_err());
+    assert!(interpreter.borrow().frames.borrow().is_empty());
+}
+
+#[test]
+fn test_trace_is_rendered() {
+    let source = "fun boom() {\n  return nil + 1;\n}\nfun go() {\n  boom();\n}\ngo();";
+    let error = runtime_error(source);
+    let rendered = render_plain(source, &error);
+    assert!(
+        rendered.ends_with(
+            "stack trace (innermost call first):\n  \
+             <fn boom> called at test.lox:5:8\n  \
+             <fn go> called at test.lox:7:4\n"
+        ),
+        "{}",
+        rendered
+    );
+}
+
+#[test]
+fn test_trace_in_json() {
+    let source = "fun f() {\n  return nil + 1;\n}\nf();";
+    let json = to_json(&runtime_error(source), "test.lox");
+    assert!(
+        json.ends_with(
+            r#""trace":[{"function":"<fn f>","line":4,"column":3,"span":{"start":32,"end":33}}]}"#
+        ),
+        "{}",
+        json
+    );
+}
@@ -0,0 +1,79 @@
+use crate::common::TestErrorReporter;
+use lox::compiler::astPrinter::AstPrinter;
+use lox::compiler::formatter::{FormatOptions, format};
+use lox::compiler::parser::Parser;
+use lox::compiler::scanner::Scanner;
+use std::path::{Path, PathBuf};
+
+// every .lox file under the directory, depth first
+fn lox_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(path) = pending.pop() {
+        if path.is_dir() {
+            pending.extend(path.read_dir().unwrap().map(|entry| entry.unwrap().path()));
+        } else if path.extension().is_some_and(|ext| ext == "lox") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
+
+// the program's syntax tree and comments, which formatting must not change
+fn meaning(source: &str) -> (String, Vec<String>) {
+    let mut reporter = TestErrorReporter::new();
+    let mut scanner = Scanner::new(source.to_string(), &mut reporter);
+    scanner.scan_tokens();
+    let statements = Parser::new(&scanner.tokens)
+        .parse()
+        .expect("formatted source should parse");
+    let comments = scanner
+        .comments
+        .iter()
+        .map(|comment| comment.text.trim_end().to_string())
+        .collect();
+    (AstPrinter.print_program(&statements), comments)
+}
+
+// Formatting any sample that parses, with a few different options, gives a fixed point:
+// formatting the output again changes nothing, and the output has the same syntax tree
+// and comments as the original.
+#[test]
+fn test_formatting_samples_is_idempotent() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut files = lox_files(&root.join("lox_samples"));
+    files.extend(lox_files(&root.join("tests/parser/golden")));
+    let options = [
+        FormatOptions::default(),
+        FormatOptions {
+            indent: 2,
+            line_width: 40,
+        },
+        FormatOptions {
+            indent: 8,
+            line_width: 20,
+        },
+    ];
+
+    let mut checked = 0;
+    for path in &files {
+        let source = std::fs::read_to_string(path).unwrap();
+        for options in &options {
+            // the error samples don't parse, which is what they're for
+            let Ok(once) = format(&source, options) else {
+                continue;
+            };
+            let twice = format(&once, options).expect("formatted source should parse");
+            assert_eq!(once, twice, "{} with {:?}", path.display(), options);
+            assert_eq!(meaning(&once), meaning(&source), "{}", path.display());
+            assert!(
+                once.lines().all(|line| line.len() == line.trim_end().len()),
+                "{} has trailing whitespace",
+                path.display()
+            );
+            checked += 1;
+        }
+    }
+    assert!(checked > 20, "only {} samples formatted", checked);
+}
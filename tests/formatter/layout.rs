@@ -0,0 +1,290 @@
+use lox::compiler::ErrorCode;
+use lox::compiler::formatter::{FormatOptions, format};
+use std::process::Command;
+
+fn fmt(source: &str) -> String {
+    format(source, &FormatOptions::default()).expect("source should parse")
+}
+
+fn fmt_with(source: &str, indent: usize, line_width: usize) -> String {
+    let options = FormatOptions { indent, line_width };
+    format(source, &options).expect("source should parse")
+}
+
+#[test]
+fn test_reindents_and_splits_statements() {
+    assert_eq!(
+        fmt("var a=1;print a;{var b=a+1;if(b>1){print b;}else{print a;}}"),
+        "var a = 1;
+print a;
+{
+    var b = a + 1;
+    if (b > 1) {
+        print b;
+    } else {
+        print a;
+    }
+}
+"
+    );
+}
+
+#[test]
+fn test_puts_desugared_syntax_back() {
+    let source = "for(var i=0;i<3;i=i+1)print i;
+for(;;){break;}
+for(i=0;i<3;)print i;
+var x;
+var add=(a,b)=>a+b;
+var sq=fun(n){return n*n;};
+if(a)print 1;else if(b)print 2;else{print 3;}";
+    assert_eq!(
+        fmt(source),
+        "for (var i = 0; i < 3; i = i + 1) print i;
+for (;;) {
+    break;
+}
+for (i = 0; i < 3;) print i;
+var x;
+var add = (a, b) => a + b;
+var sq = fun (n) {
+    return n * n;
+};
+if (a) print 1;
+else if (b) print 2;
+else {
+    print 3;
+}
+"
+    );
+}
+
+#[test]
+fn test_classes_and_collections() {
+    let source = "class B<A{init(x){this.x=x;}get(){return super.get()+[1,2][0];}empty(){}}
+var m={\"a\":1,2:nil};
+m[\"a\"]=-m[\"a\"];
+class Empty{}";
+    assert_eq!(
+        fmt(source),
+        "class B < A {
+    init(x) {
+        this.x = x;
+    }
+    get() {
+        return super.get() + [1, 2][0];
+    }
+    empty() {}
+}
+var m = {\"a\": 1, 2: nil};
+m[\"a\"] = -m[\"a\"];
+class Empty {}
+"
+    );
+}
+
+#[test]
+fn test_literals_are_kept_as_written() {
+    assert_eq!(
+        fmt("print 1.50 + 007;\nprint \"tab\\t\\\"q\\\"\";"),
+        "print 1.50 + 007;\nprint \"tab\\t\\\"q\\\"\";\n"
+    );
+}
+
+#[test]
+fn test_keeps_comments() {
+    let source = "// header
+var a = 1;   // trailing
+/* before */ var b = 2;
+fun f() { // after brace
+  /* nested /* block */ comment */
+  return a;
+  // end of body
+}
+// end of file";
+    assert_eq!(
+        fmt(source),
+        "// header
+var a = 1; // trailing
+/* before */
+var b = 2;
+fun f() { // after brace
+    /* nested /* block */ comment */
+    return a;
+    // end of body
+}
+// end of file
+"
+    );
+}
+
+#[test]
+fn test_comments_inside_expressions_move_after_the_statement() {
+    let formatted = fmt("print f(1, // one\n 2);\nprint 3;");
+    assert_eq!(formatted, "print f(1, 2);\n// one\nprint 3;\n");
+
+    let formatted = fmt("var xs = [1, // one\n 2];\nprint xs;");
+    assert_eq!(formatted, "var xs = [1, 2];\n// one\nprint xs;\n");
+}
+
+#[test]
+fn test_comment_only_bodies_close_right_after_the_comment() {
+    assert_eq!(fmt("if (true) { // z\n}"), "if (true) { // z\n}\n");
+    assert_eq!(fmt("{ /* x */ }"), "{ /* x */ }\n");
+    assert_eq!(fmt("fun g() { /* y */ }"), "fun g() { /* y */ }\n");
+    assert_eq!(
+        fmt("class C { // none yet\n}"),
+        "class C { // none yet\n}\n"
+    );
+}
+
+#[test]
+fn test_comments_stay_with_their_branch() {
+    let source = "if (a) // then
+  print 1;
+else // otherwise
+  print 2;
+if (b) print 1; // one
+else print 2; // two
+if (c) { print 1; } // after then
+else { print 2; }
+while (d) // loop
+{ print d; }";
+    assert_eq!(
+        fmt(source),
+        "if (a) // then
+    print 1;
+else // otherwise
+    print 2;
+if (b) print 1; // one
+else print 2; // two
+if (c) {
+    print 1;
+} // after then
+else {
+    print 2;
+}
+while (d) // loop
+{
+    print d;
+}
+"
+    );
+}
+
+#[test]
+fn test_comments_stay_among_parameters() {
+    assert_eq!(
+        fmt("fun f(a, // first\n  b) { return a; }"),
+        "fun f(\n    a, // first\n    b\n) {\n    return a;\n}\n"
+    );
+    assert_eq!(
+        fmt("var g = (x, // the x\n y) => x;"),
+        "var g = (\n    x, // the x\n    y\n) => x;\n"
+    );
+}
+
+#[test]
+fn test_blank_lines_collapse_to_one() {
+    assert_eq!(
+        fmt("var a = 1;\n\n\n\nvar b = 2;\nvar c = 3;\n\n{\n\n  print a;\n\n}\n"),
+        "var a = 1;\n\nvar b = 2;\nvar c = 3;\n\n{\n    print a;\n}\n"
+    );
+}
+
+#[test]
+fn test_breaks_long_lines() {
+    let source = "var total = compute(firstArgument, secondArgument, [1, 2, 3], third);
+var sum = alpha + beta + gamma + delta + epsilon + zeta + eta;
+var pick = flag ? \"on the true side\" : \"on the false side\";
+var short = f(a, b);";
+    assert_eq!(
+        fmt_with(source, 2, 40),
+        "var total = compute(
+  firstArgument,
+  secondArgument,
+  [1, 2, 3],
+  third
+);
+var sum = alpha +
+  beta +
+  gamma +
+  delta +
+  epsilon +
+  zeta +
+  eta;
+var pick = flag
+  ? \"on the true side\"
+  : \"on the false side\";
+var short = f(a, b);
+"
+    );
+}
+
+#[test]
+fn test_lambda_arguments_stay_on_the_call_line() {
+    assert_eq!(
+        fmt("each(items, fun (item) { print item; });"),
+        "each(items, fun (item) {\n    print item;\n});\n"
+    );
+}
+
+#[test]
+fn test_indent_option() {
+    assert_eq!(
+        fmt_with("while (true) { if (x) { break; } }", 2, 80),
+        "while (true) {\n  if (x) {\n    break;\n  }\n}\n"
+    );
+}
+
+#[test]
+fn test_reports_every_error() {
+    let errors = format("var = 1;\nprint @;\nprint (;", &FormatOptions::default())
+        .expect_err("source should not parse");
+    assert!(
+        errors
+            .iter()
+            .any(|e| e.code == ErrorCode::UnexpectedCharacter),
+        "{:?}",
+        errors
+    );
+    assert!(errors.len() >= 3, "{:?}", errors);
+}
+
+#[test]
+fn test_cli_check_and_rewrite() {
+    let path = std::env::temp_dir().join(format!("lox_fmt_{}.lox", std::process::id()));
+    std::fs::write(&path, "var a=1;").unwrap();
+    let lox = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_lox"))
+            .args(args)
+            .arg(&path)
+            .output()
+            .expect("lox should run")
+    };
+
+    let check = lox(&["fmt", "--check"]);
+    assert_eq!(check.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&check.stdout).trim(),
+        path.display().to_string()
+    );
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "var a=1;");
+
+    assert!(lox(&["fmt"]).status.success());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "var a = 1;\n");
+    assert_eq!(lox(&["fmt", "--check"]).status.code(), Some(0));
+
+    // different options make the same file unformatted again
+    std::fs::write(&path, "{ print 1; }\n").unwrap();
+    assert!(lox(&["fmt", "--indent=2"]).status.success());
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "{\n  print 1;\n}\n"
+    );
+    assert_eq!(lox(&["fmt", "--check"]).status.code(), Some(1));
+
+    std::fs::write(&path, "print (;").unwrap();
+    assert_eq!(lox(&["fmt", "--check"]).status.code(), Some(65));
+    std::fs::remove_file(&path).unwrap();
+}
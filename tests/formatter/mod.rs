@@ -0,0 +1,2 @@
+mod idempotence;
+mod layout;
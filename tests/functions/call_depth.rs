@@ -0,0 +1,96 @@
+use crate::common::{global, run_program_with};
+use lox::Object;
+use lox::compiler::ErrorCode;
+use lox::compiler::error::LoxErrorKind;
+use lox::compiler::interpreter::{DEFAULT_MAX_CALL_DEPTH, Interpreter, MAX_CALL_DEPTH_LIMIT};
+use std::process::Command;
+use std::thread;
+
+fn interpreter_with_depth(depth: usize) -> Interpreter {
+    let interpreter = Interpreter::new();
+    interpreter.set_max_call_depth(depth);
+    interpreter
+}
+
+#[test]
+fn test_default_limit() {
+    assert_eq!(Interpreter::new().max_call_depth(), DEFAULT_MAX_CALL_DEPTH);
+}
+
+#[test]
+fn test_unbounded_recursion_overflows_cleanly() {
+    let source = "fun f() {\n  f();\n}\nf();";
+    let (result, interpreter) = run_program_with(interpreter_with_depth(50), source);
+    let error = result.expect_err("recursion should hit the limit");
+
+    assert_eq!(error.kind, LoxErrorKind::Runtime);
+    assert_eq!(error.code, ErrorCode::StackOverflow);
+    assert_eq!(error.message, "Stack overflow.");
+    // the failing call is the recursive one inside f
+    assert_eq!(error.line(), Some(2));
+    assert_eq!(error.trace.len(), 50);
+    assert!(interpreter.borrow().frames.borrow().is_empty());
+}
+
+#[test]
+fn test_recursion_within_the_limit_runs() {
+    let source = "fun count(n) { if (n == 0) return 0; return count(n - 1) + 1; }\n\
+                  var result = count(49);";
+    let (result, interpreter) = run_program_with(interpreter_with_depth(50), source);
+    assert!(result.is_ok(), "{:?}", result.err());
+    assert_eq!(global(&interpreter, "result"), Object::Number(49.0));
+}
+
+#[test]
+fn test_limit_counts_every_active_call() {
+    let source = "fun a() { b(); }\nfun b() { c(); }\nfun c() { clock(); }\na();";
+    let (result, _) = run_program_with(interpreter_with_depth(3), source);
+    let error = result.expect_err("the native call is the fourth frame");
+    assert_eq!(error.code, ErrorCode::StackOverflow);
+
+    let (result, _) = run_program_with(interpreter_with_depth(4), source);
+    assert!(result.is_ok(), "{:?}", result.err());
+}
+
+#[test]
+fn test_default_limit_fits_in_recommended_stack() {
+    let handle = thread::Builder::new()
+        .stack_size(Interpreter::stack_size_for(DEFAULT_MAX_CALL_DEPTH))
+        .spawn(|| {
+            let interpreter = Interpreter::new();
+            interpreter.set_stack_size(Interpreter::stack_size_for(DEFAULT_MAX_CALL_DEPTH));
+            let (result, _) = run_program_with(interpreter, "fun f() { f(); }\nf();");
+            result.map_err(|error| error.trace.len())
+        })
+        .unwrap();
+    // it's the depth limit that stops the recursion, not the stack running short
+    assert_eq!(handle.join().unwrap(), Err(DEFAULT_MAX_CALL_DEPTH));
+}
+
+#[test]
+fn test_recursion_on_a_default_thread_overflows_cleanly() {
+    // embedders that don't size their thread still get an error rather than a crash
+    let handle = thread::spawn(|| {
+        let (result, _) = run_program_with(Interpreter::new(), "fun f() { f(); }\nf();");
+        result.map_err(|error| error.code)
+    });
+    assert_eq!(handle.join().unwrap(), Err(ErrorCode::StackOverflow));
+}
+
+#[test]
+fn test_max_call_depth_flag_is_capped() {
+    let too_deep = format!("--max-call-depth={}", MAX_CALL_DEPTH_LIMIT + 1);
+    let output = Command::new(env!("CARGO_BIN_EXE_lox"))
+        .args([too_deep.as_str(), "lox_samples/fib.lox"])
+        .output()
+        .expect("Failed to run lox");
+    assert_eq!(output.status.code(), Some(64));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        format!(
+            "--max-call-depth can be at most {}, got {}.\n",
+            MAX_CALL_DEPTH_LIMIT,
+            MAX_CALL_DEPTH_LIMIT + 1
+        )
+    );
+}
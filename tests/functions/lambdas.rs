@@ -0,0 +1,150 @@
+use crate::common::{global, run_program};
+use lox::compiler::expr::Object;
+
+#[test]
+fn test_lambda_assigned_to_variable() {
+    let source = r#"
+        var add = fun (a, b) {
+            return a + b;
+        };
+        var result = add(2, 3);
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "Lambda call failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "result"), Object::Number(5.0));
+}
+
+#[test]
+fn test_lambda_passed_as_callback() {
+    let source = r#"
+        fun map(xs, f) {
+            var out = [];
+            for (var i = 0; i < len(xs); i = i + 1) {
+                push(out, f(xs[i]));
+            }
+            return out;
+        }
+        var doubled = map([1, 2, 3], fun (x) { return x * 2; });
+        var total = doubled[0] + doubled[1] + doubled[2];
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "Lambda callback failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "total"), Object::Number(12.0));
+}
+
+#[test]
+fn test_lambda_closes_over_environment() {
+    let source = r#"
+        fun makeCounter() {
+            var count = 0;
+            return fun () {
+                count = count + 1;
+                return count;
+            };
+        }
+        var counter = makeCounter();
+        counter();
+        counter();
+        var result = counter();
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "Lambda closure failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "result"), Object::Number(3.0));
+}
+
+#[test]
+fn test_arrow_lambda() {
+    let source = r#"
+        var square = (x) => x * x;
+        var add = (a, b) => a + b;
+        var answer = () => 42;
+        var result = add(square(3), answer());
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "Arrow lambda failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "result"), Object::Number(51.0));
+}
+
+#[test]
+fn test_grouping_still_parses() {
+    let source = r#"
+        var a = 2;
+        var result = (a) * (3 + 1);
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "Grouping regressed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "result"), Object::Number(8.0));
+}
+
+#[test]
+fn test_immediately_invoked_lambda_statement() {
+    let source = r#"
+        var result;
+        fun (x) { result = x; }(7);
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "IIFE failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "result"), Object::Number(7.0));
+}
+
+#[test]
+fn test_lambda_uses_this_in_method() {
+    let source = r#"
+        class Adder {
+            init(base) {
+                this.base = base;
+            }
+            adder() {
+                return (x) => this.base + x;
+            }
+        }
+        var add10 = Adder(10).adder();
+        var result = add10(5);
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(
+        result.is_ok(),
+        "Lambda with this failed: {:?}",
+        result.err()
+    );
+    assert_eq!(global(&interpreter, "result"), Object::Number(15.0));
+}
+
+#[test]
+fn test_lambda_arity_checked() {
+    let (result, _) = run_program("var f = (a) => a; f(1, 2);");
+    let error = result.expect_err("Expected arity error for lambda");
+    assert!(
+        error.message.contains("Expected 1 arguments but got 2"),
+        "Unexpected error message: {}",
+        error
+    );
+}
+
+#[test]
+fn test_lambda_display_name() {
+    let (result, interpreter) = run_program("var f = fun () {};");
+    assert!(
+        result.is_ok(),
+        "Lambda declaration failed: {:?}",
+        result.err()
+    );
+    assert_eq!(global(&interpreter, "f").to_string(), "<fn lambda>");
+}
+
+#[test]
+fn test_return_in_top_level_lambda_is_allowed() {
+    let (result, _) = run_program("var f = fun () { return 1; };");
+    assert!(
+        result.is_ok(),
+        "Return inside a lambda is not top-level: {:?}",
+        result.err()
+    );
+}
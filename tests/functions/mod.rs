@@ -1 +1,4 @@
-mod basic_functions;
\ No newline at end of file
+mod basic_functions;
+mod call_depth;
+mod lambdas;
+mod tail_calls;
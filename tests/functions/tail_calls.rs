@@ -0,0 +1,121 @@
+use crate::common::{global, run_ok, run_program_with};
+use lox::Object;
+use lox::compiler::ErrorCode;
+use lox::compiler::interpreter::Interpreter;
+
+#[test]
+fn test_self_recursion_a_million_deep() {
+    let interpreter = run_ok(
+        "fun count(n, acc) {
+           if (n == 0) return acc;
+           return count(n - 1, acc + 1);
+         }
+         var result = count(1000000, 0);",
+    );
+    assert_eq!(global(&interpreter, "result"), Object::Number(1000000.0));
+}
+
+#[test]
+fn test_mutual_recursion() {
+    let interpreter = run_ok(
+        "fun isEven(n) { if (n == 0) return true; return isOdd(n - 1); }
+         fun isOdd(n) { if (n == 0) return false; return isEven(n - 1); }
+         var even = isEven(100001);
+         var odd = isOdd(100001);",
+    );
+    assert_eq!(global(&interpreter, "even"), Object::Boolean(false));
+    assert_eq!(global(&interpreter, "odd"), Object::Boolean(true));
+}
+
+#[test]
+fn test_accumulator_fibonacci() {
+    let interpreter = run_ok(
+        "fun fib(n, a, b) {
+           if (n == 0) return a;
+           return fib(n - 1, b, a + b);
+         }
+         var result = fib(50, 0, 1);",
+    );
+    assert_eq!(
+        global(&interpreter, "result"),
+        Object::Number(12586269025.0)
+    );
+}
+
+#[test]
+fn test_tail_calls_from_loops_and_blocks() {
+    let interpreter = run_ok(
+        "fun down(n) {
+           while (true) {
+             {
+               if (n == 0) return \"done\";
+               return down(n - 1);
+             }
+           }
+         }
+         var result = down(5000);",
+    );
+    assert_eq!(
+        global(&interpreter, "result"),
+        Object::String("done".into())
+    );
+}
+
+#[test]
+fn test_methods_and_lambdas_tail_call() {
+    let interpreter = run_ok(
+        "class Counter {
+           loop(n) { if (n == 0) return \"method\"; return this.loop(n - 1); }
+         }
+         var method = Counter().loop(5000);
+         var step = fun (n) {
+           if (n == 0) return \"lambda\";
+           return step(n - 1);
+         };
+         var wrap = (n) => step(n);
+         var lambda = wrap(5000);",
+    );
+    assert_eq!(
+        global(&interpreter, "method"),
+        Object::String("method".into())
+    );
+    assert_eq!(
+        global(&interpreter, "lambda"),
+        Object::String("lambda".into())
+    );
+}
+
+#[test]
+fn test_tail_calls_respect_the_call_depth() {
+    // the tail call stays within one frame, so a limit of two calls is plenty
+    let interpreter = Interpreter::new();
+    interpreter.set_max_call_depth(2);
+    let source = "fun loop(n) { if (n == 0) return 0; return loop(n - 1); }\nloop(100);";
+    let (result, _) = run_program_with(interpreter, source);
+    assert!(result.is_ok(), "{:?}", result.err());
+}
+
+#[test]
+fn test_non_tail_recursion_still_overflows() {
+    let interpreter = Interpreter::new();
+    interpreter.set_max_call_depth(50);
+    let source = "fun sum(n) { if (n == 0) return 0; return sum(n - 1) + n; }\nsum(100);";
+    let (result, _) = run_program_with(interpreter, source);
+    assert_eq!(
+        result.map_err(|e| e.code).err(),
+        Some(ErrorCode::StackOverflow)
+    );
+}
+
+#[test]
+fn test_tail_call_to_class_and_native() {
+    let interpreter = run_ok(
+        "class Point { init(x) { this.x = x; } }
+         fun make(x) { return Point(x); }
+         fun size(list) { return len(list); }
+         var x = make(3).x;
+         var n = size([1, 2]);",
+    );
+    assert_eq!(global(&interpreter, "x"), Object::Number(3.0));
+    assert_eq!(global(&interpreter, "n"), Object::Number(2.0));
+}
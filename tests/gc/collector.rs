@@ -0,0 +1,166 @@
+use crate::common::{global, run_ok_on};
+use lox::Object;
+use lox::compiler::error::Result;
+use lox::compiler::gc;
+use lox::compiler::interpreter::{Backend, Interpreter};
+use std::cell::RefCell;
+use std::process::Command;
+use std::rc::Rc;
+
+// Each test runs on its own thread, and so gets a heap of its own
+
+// how many objects a collection run now frees
+fn freed_by_collect() -> usize {
+    let before = gc::stats().freed;
+    gc::collect();
+    gc::stats().freed - before
+}
+
+fn call(interpreter: &Rc<RefCell<Interpreter>>, name: &str) -> Result<Object> {
+    match global(interpreter, name) {
+        Object::Function(function) => function.call(&interpreter.borrow(), &[]),
+        other => panic!("{} is not a function: {:?}", name, other),
+    }
+}
+
+#[test]
+fn test_frees_recursive_functions_in_blocks() {
+    for backend in [Backend::TreeWalk, Backend::Bytecode] {
+        // each function refers to itself through the scope it's declared in
+        let _interpreter = run_ok_on(
+            backend,
+            "for (var i = 0; i < 100; i = i + 1) {
+               fun f() { return f; }
+             }",
+        );
+        let freed = freed_by_collect();
+        assert!(freed >= 100, "{:?} freed only {}", backend, freed);
+    }
+}
+
+#[test]
+fn test_frees_instances_that_refer_to_themselves() {
+    for backend in [Backend::TreeWalk, Backend::Bytecode] {
+        let _interpreter = run_ok_on(
+            backend,
+            "class Node {}
+             for (var i = 0; i < 100; i = i + 1) {
+               var node = Node();
+               node.next = node;
+             }",
+        );
+        let freed = freed_by_collect();
+        assert!(freed >= 100, "{:?} freed only {}", backend, freed);
+    }
+}
+
+#[test]
+fn test_frees_cycles_through_lists_and_maps() {
+    let _interpreter = run_ok_on(
+        Backend::TreeWalk,
+        "for (var i = 0; i < 100; i = i + 1) {
+           var list = [nil];
+           list[0] = list;
+           var map = {\"self\": nil};
+           map[\"self\"] = map;
+         }",
+    );
+    assert!(freed_by_collect() >= 200);
+}
+
+#[test]
+fn test_keeps_reachable_objects() {
+    for backend in [Backend::TreeWalk, Backend::Bytecode] {
+        let interpreter = run_ok_on(
+            backend,
+            "class Node {}
+             var keep = Node();
+             keep.next = keep;
+             keep.value = 7;
+             fun counter() {
+               var count = 0;
+               fun increment() { count = count + 1; return count; }
+               return increment;
+             }
+             var tick = counter();
+             tick();",
+        );
+        gc::collect();
+
+        match global(&interpreter, "keep") {
+            Object::Instance(instance) => {
+                let fields = instance.fields.borrow();
+                assert_eq!(fields.get("value"), Some(&Object::Number(7.0)));
+                assert!(matches!(fields.get("next"), Some(Object::Instance(_))));
+            }
+            other => panic!("keep is not an instance: {:?}", other),
+        }
+        // the closure's captured count survived the collection
+        assert_eq!(
+            call(&interpreter, "tick").unwrap(),
+            Object::Number(2.0),
+            "{:?}",
+            backend
+        );
+    }
+}
+
+#[test]
+fn test_stats_count_allocations_and_collections() {
+    let before = gc::stats();
+    let _interpreter = run_ok_on(Backend::TreeWalk, "var xs = [1, 2, 3]; var m = {};");
+    gc::collect();
+    let after = gc::stats();
+    assert!(after.allocated >= before.allocated + 2);
+    assert_eq!(after.collections, before.collections + 1);
+    assert!(after.live >= 2);
+}
+
+#[test]
+fn test_stress_mode_runs_programs_correctly() {
+    gc::set_stress(true);
+    for backend in [Backend::TreeWalk, Backend::Bytecode] {
+        let interpreter = run_ok_on(
+            backend,
+            "class Shape {
+               init(sides) { this.sides = sides; }
+               describe() { return fun () { return this.sides; }; }
+             }
+             class Square < Shape {
+               init() { super.init(4); }
+             }
+             fun sum(list) {
+               var total = 0;
+               for (var i = 0; i < len(list); i = i + 1) total = total + list[i];
+               return total;
+             }
+             var shapes = [];
+             for (var i = 0; i < 20; i = i + 1) {
+               var square = Square();
+               square.self = square;
+               push(shapes, square.describe()());
+             }
+             var total = sum(shapes);
+             var counts = {\"squares\": len(shapes)};",
+        );
+        assert_eq!(global(&interpreter, "total"), Object::Number(80.0));
+        match global(&interpreter, "counts") {
+            Object::Map(map) => assert_eq!(map.borrow().len(), 1),
+            other => panic!("counts is not a map: {:?}", other),
+        }
+    }
+    gc::set_stress(false);
+    assert!(gc::stats().collections > 100);
+}
+
+#[test]
+fn test_cli_prints_stats() {
+    let output = Command::new(env!("CARGO_BIN_EXE_lox"))
+        .args(["--gc-stress", "--gc-stats", "lox_samples/blocks.lox"])
+        .output()
+        .expect("run lox");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("gc: "), "{}", stderr);
+    assert!(stderr.contains("collections"), "{}", stderr);
+}
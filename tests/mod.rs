@@ -1,8 +1,17 @@
+mod classes;
+mod collections;
 mod common;
+mod diagnostics;
+mod formatter;
 mod functions;
+mod gc;
+mod optimizer;
 mod parser;
+mod repl;
 mod resolver;
 mod scanner;
+mod strings;
+mod vm;
 
 pub use common::*;
 pub use functions::*;
@@ -0,0 +1,233 @@
+use crate::common::{TestErrorReporter, global};
+use lox::Object;
+use lox::compiler::ErrorCode;
+use lox::compiler::astPrinter::AstPrinter;
+use lox::compiler::error::Result;
+use lox::compiler::expr::Expr;
+use lox::compiler::interpreter::{Backend, Interpreter};
+use lox::compiler::optimizer::optimize;
+use lox::compiler::parser::Parser;
+use lox::compiler::resolver::Resolver;
+use lox::compiler::scanner::Scanner;
+use lox::compiler::stmt::Stmt;
+use std::cell::RefCell;
+use std::path::Path;
+use std::process::Command;
+use std::rc::Rc;
+
+// Parses and resolves the source against a fresh interpreter, optionally optimizing it
+fn front_end(source: &str, optimized: bool) -> (Vec<Stmt>, Rc<RefCell<Interpreter>>) {
+    let mut reporter = TestErrorReporter::new();
+    let mut scanner = Scanner::new(source.to_string(), &mut reporter);
+    scanner.scan_tokens();
+    let statements = Parser::new(&scanner.tokens)
+        .parse()
+        .expect("source should parse");
+
+    let interpreter = Rc::new(RefCell::new(Interpreter::new()));
+    let resolver = Resolver::new(interpreter.clone());
+    resolver.resolve_statements(&statements);
+    match optimized {
+        true => (optimize(statements), interpreter),
+        false => (statements, interpreter),
+    }
+}
+
+fn optimized(source: &str) -> Vec<Stmt> {
+    front_end(source, true).0
+}
+
+fn run(backend: Backend, source: &str, optimized: bool) -> (Result<()>, Rc<RefCell<Interpreter>>) {
+    let (statements, interpreter) = front_end(source, optimized);
+    interpreter.borrow().set_backend(backend);
+    let result = interpreter.borrow_mut().interpret(statements);
+    (result, interpreter)
+}
+
+// the value a `var` declaration was folded to, if it was
+fn initializer(statement: &Stmt) -> Option<Object> {
+    match statement {
+        Stmt::Var(var) => match var.initializer.as_ref() {
+            Expr::Literal(literal) => Some(literal.value.clone()),
+            _ => None,
+        },
+        other => panic!("expected a var declaration, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_folds_constant_expressions() {
+    let program = optimized(
+        "var arithmetic = 2 * (3 + 4) - 10 / 4;
+         var comparison = 1 + 1 == 2;
+         var ordering = 3 >= 4;
+         var concatenation = \"a\" + \"b\" + 1;
+         var not = !nil;
+         var negated = -(2 + 3);
+         var ternary = 1 < 2 ? \"yes\" : \"no\";",
+    );
+    let values: Vec<Option<Object>> = program.iter().map(initializer).collect();
+    assert_eq!(
+        values,
+        vec![
+            Some(Object::Number(11.5)),
+            Some(Object::Boolean(true)),
+            Some(Object::Boolean(false)),
+            Some(Object::String("ab1".into())),
+            Some(Object::Boolean(true)),
+            Some(Object::Number(-5.0)),
+            Some(Object::String("yes".into())),
+        ]
+    );
+}
+
+#[test]
+fn test_leaves_non_constant_and_failing_expressions() {
+    let program = optimized(
+        "var a = 1;
+         var b = a + (2 * 3);
+         var c = 1 / 0;
+         var d = -\"a\";
+         var e = \"a\" - 1;
+         var f = a ? 1 : 2;",
+    );
+    let values: Vec<Option<Object>> = program.iter().map(initializer).collect();
+    assert_eq!(
+        values,
+        vec![Some(Object::Number(1.0)), None, None, None, None, None]
+    );
+
+    // the constant half of `a + (2 * 3)` is still folded
+    match &program[1] {
+        Stmt::Var(var) => assert_eq!(AstPrinter.print_expr(&var.initializer), "(+ a 6)"),
+        other => panic!("expected a var declaration, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_removes_dead_branches() {
+    let program = optimized(
+        "if (true) print 1; else print 2;
+         if (false) print 3;
+         if (nil) print 4; else print 5;
+         while (false) print 6;
+         while (1 > 2) { print 7; }
+         for (var i = 0; false; i = i + 1) print i;",
+    );
+    // the for loop's variable stays, only the loop goes
+    assert_eq!(
+        AstPrinter.print_program(&program),
+        "(print 1)\n(print 5)\n(block\n  (var i 0))\n"
+    );
+}
+
+#[test]
+fn test_optimizes_inside_functions_and_classes() {
+    let program = optimized(
+        "fun f() { if (false) return 1; return 2 + 3; }
+         class A { m() { while (false) {} return \"x\" + \"y\"; } }",
+    );
+    assert_eq!(
+        AstPrinter.print_program(&program),
+        "(fun f ()\n  (return 5))\n(class A\n  (fun m ()\n    (return \"xy\")))\n"
+    );
+}
+
+#[test]
+fn test_optimized_programs_give_the_same_results() {
+    let source = "var total = 0;
+         for (var i = 0; i < 10; i = i + 1) {
+           if (true) total = total + i * (2 - 1);
+           if (false) total = -1;
+         }
+         fun describe(n) { return n > 1 ? \"many\" : \"one\" + \"\"; }
+         class Box { init() { this.label = \"b\" + \"ox\"; } }
+         var label = Box().label + \" \" + describe(total);
+         var empty = 0;
+         while (false) empty = 1;
+         var kind = true ? [1 + 1] : nil;
+         var first = kind[0];";
+    for backend in [Backend::TreeWalk, Backend::Bytecode] {
+        let (plain_result, plain) = run(backend, source, false);
+        let (optimized_result, optimized) = run(backend, source, true);
+        assert!(plain_result.is_ok(), "{:?}", plain_result.err());
+        assert!(optimized_result.is_ok(), "{:?}", optimized_result.err());
+        for name in ["total", "label", "empty", "first"] {
+            assert_eq!(
+                global(&plain, name),
+                global(&optimized, name),
+                "{} on {:?}",
+                name,
+                backend
+            );
+        }
+        assert_eq!(
+            global(&optimized, "label"),
+            Object::String("box many".into())
+        );
+    }
+}
+
+#[test]
+fn test_runtime_errors_keep_their_location() {
+    let source = "var ok = 1 + 2;\nvar bad = ok * (4 / 0);";
+    for backend in [Backend::TreeWalk, Backend::Bytecode] {
+        let plain = run(backend, source, false).0.unwrap_err();
+        let optimized = run(backend, source, true).0.unwrap_err();
+        assert_eq!(optimized.code, ErrorCode::DivisionByZero);
+        assert_eq!(optimized.message, plain.message);
+        assert_eq!(optimized.span(), plain.span());
+        assert_eq!(optimized.span().unwrap().line, 2);
+    }
+}
+
+#[test]
+fn test_samples_behave_the_same_when_optimized() {
+    let dir = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/lox_samples"));
+    let mut pending = vec![dir.to_path_buf()];
+    let mut checked = 0;
+    while let Some(path) = pending.pop() {
+        if path.is_dir() {
+            pending.extend(path.read_dir().unwrap().map(|entry| entry.unwrap().path()));
+            continue;
+        }
+        let source = std::fs::read_to_string(&path).unwrap_or_default();
+        if path.extension().is_none_or(|ext| ext != "lox") || source.contains("clock()") {
+            continue;
+        }
+        let run = |optimize: bool| {
+            let mut command = Command::new(env!("CARGO_BIN_EXE_lox"));
+            if optimize {
+                command.arg("--optimize");
+            }
+            let output = command
+                .arg("--color=never")
+                .arg(&path)
+                .output()
+                .expect("lox should run");
+            (output.status.code(), output.stdout, output.stderr)
+        };
+        assert_eq!(run(false), run(true), "{}", path.display());
+        checked += 1;
+    }
+    assert!(checked > 0);
+}
+
+#[test]
+fn test_print_optimized_prints_only_the_program() {
+    let path = Path::new(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/lox_samples/control_flow/if_basic.lox"
+    ));
+    let output = Command::new(env!("CARGO_BIN_EXE_lox"))
+        .arg("--print-optimized")
+        .arg(path)
+        .output()
+        .expect("lox should run");
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+
+    let source = std::fs::read_to_string(path).unwrap();
+    let expected = AstPrinter.print_program(&optimized(&source));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+}
@@ -0,0 +1,127 @@
+use crate::common::TestErrorReporter;
+use lox::compiler::astPrinter::AstPrinter;
+use lox::compiler::error::LoxError;
+use lox::compiler::parser::Parser;
+use lox::compiler::scanner::Scanner;
+use lox::compiler::stmt::Stmt;
+
+// Helper function to parse a source string with error recovery enabled
+fn parse_all(source: &str) -> (Vec<Stmt>, Vec<LoxError>) {
+    let mut reporter = TestErrorReporter::new();
+    let mut scanner = Scanner::new(source.to_string(), &mut reporter);
+    scanner.scan_tokens();
+
+    let mut parser = Parser::new(&scanner.tokens);
+    parser.parse_all()
+}
+
+#[test]
+fn test_reports_every_error_in_one_pass() {
+    let source = r#"
+var a = ;
+print 1
+var b = 2;
+fun broken( { }
+class Good {}
+while true) print 3;
+print a + ;
+"#;
+
+    let (_, errors) = parse_all(source);
+    assert_eq!(
+        errors.len(),
+        5,
+        "Expected five errors, got: {:?}",
+        errors.iter().map(|e| e.to_string()).collect::<Vec<_>>()
+    );
+
+    let lines: Vec<usize> = errors
+        .iter()
+        .map(|e| e.token.as_ref().expect("Parse errors carry a token").line)
+        .collect();
+    assert_eq!(lines, vec![2, 4, 5, 7, 8]);
+}
+
+#[test]
+fn test_returns_statements_that_parsed() {
+    let source = r#"
+var ok1 = 1;
+var broken = ;
+var ok2 = 2;
+print ok1 + ok2;
+"#;
+
+    let (statements, errors) = parse_all(source);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        AstPrinter.print_program(&statements),
+        "(var ok1 1)\n(var ok2 2)\n(print (+ ok1 ok2))\n",
+        "Valid statements should survive recovery"
+    );
+}
+
+#[test]
+fn test_recovers_inside_blocks() {
+    let source = r#"
+fun f() {
+    var x = ;
+    print x;
+    return 1 +;
+}
+print "after";
+"#;
+
+    let (statements, errors) = parse_all(source);
+    assert_eq!(
+        errors.len(),
+        2,
+        "Expected both errors inside the function body, got: {:?}",
+        errors.iter().map(|e| e.to_string()).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        AstPrinter.print_program(&statements),
+        "(fun f ()\n  (print x))\n(print \"after\")\n",
+        "Function and trailing print should remain"
+    );
+}
+
+#[test]
+fn test_synchronizes_on_statement_keywords() {
+    // no semicolon after the bad declaration, so recovery has to stop at 'print'
+    let source = "var x = 1 2 print \"next\"; print \"last\";";
+
+    let (statements, errors) = parse_all(source);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(statements.len(), 2);
+}
+
+#[test]
+fn test_synchronizes_on_loop_control() {
+    // recovery stops at 'break' and 'continue' rather than skipping them
+    let source = "while (true) { var x = 1 2 break; }\nwhile (true) { var y = 1 2 continue; }";
+
+    let (statements, errors) = parse_all(source);
+    assert_eq!(errors.len(), 2);
+    assert_eq!(
+        AstPrinter.print_program(&statements),
+        "(while true\n  (block\n    (break)))\n(while true\n  (block\n    (continue)))\n"
+    );
+}
+
+#[test]
+fn test_clean_source_has_no_errors() {
+    let (statements, errors) = parse_all("var a = 1; print a;");
+    assert!(errors.is_empty());
+    assert_eq!(statements.len(), 2);
+}
+
+#[test]
+fn test_parse_still_returns_first_error() {
+    let mut reporter = TestErrorReporter::new();
+    let mut scanner = Scanner::new("var = 1;\nprint ;".to_string(), &mut reporter);
+    scanner.scan_tokens();
+
+    let mut parser = Parser::new(&scanner.tokens);
+    let error = parser.parse().expect_err("Expected a parse error");
+    assert_eq!(error.token.expect("Parse errors carry a token").line, 1);
+}
@@ -0,0 +1,52 @@
+use super::parse_source;
+use lox::compiler::astPrinter::AstPrinter;
+use std::path::Path;
+use std::process::Command;
+
+// Each `golden/*.lox` file is parsed and dumped as S-expressions, then compared with the
+// `.ast` file next to it. Run with LOX_BLESS=1 to rewrite the `.ast` files after an
+// intended change to the parser or the printer.
+#[test]
+fn test_golden_asts() {
+    let dir = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/parser/golden"));
+    let bless = std::env::var_os("LOX_BLESS").is_some_and(|value| value != "0");
+    let mut checked = 0;
+    for entry in dir.read_dir().expect("golden directory should exist") {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|ext| ext != "lox") {
+            continue;
+        }
+        let source = std::fs::read_to_string(&path).unwrap();
+        let (statements, reporter) = parse_source(&source);
+        reporter.assert_no_errors();
+        let actual = AstPrinter.print_program(&statements.expect("golden files should parse"));
+
+        let expected_path = path.with_extension("ast");
+        if bless {
+            std::fs::write(&expected_path, &actual).unwrap();
+        } else {
+            let expected = std::fs::read_to_string(&expected_path)
+                .unwrap_or_else(|_| panic!("missing {}", expected_path.display()));
+            assert_eq!(actual, expected, "{}", path.display());
+        }
+        checked += 1;
+    }
+    assert!(checked > 0);
+}
+
+#[test]
+fn test_dump_ast_flag() {
+    let path = Path::new(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/parser/golden/for_loops.lox"
+    ));
+    let output = Command::new(env!("CARGO_BIN_EXE_lox"))
+        .arg("--dump-ast")
+        .arg(path)
+        .output()
+        .expect("lox should run");
+    assert!(output.status.success());
+    let expected = std::fs::read_to_string(path.with_extension("ast")).unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+    assert!(output.stderr.is_empty());
+}
@@ -0,0 +1,158 @@
+use crate::common::{global, run_program};
+use lox::compiler::expr::Object;
+
+#[test]
+fn test_break_exits_while_loop() {
+    let source = r#"
+        var i = 0;
+        while (true) {
+            if (i == 5) break;
+            i = i + 1;
+        }
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "Break in while failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "i"), Object::Number(5.0));
+}
+
+#[test]
+fn test_continue_skips_rest_of_while_body() {
+    let source = r#"
+        var i = 0;
+        var odds = 0;
+        while (i < 10) {
+            i = i + 1;
+            if (i == 2 or i == 4 or i == 6 or i == 8 or i == 10) continue;
+            odds = odds + 1;
+        }
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(
+        result.is_ok(),
+        "Continue in while failed: {:?}",
+        result.err()
+    );
+    assert_eq!(global(&interpreter, "odds"), Object::Number(5.0));
+}
+
+#[test]
+fn test_continue_in_for_loop_runs_increment() {
+    let source = r#"
+        var sum = 0;
+        for (var i = 0; i < 10; i = i + 1) {
+            if (i < 5) continue;
+            sum = sum + i;
+        }
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "Continue in for failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "sum"), Object::Number(35.0));
+}
+
+#[test]
+fn test_break_in_for_loop() {
+    let source = r#"
+        var last = -1;
+        for (var i = 0; ; i = i + 1) {
+            last = i;
+            if (i >= 3) {
+                break;
+            }
+        }
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "Break in for failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "last"), Object::Number(3.0));
+}
+
+#[test]
+fn test_break_only_exits_innermost_loop() {
+    let source = r#"
+        var count = 0;
+        for (var i = 0; i < 3; i = i + 1) {
+            for (var j = 0; j < 10; j = j + 1) {
+                if (j == 2) break;
+                count = count + 1;
+            }
+        }
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "Nested break failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "count"), Object::Number(6.0));
+}
+
+#[test]
+fn test_return_inside_loop_still_returns() {
+    let source = r#"
+        fun find() {
+            for (var i = 0; i < 10; i = i + 1) {
+                if (i == 4) return i;
+            }
+            return -1;
+        }
+        var found = find();
+    "#;
+
+    let (result, interpreter) = run_program(source);
+    assert!(result.is_ok(), "Return in loop failed: {:?}", result.err());
+    assert_eq!(global(&interpreter, "found"), Object::Number(4.0));
+}
+
+#[test]
+fn test_break_outside_loop_is_error() {
+    let (result, _) = run_program("break;");
+    let error = result.expect_err("Expected resolver error for top-level break");
+    assert!(
+        error
+            .message
+            .contains("Can't use 'break' outside of a loop"),
+        "Unexpected error message: {}",
+        error
+    );
+}
+
+#[test]
+fn test_continue_outside_loop_is_error() {
+    let (result, _) = run_program("if (true) { continue; }");
+    let error = result.expect_err("Expected resolver error for continue outside loop");
+    assert!(
+        error
+            .message
+            .contains("Can't use 'continue' outside of a loop"),
+        "Unexpected error message: {}",
+        error
+    );
+}
+
+#[test]
+fn test_break_in_function_inside_loop_is_error() {
+    let source = r#"
+        while (true) {
+            fun escape() {
+                break;
+            }
+        }
+    "#;
+
+    let (result, _) = run_program(source);
+    assert!(
+        result.is_err(),
+        "Break inside a function nested in a loop should not target the loop"
+    );
+}
+
+#[test]
+fn test_break_requires_semicolon() {
+    let (result, _) = run_program("while (true) { break }");
+    let error = result.expect_err("Expected parse error for missing semicolon");
+    assert!(
+        error.message.contains("Expect ';' after 'break'"),
+        "Unexpected error message: {}",
+        error
+    );
+}
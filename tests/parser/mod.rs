@@ -1,9 +1,13 @@
 // Include our error reporting test modules
 mod block_scoping_tests;
+mod error_recovery;
 mod error_reporting;
+mod golden;
 mod if_statements;
 mod logical_operators;
+mod loop_control;
 mod runtime_errors;
+mod spans;
 
 use crate::common::TestErrorReporter;
 use lox::compiler::token::{Token, TokenType};
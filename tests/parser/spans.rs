@@ -0,0 +1,68 @@
+use super::parse_source;
+use lox::compiler::expr::Expr;
+use lox::compiler::stmt::Stmt;
+use lox::compiler::token::Span;
+
+fn text(source: &str, span: Span) -> &str {
+    &source[span.start..span.end]
+}
+
+#[test]
+fn test_statement_spans_include_semicolon() {
+    let source = "var x = 1 + 2;\nprint x;";
+    let (stmts, _) = parse_source(source);
+    let stmts = stmts.expect("Parsing should succeed");
+    assert_eq!(text(source, stmts[0].span()), "var x = 1 + 2;");
+    assert_eq!(text(source, stmts[1].span()), "print x;");
+    assert_eq!(stmts[1].span().line, 2);
+    assert_eq!(stmts[1].span().column, 1);
+}
+
+#[test]
+fn test_expression_spans() {
+    let source = "foo.bar(1, (2 * 3))[0] = -x;";
+    let (stmts, _) = parse_source(source);
+    let stmts = stmts.expect("Parsing should succeed");
+    let Stmt::Expression(stmt) = &stmts[0] else {
+        panic!("Expected expression statement");
+    };
+    let Expr::IndexSet(set) = stmt.expression.as_ref() else {
+        panic!("Expected index assignment");
+    };
+    assert_eq!(text(source, set.span), "foo.bar(1, (2 * 3))[0] = -x");
+    assert_eq!(text(source, set.object.span()), "foo.bar(1, (2 * 3))");
+    assert_eq!(text(source, set.value.span()), "-x");
+
+    let Expr::Call(call) = set.object.as_ref() else {
+        panic!("Expected call");
+    };
+    assert_eq!(text(source, call.callee.span()), "foo.bar");
+    assert_eq!(text(source, call.args[1].span()), "(2 * 3)");
+}
+
+#[test]
+fn test_block_and_function_spans() {
+    let source = "fun add(a, b) {\n  return a + b;\n}";
+    let (stmts, _) = parse_source(source);
+    let stmts = stmts.expect("Parsing should succeed");
+    let Stmt::Function(function) = &stmts[0] else {
+        panic!("Expected function declaration");
+    };
+    assert_eq!(text(source, function.span), source);
+    assert_eq!(text(source, function.body.span()), "{\n  return a + b;\n}");
+
+    let Stmt::Block(block) = function.body.as_ref() else {
+        panic!("Expected block body");
+    };
+    let ret = block.statements[0].span();
+    assert_eq!(text(source, ret), "return a + b;");
+    assert_eq!((ret.line, ret.column), (2, 3));
+}
+
+#[test]
+fn test_desugared_for_loop_points_at_whole_loop() {
+    let source = "for (var i = 0; i < 3; i = i + 1) print i;";
+    let (stmts, _) = parse_source(source);
+    let stmts = stmts.expect("Parsing should succeed");
+    assert_eq!(text(source, stmts[0].span()), source);
+}
@@ -0,0 +1,158 @@
+use crate::common::TestErrorReporter;
+use lox::compiler::error::LoxErrorKind;
+use lox::compiler::expr::Object;
+use lox::compiler::repl::needs_more_input;
+use lox::compiler::stmt::Stmt;
+use lox::compiler::{Interpreter, Parser, Resolver, Scanner};
+use std::cell::RefCell;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+
+// Mirrors what the REPL does per line: one interpreter and resolver shared by every run
+struct Session {
+    interpreter: Rc<RefCell<Interpreter>>,
+    resolver: Resolver,
+}
+
+impl Session {
+    fn new() -> Self {
+        let interpreter = Rc::new(RefCell::new(Interpreter::new()));
+        let resolver = Resolver::new(interpreter.clone());
+        Self {
+            interpreter,
+            resolver,
+        }
+    }
+
+    // Runs one line, returning the echoed value for bare expression statements
+    fn run_line(&self, source: &str) -> Option<Object> {
+        let mut reporter = TestErrorReporter::new();
+        let mut scanner = Scanner::new(source.to_string(), &mut reporter);
+        scanner.scan_tokens();
+
+        let mut parser = Parser::new(&scanner.tokens);
+        let ast = parser.parse().expect("Parsing should succeed");
+
+        self.resolver.errors.borrow_mut().clear();
+        self.resolver.resolve_statements(&ast);
+        let has_real_errors = self
+            .resolver
+            .errors
+            .borrow()
+            .iter()
+            .any(|e| e.kind != LoxErrorKind::Warning);
+        assert!(
+            !has_real_errors,
+            "Unexpected resolver error for {:?}",
+            source
+        );
+
+        match ast.as_slice() {
+            [Stmt::Expression(expression)] => Some(
+                self.interpreter
+                    .borrow()
+                    .evaluate(&expression.expression)
+                    .expect("Evaluation should succeed"),
+            ),
+            _ => {
+                self.interpreter
+                    .borrow_mut()
+                    .interpret(ast)
+                    .expect("Interpretation should succeed");
+                None
+            }
+        }
+    }
+}
+
+#[test]
+fn test_variables_persist_between_lines() {
+    let session = Session::new();
+    assert_eq!(session.run_line("var x = 1;"), None);
+    assert_eq!(session.run_line("x = x + 1;"), Some(Object::Number(2.0)));
+    assert_eq!(session.run_line("x * 10;"), Some(Object::Number(20.0)));
+}
+
+#[test]
+fn test_functions_and_classes_persist_between_lines() {
+    let session = Session::new();
+    session.run_line("fun twice(f, x) { return f(f(x)); }");
+    session.run_line("class Point { init(x) { this.x = x; } }");
+    session.run_line("var p = Point(3);");
+    assert_eq!(
+        session.run_line("twice((n) => n * 2, p.x);"),
+        Some(Object::Number(12.0))
+    );
+}
+
+#[test]
+fn test_closures_resolved_on_earlier_lines_still_work() {
+    let session = Session::new();
+    session.run_line("fun counter() { var n = 0; return fun () { n = n + 1; return n; }; }");
+    session.run_line("var next = counter();");
+    session.run_line("next();");
+    assert_eq!(session.run_line("next();"), Some(Object::Number(2.0)));
+}
+
+// Feeds `input` to the rlox REPL, returning what it wrote to stdout and stderr
+fn run_repl(input: &str) -> (String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lox"))
+        .arg("--color=never")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to start the REPL");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    (
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+        String::from_utf8_lossy(&output.stderr).into_owned(),
+    )
+}
+
+#[test]
+fn test_echo_matches_print() {
+    let (stdout, _) = run_repl("1 + 2;\nprint 1 + 2;\n\"a\";\nprint \"a\";\n");
+    assert_eq!(
+        stdout,
+        "> Number(3.0)\n> Number(3.0)\n> String(\"a\")\n> String(\"a\")\n> "
+    );
+}
+
+#[test]
+fn test_errors_quote_the_input_they_come_from() {
+    // the error is raised in the first input but only when the second one runs
+    let (_, stderr) = run_repl("fun f() {\n  return 1 + nil;\n}\nf();\n");
+    assert!(
+        stderr.contains("2 |   return 1 + nil;\n  |            ^\n"),
+        "Error should quote the function body:\n{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_needs_more_input_for_open_delimiters() {
+    assert!(needs_more_input("fun add(a, b) {"));
+    assert!(needs_more_input("fun add(a, b) {\n  return a +"));
+    assert!(needs_more_input("print (1 +"));
+    assert!(needs_more_input("var xs = [1,"));
+    assert!(needs_more_input("var m = {\"a\":"));
+    assert!(needs_more_input("print \"unterminated"));
+}
+
+#[test]
+fn test_needs_more_input_for_complete_input() {
+    assert!(!needs_more_input("print 1;"));
+    assert!(!needs_more_input("fun add(a, b) {\n  return a + b;\n}"));
+    assert!(!needs_more_input("print \"{ inside a string\";"));
+    assert!(!needs_more_input("// a comment with ( in it"));
+    // stray closers are left for the parser to report
+    assert!(!needs_more_input("}"));
+}
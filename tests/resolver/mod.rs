@@ -1,8 +1,10 @@
 use crate::common::*;
-use lox::compiler::{Interpreter, Parser, Resolver, Scanner};
+use lox::compiler::{ErrorCode, Interpreter, Parser, Resolver, Scanner};
 use std::cell::RefCell;
 use std::rc::Rc;
 
+mod single_line;
+mod slots;
 mod unused_variables;
 
 #[test]
@@ -27,6 +29,7 @@ fn test_return_at_top_level() {
     assert!(!errors.is_empty(), "Should have resolver errors");
 
     // Check that the error is about returning at top level
+    assert_eq!(errors[0].code, ErrorCode::TopLevelReturn);
     let error_msg = errors[0].message.to_lowercase();
     assert!(
         error_msg.contains("return") && error_msg.contains("top"),
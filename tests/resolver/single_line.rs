@@ -0,0 +1,62 @@
+use crate::common::*;
+use lox::compiler::expr::{Expr, Object};
+use lox::compiler::stmt::Stmt;
+use lox::compiler::{Parser, Scanner};
+
+fn string(value: &str) -> Object {
+    Object::String(value.into())
+}
+
+#[test]
+fn test_shadowed_variables_on_one_line() {
+    let interpreter = run_ok("var a; var b; { var x = 1; { var x = 2; a = x; } b = x; }");
+    assert_eq!(global(&interpreter, "a"), Object::Number(2.0));
+    assert_eq!(global(&interpreter, "b"), Object::Number(1.0));
+}
+
+#[test]
+fn test_closure_keeps_its_binding_on_one_line() {
+    let interpreter = run_ok(
+        r#"var a = "global"; var r1; var r2; { fun show() { return a; } r1 = show(); var a = "block"; r2 = show(); }"#,
+    );
+    assert_eq!(global(&interpreter, "r1"), string("global"));
+    assert_eq!(global(&interpreter, "r2"), string("global"));
+}
+
+#[test]
+fn test_assignment_targets_the_resolved_variable() {
+    // `x` inside set() resolves to the global even though a local `x` is in the
+    // closure's env by the time it runs
+    let interpreter = run_ok(
+        r#"var x = "global"; var r; { fun set() { x = "set"; } var x = "block"; set(); r = x; }"#,
+    );
+    assert_eq!(global(&interpreter, "x"), string("set"));
+    assert_eq!(global(&interpreter, "r"), string("block"));
+}
+
+#[test]
+fn test_same_name_at_different_depths_on_one_line() {
+    let interpreter = run_ok(
+        "var r; fun outer() { var x = 1; fun inner() { var x = 2; return x; } return inner() + x; } r = outer();",
+    );
+    assert_eq!(global(&interpreter, "r"), Object::Number(3.0));
+}
+
+#[test]
+fn test_ids_are_unique_across_parses() {
+    // the same text parsed twice (as the REPL does) must not share resolution ids
+    let parse_variable = || {
+        let mut reporter = TestErrorReporter::new();
+        let mut scanner = Scanner::new("x;".to_string(), &mut reporter);
+        scanner.scan_tokens();
+        let ast = Parser::new(&scanner.tokens).parse().unwrap();
+        match &ast[0] {
+            Stmt::Expression(statement) => match statement.expression.as_ref() {
+                Expr::Variable(variable) => variable.id,
+                other => panic!("expected a variable, got {:?}", other),
+            },
+            other => panic!("expected an expression statement, got {:?}", other),
+        }
+    };
+    assert_ne!(parse_variable(), parse_variable());
+}
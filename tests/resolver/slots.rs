@@ -0,0 +1,97 @@
+use crate::common::{global, run_ok};
+use lox::compiler::env::{Env, Slot};
+use lox::compiler::expr::Object;
+use lox::compiler::token::{Token, TokenType};
+
+#[test]
+fn test_locals_get_slots_in_declaration_order() {
+    let interpreter = run_ok("{ var a = 1; var b = 2; fun f() {} print b; print a; }");
+    let mut slots: Vec<Slot> = interpreter
+        .borrow()
+        .locals
+        .borrow()
+        .values()
+        .copied()
+        .collect();
+    slots.sort_by_key(|slot| slot.index);
+    assert_eq!(
+        slots,
+        vec![Slot { depth: 0, index: 0 }, Slot { depth: 0, index: 1 }]
+    );
+}
+
+#[test]
+fn test_slots_across_nested_scopes() {
+    let interpreter = run_ok(
+        "var result;
+         {
+           var a = 1;
+           var b = 10;
+           {
+             var c = 100;
+             fun add(x) { var y = x + c; return y + b + a; }
+             b = 20;
+             result = add(1000);
+           }
+         }",
+    );
+    assert_eq!(global(&interpreter, "result"), Object::Number(1121.0));
+}
+
+#[test]
+fn test_closures_capture_their_own_slots() {
+    let interpreter = run_ok(
+        "fun counter() {
+           var count = 0;
+           fun step() { count = count + 1; return count; }
+           return step;
+         }
+         var first = counter();
+         var second = counter();
+         first(); first();
+         var a = first();
+         var b = second();",
+    );
+    assert_eq!(global(&interpreter, "a"), Object::Number(3.0));
+    assert_eq!(global(&interpreter, "b"), Object::Number(1.0));
+}
+
+#[test]
+fn test_local_classes_and_super() {
+    let interpreter = run_ok(
+        "var result;
+         {
+           var unrelated = \"x\";
+           class A { name() { return \"A\"; } }
+           class B < A { name() { return super.name() + \"B\" + unrelated; } }
+           result = B().name();
+         }",
+    );
+    assert_eq!(global(&interpreter, "result"), Object::String("ABx".into()));
+}
+
+#[test]
+fn test_env_reads_and_writes_by_slot() {
+    let globals = Env::new_global();
+    let outer = Env::new_enclosed(globals.clone());
+    outer.borrow_mut().define("a", Object::Number(1.0));
+    outer.borrow_mut().define("b", Object::Number(2.0));
+    let inner = Env::new_enclosed(outer.clone());
+    inner.borrow_mut().define("c", Object::Number(3.0));
+
+    assert_eq!(inner.borrow().get_at(1, 1).unwrap(), Object::Number(2.0));
+    inner
+        .borrow_mut()
+        .assign_at(1, 0, Object::Number(5.0))
+        .unwrap();
+    assert_eq!(outer.borrow().get_at(0, 0).unwrap(), Object::Number(5.0));
+    assert!(inner.borrow().get_at(0, 1).is_err());
+
+    // the global env still binds by name
+    globals.borrow_mut().define("g", Object::Boolean(true));
+    let token = Token::new(TokenType::IDENTIFIER, "g".to_string(), 1, None);
+    assert_eq!(
+        globals.borrow().get("g", &token).unwrap(),
+        Object::Boolean(true)
+    );
+}
@@ -130,4 +130,21 @@ fn test_whitespace_handling() {
         TokenType::SEMICOLON,
     ]);
     reporter.assert_no_errors();
-} 
\ No newline at end of file
+} 
+
+#[test]
+fn test_arrow_operator() {
+    let (tokens, reporter) = scan("(x) => x == = >");
+    assert_token_sequence(&tokens, &[
+        TokenType::LPAREN,
+        TokenType::IDENTIFIER,
+        TokenType::RPAREN,
+        TokenType::ARROW,
+        TokenType::IDENTIFIER,
+        TokenType::EQUAL_EQUAL,
+        TokenType::EQUAL,
+        TokenType::GREATER,
+    ]);
+    assert_eq!(tokens[3].lexeme, "=>");
+    reporter.assert_no_errors();
+}
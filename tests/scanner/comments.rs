@@ -108,4 +108,37 @@ fn test_multiline_comment_at_eof() {
     let (tokens, reporter) = scan("123 /* comment */");
     assert_token_sequence(&tokens, &[TokenType::NUMBER]);
     reporter.assert_no_errors();
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_comments_are_kept_aside() {
+    let mut reporter = TestErrorReporter::new();
+    let source = "// first\nprint 1; /* outer /* nested */\n still */ print 2;";
+    let (tokens, comments) = {
+        let mut scanner = Scanner::new(source.to_string(), &mut reporter);
+        scanner.scan_tokens();
+        (scanner.tokens, scanner.comments)
+    };
+    reporter.assert_no_errors();
+
+    let texts: Vec<&str> = comments.iter().map(|c| c.text.as_str()).collect();
+    assert_eq!(
+        texts,
+        vec!["// first", "/* outer /* nested */\n still */"]
+    );
+    let second = comments[1].span;
+    assert_eq!((second.line, second.column), (2, 10));
+    assert_eq!(&source[second.start..second.end], texts[1]);
+    // the parser still only sees the code
+    assert_token_sequence(
+        &tokens,
+        &[
+            TokenType::PRINT,
+            TokenType::NUMBER,
+            TokenType::SEMICOLON,
+            TokenType::PRINT,
+            TokenType::NUMBER,
+            TokenType::SEMICOLON,
+        ],
+    );
+}
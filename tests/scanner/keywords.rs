@@ -24,6 +24,18 @@ fn test_keywords() {
     reporter.assert_no_errors();
 }
 
+#[test]
+fn test_loop_control_keywords() {
+    let (tokens, reporter) = scan("break continue breaker continued");
+    assert_token_sequence(&tokens, &[
+        TokenType::BREAK,
+        TokenType::CONTINUE,
+        TokenType::IDENTIFIER,
+        TokenType::IDENTIFIER,
+    ]);
+    reporter.assert_no_errors();
+}
+
 #[test]
 fn test_identifiers() {
     let (tokens, reporter) = scan("andy classroom elsewhere falsify forest funny iffier nilpotent orca printer returner superman thirst truthy variant whiley");
@@ -9,6 +9,7 @@ mod keywords;
 mod errors;
 mod comments;
 mod edge_cases;
+mod spans;
 
 // Helper function to create a scanner and get tokens
 fn scan(input: &str) -> (Vec<Token>, TestErrorReporter) {
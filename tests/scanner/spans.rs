@@ -0,0 +1,48 @@
+use super::*;
+use lox::compiler::token::Span;
+
+#[test]
+fn test_token_spans_on_one_line() {
+    let (tokens, reporter) = scan("var answer = 42;");
+    assert_eq!(tokens[0].span, Span::new(0, 3, 1, 1));
+    assert_eq!(tokens[1].span, Span::new(4, 10, 1, 5));
+    assert_eq!(tokens[2].span, Span::new(11, 12, 1, 12));
+    assert_eq!(tokens[3].span, Span::new(13, 15, 1, 14));
+    assert_eq!(tokens[4].span, Span::new(15, 16, 1, 16));
+    reporter.assert_no_errors();
+}
+
+#[test]
+fn test_columns_restart_on_each_line() {
+    let (tokens, reporter) = scan("print 1;\n  print 22;");
+    assert_eq!(tokens[3].lexeme, "print");
+    assert_eq!(tokens[3].span, Span::new(11, 16, 2, 3));
+    assert_eq!(tokens[4].span, Span::new(17, 19, 2, 9));
+    reporter.assert_no_errors();
+}
+
+#[test]
+fn test_two_char_and_string_spans() {
+    let (tokens, reporter) = scan("a >= \"hi\"");
+    assert_eq!(tokens[1].span, Span::new(2, 4, 1, 3));
+    // string spans include both quotes
+    assert_eq!(tokens[2].span, Span::new(5, 9, 1, 6));
+    reporter.assert_no_errors();
+}
+
+#[test]
+fn test_multiline_string_starts_where_it_opens() {
+    let (tokens, reporter) = scan("x = \"a\nb\";");
+    assert_eq!(tokens[2].token_type, TokenType::STRING);
+    assert_eq!(tokens[2].span, Span::new(4, 9, 1, 5));
+    assert_eq!(tokens[3].span, Span::new(9, 10, 2, 3));
+    reporter.assert_no_errors();
+}
+
+#[test]
+fn test_eof_span_is_empty_at_end() {
+    let (tokens, _) = scan("a\nbc");
+    let eof = tokens.last().unwrap();
+    assert_eq!(eof.span, Span::new(4, 4, 2, 3));
+    assert!(eof.span.is_empty());
+}
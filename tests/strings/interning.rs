@@ -0,0 +1,90 @@
+use crate::common::{global, run_ok_on};
+use lox::Object;
+use lox::compiler::interpreter::{Backend, Interpreter};
+use lox::compiler::lox_string::{LoxString, interned_count};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+fn string(interpreter: &Rc<RefCell<Interpreter>>, name: &str) -> LoxString {
+    match global(interpreter, name) {
+        Object::String(s) => s,
+        other => panic!("{} is not a string: {:?}", name, other),
+    }
+}
+
+#[test]
+fn test_equal_strings_share_one_allocation() {
+    for backend in [Backend::TreeWalk, Backend::Bytecode] {
+        let interpreter = run_ok_on(
+            backend,
+            "var literal = \"ab\";
+             var joined = \"a\" + \"b\";
+             var other = \"ba\";
+             var same = literal == joined;
+             var different = literal == other;",
+        );
+        let literal = string(&interpreter, "literal");
+        assert!(LoxString::ptr_eq(&literal, &string(&interpreter, "joined")));
+        assert!(!LoxString::ptr_eq(&literal, &string(&interpreter, "other")));
+        assert!(LoxString::ptr_eq(&literal, &LoxString::new("ab")));
+        assert_eq!(global(&interpreter, "same"), Object::Boolean(true));
+        assert_eq!(global(&interpreter, "different"), Object::Boolean(false));
+    }
+}
+
+#[test]
+fn test_copies_share_the_string() {
+    let interpreter = run_ok_on(
+        Backend::TreeWalk,
+        "var a = \"shared\";
+         var b = a;
+         fun id(x) { return x; }
+         var c = id(b);",
+    );
+    let a = string(&interpreter, "a");
+    assert!(LoxString::ptr_eq(&a, &string(&interpreter, "b")));
+    assert!(LoxString::ptr_eq(&a, &string(&interpreter, "c")));
+}
+
+#[test]
+fn test_global_names_use_the_intern_table() {
+    let _interpreter = run_ok_on(Backend::TreeWalk, "var someUnusualGlobalName = 1;");
+    let before = interned_count();
+    let _name = LoxString::new("someUnusualGlobalName");
+    assert_eq!(interned_count(), before);
+}
+
+#[test]
+fn test_map_keys_built_differently_match() {
+    let interpreter = run_ok_on(
+        Backend::Bytecode,
+        "var m = {};
+         m[\"key\" + 1] = \"found\";
+         var v = m[\"key1\"];",
+    );
+    assert_eq!(global(&interpreter, "v"), Object::String("found".into()));
+}
+
+#[test]
+fn test_unused_strings_are_pruned() {
+    let _interpreter = run_ok_on(
+        Backend::TreeWalk,
+        "for (var i = 0; i < 5000; i = i + 1) {
+           var temporary = \"string number \" + i;
+         }",
+    );
+    assert!(
+        interned_count() < 5000,
+        "{} strings interned",
+        interned_count()
+    );
+}
+
+#[test]
+fn test_debug_output_is_unchanged() {
+    assert_eq!(
+        format!("{:?}", Object::String("hi".into())),
+        "String(\"hi\")"
+    );
+    assert_eq!(LoxString::new("hi").to_string(), "hi");
+}
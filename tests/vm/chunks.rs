@@ -0,0 +1,102 @@
+use crate::common::compile_source;
+use lox::compiler::vm::{Constant, FunctionProto, OpCode};
+use std::rc::Rc;
+
+fn op(code: OpCode) -> u8 {
+    code as u8
+}
+
+fn function_constant(proto: &FunctionProto, name: &str) -> Rc<FunctionProto> {
+    proto
+        .chunk
+        .constants
+        .iter()
+        .find_map(|constant| match constant {
+            Constant::Function(function) if function.name == name => Some(function.clone()),
+            _ => None,
+        })
+        .expect("function should be in the constant pool")
+}
+
+#[test]
+fn test_expression_statement_bytecode() {
+    let script = compile_source("print 1 + 2;");
+    assert_eq!(
+        script.chunk.code,
+        vec![
+            op(OpCode::Constant),
+            0,
+            0,
+            op(OpCode::Constant),
+            1,
+            0,
+            op(OpCode::Add),
+            op(OpCode::Print),
+            op(OpCode::Nil),
+            op(OpCode::Return),
+        ]
+    );
+    assert!(
+        matches!(script.chunk.constants[..], [Constant::Number(a), Constant::Number(b)] if a == 1.0 && b == 2.0)
+    );
+}
+
+#[test]
+fn test_constants_are_deduplicated() {
+    let script = compile_source("print \"a\"; print \"a\"; print 3; print 3;");
+    assert_eq!(script.chunk.constants.len(), 2);
+}
+
+#[test]
+fn test_opcodes_round_trip_through_bytes() {
+    for byte in 0..=u8::MAX {
+        if let Some(code) = OpCode::from_byte(byte) {
+            assert_eq!(code as u8, byte);
+        }
+    }
+    assert_eq!(OpCode::from_byte(OpCode::IndexSet as u8 + 1), None);
+}
+
+#[test]
+fn test_line_table_maps_offsets_to_lines() {
+    let script = compile_source("var a = 1;\n\nprint a;");
+    let print = script
+        .chunk
+        .code
+        .iter()
+        .position(|&byte| byte == op(OpCode::Print))
+        .unwrap();
+
+    assert_eq!(script.chunk.line_at(0), 1);
+    assert_eq!(script.chunk.line_at(print), 3);
+    // one entry per run, not per byte
+    assert!(script.chunk.lines.len() < script.chunk.code.len());
+}
+
+#[test]
+fn test_closures_record_their_upvalues() {
+    let script = compile_source(
+        "fun outer() {
+           var count = 0;
+           fun inner() { count = count + 1; return count; }
+           return inner;
+         }",
+    );
+    let outer = function_constant(&script, "outer");
+    let inner = function_constant(&outer, "inner");
+
+    assert_eq!(outer.upvalue_count, 0);
+    assert_eq!(inner.upvalue_count, 1);
+    assert_eq!(inner.arity, 0);
+    assert!(inner.chunk.code.contains(&op(OpCode::GetUpvalue)));
+}
+
+#[test]
+fn test_return_of_a_call_is_a_tail_call() {
+    let script = compile_source("fun f(n) { return g(n); } fun g(n) { return n + 1; }");
+    let f = function_constant(&script, "f");
+    let g = function_constant(&script, "g");
+
+    assert!(f.chunk.code.contains(&op(OpCode::TailCall)));
+    assert!(!g.chunk.code.contains(&op(OpCode::TailCall)));
+}
@@ -0,0 +1,61 @@
+use crate::common::TestErrorReporter;
+use lox::compiler::parser::Parser;
+use lox::compiler::scanner::Scanner;
+use lox::compiler::vm::{compile, disassemble};
+
+fn disassemble_source(source: &str) -> String {
+    let mut reporter = TestErrorReporter::new();
+    let mut scanner = Scanner::new(source.to_string(), &mut reporter);
+    scanner.scan_tokens();
+    let statements = Parser::new(&scanner.tokens)
+        .parse()
+        .expect("source should parse");
+    disassemble(&compile(&statements).expect("source should compile"))
+}
+
+#[test]
+fn test_lists_offsets_lines_and_constants() {
+    let listing = disassemble_source("var greeting = \"hi\";\nprint greeting + \"!\";");
+    assert_eq!(
+        listing,
+        "== script ==
+0000    1 CONSTANT            0 \"hi\"
+0003    | DEFINE_GLOBAL       1 \"greeting\"
+0006    2 GET_GLOBAL          1 \"greeting\"
+0009    | CONSTANT            2 \"!\"
+0012    | ADD
+0013    | PRINT
+0014    | NIL
+0015    | RETURN
+"
+    );
+}
+
+#[test]
+fn test_shows_jump_targets() {
+    let listing = disassemble_source("while (false) print 1;");
+    assert!(listing.contains("JUMP_IF_FALSE       1 -> "), "{}", listing);
+    assert!(listing.contains("LOOP               "), "{}", listing);
+}
+
+#[test]
+fn test_nested_functions_follow_their_parent() {
+    let listing = disassemble_source(
+        "fun outer() {\n  var x = 1;\n  fun inner() { return x; }\n  return inner;\n}",
+    );
+    let script = listing.find("== script ==").unwrap();
+    let outer = listing.find("== outer ==").unwrap();
+    let inner = listing.find("== inner ==").unwrap();
+    assert!(script < outer && outer < inner, "{}", listing);
+    assert!(
+        listing.contains("CLOSURE             0 <fn outer>"),
+        "{}",
+        listing
+    );
+    assert!(
+        listing.contains("|                     local 1"),
+        "{}",
+        listing
+    );
+    assert!(listing.contains("GET_UPVALUE         0"), "{}", listing);
+}
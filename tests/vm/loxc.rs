@@ -0,0 +1,221 @@
+use crate::common::{TestErrorReporter, compile_source, global};
+use lox::Object;
+use lox::compiler::ErrorCode;
+use lox::compiler::interpreter::Interpreter;
+use lox::compiler::parser::Parser;
+use lox::compiler::scanner::Scanner;
+use lox::compiler::token::Span;
+use lox::compiler::vm::loxc::{self, FORMAT_VERSION, MAGIC};
+use lox::compiler::vm::{Chunk, Constant, FunctionProto, OpCode, compile, disassemble};
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+const PROGRAM: &str = "fun counter() {
+  var count = 0;
+  fun increment() { count = count + 1; return count; }
+  return increment;
+}
+class Greeter {
+  init(name) { this.name = name; }
+  greet() { return \"hi \" + this.name; }
+}
+var next = counter();
+next();
+var count = next();
+var greeting = Greeter(\"bob\").greet();
+var total = 0;
+for (var i = 0; i < 4; i = i + 1) {
+  if (i == 2) continue;
+  total = total + i;
+}";
+
+fn script(code: Vec<u8>, constants: Vec<Constant>) -> FunctionProto {
+    let mut chunk = Chunk::new();
+    for byte in code {
+        chunk.write(byte, Span::new(0, 1, 1, 1));
+    }
+    chunk.constants = constants;
+    FunctionProto {
+        name: "script".to_string(),
+        arity: 0,
+        upvalue_count: 0,
+        chunk,
+    }
+}
+
+fn rejection(bytes: &[u8]) -> ErrorCode {
+    loxc::read(bytes).expect_err("file should be rejected").code
+}
+
+#[test]
+fn test_round_trip_runs_the_same_program() {
+    let compiled = compile_source(PROGRAM);
+    let bytes = loxc::write(&compiled);
+    assert!(bytes.starts_with(MAGIC));
+
+    let loaded = loxc::read(&bytes).expect("file should load");
+    assert_eq!(disassemble(&loaded), disassemble(&compiled));
+    assert_eq!(loxc::write(&loaded), bytes);
+
+    let interpreter = Rc::new(RefCell::new(Interpreter::new()));
+    let result = interpreter.borrow().run_compiled(loaded);
+    assert!(result.is_ok(), "{:?}", result.err());
+    assert_eq!(global(&interpreter, "count"), Object::Number(2.0));
+    assert_eq!(
+        global(&interpreter, "greeting"),
+        Object::String("hi bob".into())
+    );
+    assert_eq!(global(&interpreter, "total"), Object::Number(4.0));
+}
+
+#[test]
+fn test_line_table_survives_the_round_trip() {
+    let compiled = compile_source(PROGRAM);
+    let loaded = loxc::read(&loxc::write(&compiled)).unwrap();
+    assert_eq!(loaded.chunk.lines, compiled.chunk.lines);
+}
+
+// everything the compiler produces must pass verification, or real files would be rejected
+#[test]
+fn test_samples_verify_after_a_round_trip() {
+    fn visit(dir: &Path) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                visit(&path);
+                continue;
+            }
+            let source = fs::read_to_string(&path).unwrap();
+            let mut reporter = TestErrorReporter::new();
+            let mut scanner = Scanner::new(source, &mut reporter);
+            scanner.scan_tokens();
+            let Ok(statements) = Parser::new(&scanner.tokens).parse() else {
+                continue;
+            };
+            let Ok(compiled) = compile(&statements) else {
+                continue;
+            };
+            let loaded = loxc::read(&loxc::write(&compiled));
+            assert!(loaded.is_ok(), "{}: {:?}", path.display(), loaded.err());
+        }
+    }
+    visit(Path::new(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/lox_samples"
+    )));
+}
+
+#[test]
+fn test_rejects_files_without_the_header() {
+    assert_eq!(rejection(b""), ErrorCode::InvalidBytecode);
+    assert_eq!(rejection(b"print 1;"), ErrorCode::InvalidBytecode);
+}
+
+#[test]
+fn test_rejects_other_format_versions() {
+    let mut bytes = loxc::write(&compile_source("print 1;"));
+    bytes[4..6].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+    assert_eq!(rejection(&bytes), ErrorCode::BytecodeVersion);
+}
+
+#[test]
+fn test_rejects_every_truncation() {
+    let bytes = loxc::write(&compile_source(PROGRAM));
+    for len in 0..bytes.len() {
+        assert_eq!(
+            rejection(&bytes[..len]),
+            ErrorCode::InvalidBytecode,
+            "length {}",
+            len
+        );
+    }
+}
+
+#[test]
+fn test_rejects_trailing_data() {
+    let mut bytes = loxc::write(&compile_source("print 1;"));
+    bytes.push(0);
+    assert_eq!(rejection(&bytes), ErrorCode::InvalidBytecode);
+}
+
+#[test]
+fn test_corrupt_bytes_never_panic() {
+    let bytes = loxc::write(&compile_source(PROGRAM));
+    for at in 0..bytes.len() {
+        for flip in [0x01, 0x80, 0xff] {
+            let mut corrupt = bytes.clone();
+            corrupt[at] ^= flip;
+            let _ = loxc::read(&corrupt);
+        }
+    }
+}
+
+#[test]
+fn test_rejects_unknown_opcodes() {
+    let bytes = loxc::write(&script(vec![0xee, OpCode::Return as u8], vec![]));
+    let error = loxc::read(&bytes).unwrap_err();
+    assert!(
+        error.message.contains("unknown opcode 238"),
+        "{}",
+        error.message
+    );
+}
+
+#[test]
+fn test_rejects_constants_of_the_wrong_kind() {
+    let code = vec![OpCode::GetGlobal as u8, 0, 0, OpCode::Return as u8];
+    let bytes = loxc::write(&script(code, vec![Constant::Number(1.0)]));
+    assert_eq!(rejection(&bytes), ErrorCode::InvalidBytecode);
+}
+
+#[test]
+fn test_rejects_stack_underflow() {
+    let bytes = loxc::write(&script(
+        vec![OpCode::Pop as u8, OpCode::Pop as u8, OpCode::Return as u8],
+        vec![],
+    ));
+    let error = loxc::read(&bytes).unwrap_err();
+    assert!(
+        error.message.contains("stack underflow"),
+        "{}",
+        error.message
+    );
+}
+
+#[test]
+fn test_rejects_locals_past_the_stack_top() {
+    let code = vec![OpCode::GetLocal as u8, 5, OpCode::Return as u8];
+    let error = loxc::read(&loxc::write(&script(code, vec![]))).unwrap_err();
+    assert!(
+        error.message.contains("local slot out of range"),
+        "{}",
+        error.message
+    );
+}
+
+#[test]
+fn test_rejects_jumps_into_an_instruction() {
+    let code = vec![
+        OpCode::Jump as u8,
+        1,
+        0,
+        OpCode::Constant as u8,
+        0,
+        0,
+        OpCode::Return as u8,
+    ];
+    let error = loxc::read(&loxc::write(&script(code, vec![Constant::Number(1.0)]))).unwrap_err();
+    assert!(
+        error.message.contains("inside an instruction"),
+        "{}",
+        error.message
+    );
+}
+
+#[test]
+fn test_rejects_code_running_off_the_end() {
+    let error = loxc::read(&loxc::write(&script(vec![OpCode::Nil as u8], vec![]))).unwrap_err();
+    assert!(error.message.contains("past the end"), "{}", error.message);
+}
@@ -0,0 +1,142 @@
+use crate::common::{global, run_ok_on, run_program_on, run_program_with};
+use lox::Object;
+use lox::compiler::ErrorCode;
+use lox::compiler::error::LoxErrorKind;
+use lox::compiler::interpreter::{Backend, Interpreter};
+use std::process::Command;
+
+#[test]
+fn test_backend_names() {
+    assert_eq!(Backend::from_name("tree"), Some(Backend::TreeWalk));
+    assert_eq!(Backend::from_name("vm"), Some(Backend::Bytecode));
+    assert_eq!(Backend::from_name("jit"), None);
+}
+
+#[test]
+fn test_closures_share_captured_variables() {
+    let interpreter = run_ok_on(
+        Backend::Bytecode,
+        "fun counter() {
+           var count = 0;
+           fun increment() { count = count + 1; return count; }
+           return increment;
+         }
+         var a = counter();
+         var b = counter();
+         a(); a();
+         var first = a();
+         var second = b();",
+    );
+    assert_eq!(global(&interpreter, "first"), Object::Number(3.0));
+    assert_eq!(global(&interpreter, "second"), Object::Number(1.0));
+}
+
+#[test]
+fn test_upvalues_close_over_each_loop_iteration() {
+    let interpreter = run_ok_on(
+        Backend::Bytecode,
+        "var fns = [nil, nil, nil];
+         for (var i = 0; i < 3; i = i + 1) {
+           var j = i;
+           fns[i] = fun () { return j; };
+         }
+         var sum = fns[0]() + fns[1]() + fns[2]();",
+    );
+    assert_eq!(global(&interpreter, "sum"), Object::Number(3.0));
+}
+
+#[test]
+fn test_classes_with_super_calls() {
+    let interpreter = run_ok_on(
+        Backend::Bytecode,
+        "class Shape {
+           init(name) { this.name = name; }
+           describe() { return this.name; }
+         }
+         class Square < Shape {
+           init(side) { super.init(\"square\"); this.side = side; }
+           describe() { return super.describe() + \" of side \"; }
+         }
+         var square = Square(2);
+         var description = square.describe();
+         var side = square.side;",
+    );
+    assert_eq!(
+        global(&interpreter, "description"),
+        Object::String("square of side ".into())
+    );
+    assert_eq!(global(&interpreter, "side"), Object::Number(2.0));
+}
+
+#[test]
+fn test_tail_calls_run_in_constant_space() {
+    let interpreter = Interpreter::new();
+    interpreter.set_backend(Backend::Bytecode);
+    interpreter.set_max_call_depth(50);
+    let (result, interpreter) = run_program_with(
+        interpreter,
+        "fun count(n, acc) {
+           if (n == 0) return acc;
+           return count(n - 1, acc + 1);
+         }
+         var result = count(100000, 0);",
+    );
+    assert!(result.is_ok(), "{:?}", result.err());
+    assert_eq!(global(&interpreter, "result"), Object::Number(100000.0));
+}
+
+#[test]
+fn test_stack_overflow_is_a_runtime_error() {
+    let interpreter = Interpreter::new();
+    interpreter.set_backend(Backend::Bytecode);
+    interpreter.set_max_call_depth(50);
+    let (result, _) = run_program_with(interpreter, "fun f() {\n  f();\n}\nf();");
+    let error = result.expect_err("recursion should hit the limit");
+
+    assert_eq!(error.kind, LoxErrorKind::Runtime);
+    assert_eq!(error.code, ErrorCode::StackOverflow);
+    assert_eq!(error.line(), Some(2));
+    assert_eq!(error.trace.len(), 50);
+}
+
+#[test]
+fn test_runtime_errors_carry_the_call_stack() {
+    let source = "fun step(a) {\n  return a - \"1\";\n}\nfun run() {\n  step(1);\n}\nrun();";
+    let (result, _) = run_program_on(Backend::Bytecode, source);
+    let error = result.expect_err("subtracting a string should fail");
+
+    assert_eq!(error.message, "Binary minus can only be applied to numbers");
+    assert_eq!(error.line(), Some(2));
+    let functions: Vec<_> = error
+        .trace
+        .iter()
+        .map(|frame| frame.function.as_str())
+        .collect();
+    assert_eq!(functions, ["<fn step>", "<fn run>"]);
+}
+
+#[test]
+fn test_natives_are_shared_with_the_tree_walker() {
+    let interpreter = run_ok_on(Backend::Bytecode, "var size = len([1, 2, 3]);");
+    assert_eq!(global(&interpreter, "size"), Object::Number(3.0));
+}
+
+#[test]
+fn test_compile_limits_are_compile_errors() {
+    let locals: String = (0..300).map(|i| format!("var v{} = {};\n", i, i)).collect();
+    let path = std::env::temp_dir().join(format!("lox_limits_{}.lox", std::process::id()));
+    std::fs::write(&path, format!("fun f() {{\n{}}}\n", locals)).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_lox"))
+        .args(["--backend=vm", "--color=never"])
+        .arg(&path)
+        .output()
+        .expect("lox should run");
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(output.status.code(), Some(65));
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("Too many local variables in function."),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,5 @@
+mod chunks;
+mod disasm;
+mod loxc;
+mod machine;
+pub mod parity;
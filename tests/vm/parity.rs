@@ -0,0 +1,87 @@
+use lox::compiler::interpreter::Backend;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// The backend the suite's shared helpers run programs on: the one LOX_BACKEND names
+/// when the suite is rerun below, otherwise the tree-walker.
+pub fn suite_backend() -> Backend {
+    env::var("LOX_BACKEND")
+        .ok()
+        .and_then(|name| Backend::from_name(&name))
+        .unwrap_or(Backend::TreeWalk)
+}
+
+// Runs every other test in this binary again with LOX_BACKEND=vm, so everything run
+// through the shared helpers keeps passing on both backends. The child skips this
+// module, which stops it from recursing.
+#[test]
+fn test_suite_passes_on_the_vm() {
+    if env::var("LOX_BACKEND").is_ok() {
+        return;
+    }
+    let output = Command::new(env::current_exe().unwrap())
+        .args(["--skip", "vm::"])
+        .env("LOX_BACKEND", "vm")
+        .output()
+        .expect("test binary should rerun");
+
+    assert!(
+        output.status.success(),
+        "suite failed on the VM:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+fn samples(dir: &Path, found: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            samples(&path, found);
+        } else if path.extension().is_some_and(|ext| ext == "lox") {
+            // clock() output can't match between two runs
+            if !fs::read_to_string(&path).unwrap().contains("clock()") {
+                found.push(path);
+            }
+        }
+    }
+}
+
+fn run_sample(path: &Path, backend: &str) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_lox"))
+        .arg(format!("--backend={}", backend))
+        .arg("--color=never")
+        .arg(path)
+        .env_remove("LOX_BACKEND")
+        .output()
+        .expect("lox should run")
+}
+
+#[test]
+fn test_samples_behave_the_same_on_both_backends() {
+    let mut paths = Vec::new();
+    samples(
+        Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/lox_samples")),
+        &mut paths,
+    );
+    assert!(!paths.is_empty());
+
+    for path in paths {
+        let tree = run_sample(&path, "tree");
+        let vm = run_sample(&path, "vm");
+        assert_eq!(tree.status.code(), vm.status.code(), "{}", path.display());
+        assert_eq!(
+            String::from_utf8_lossy(&tree.stdout),
+            String::from_utf8_lossy(&vm.stdout),
+            "{}",
+            path.display()
+        );
+        assert_eq!(
+            String::from_utf8_lossy(&tree.stderr),
+            String::from_utf8_lossy(&vm.stderr),
+            "{}",
+            path.display()
+        );
+    }
+}